@@ -0,0 +1,90 @@
+//! Measures the cost of locating the metadata atoms of the files in `files/`, or the directory
+//! passed as the first argument, when reading and writing a tag.
+//!
+//! The number of `read` and `seek` calls made while reading is counted by wrapping the file, each
+//! of which corresponds to a syscall since the file is read unbuffered. Writing locates the atoms
+//! through a buffered reader, so only the time is measured.
+//!
+//! ```sh
+//! cargo bench --bench find -- [corpus dir]
+//! ```
+
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use mp4ameta::Tag;
+
+const ITERATIONS: u32 = 100;
+
+struct CountingReader<R> {
+    inner: R,
+    reads: u64,
+    seeks: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reads += 1;
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.seeks += 1;
+        self.inner.seek(pos)
+    }
+}
+
+fn corpus(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .expect("error reading corpus directory")
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| Tag::read_from_path(p).is_ok())
+        .collect();
+    files.sort();
+    files
+}
+
+fn main() {
+    let dir = std::env::args().nth(1).filter(|a| a != "--bench").unwrap_or("files".to_owned());
+    let files = corpus(Path::new(&dir));
+    let tmp = std::env::temp_dir().join("mp4ameta_bench_find");
+
+    println!("{:<32} {:>8} {:>8} {:>12} {:>12}", "file", "reads", "seeks", "read", "write");
+    for path in files.iter() {
+        let mut reader = CountingReader { inner: File::open(path).unwrap(), reads: 0, seeks: 0 };
+        let tag = Tag::read_from(&mut reader).unwrap();
+
+        let mut read_time = Duration::default();
+        for _ in 0..ITERATIONS {
+            let mut file = File::open(path).unwrap();
+            let start = Instant::now();
+            Tag::read_from(&mut file).unwrap();
+            read_time += start.elapsed();
+        }
+
+        let mut write_time = Duration::default();
+        for _ in 0..ITERATIONS {
+            fs::copy(path, &tmp).unwrap();
+            let file = fs::OpenOptions::new().read(true).write(true).open(&tmp).unwrap();
+            let start = Instant::now();
+            tag.write_to(&file).unwrap();
+            write_time += start.elapsed();
+        }
+
+        let name = path.file_name().unwrap().to_string_lossy();
+        println!(
+            "{:<32} {:>8} {:>8} {:>12.2?} {:>12.2?}",
+            name,
+            reader.reads,
+            reader.seeks,
+            read_time / ITERATIONS,
+            write_time / ITERATIONS,
+        );
+    }
+
+    let _ = fs::remove_file(tmp);
+}
@@ -186,11 +186,7 @@ pub fn flag_value_accessor(input: TokenStream) -> TokenStream {
 impl Tag {{
     /// Returns the {n} flag (`{ais}`).
     pub fn {vi}(&self) -> bool {{
-        let vec = match self.bytes_of(&{ai}).next() {{
-            Some(v) => v,
-            None => return false,
-        }};
-        vec.get(0).map(|&v| v == 1).unwrap_or(false)
+        self.flag_of(&{ai})
     }}
 
     /// Sets the {n} flag to true (`{ais}`).
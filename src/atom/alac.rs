@@ -0,0 +1,102 @@
+//! alac atom
+//!
+//! ```md
+//! 4 bytes ?
+//! 2 bytes ?
+//! 2 bytes data reference index
+//! 2 bytes version
+//! 6 bytes ?
+//! 2 bytes channel count
+//! 2 bytes sample size
+//! 4 bytes ?
+//! 4 bytes sample rate
+//! 16 bytes ? (version 1)
+//! 36 bytes ? (version 2)
+//! │
+//! └─ alac atom (magic cookie)
+//!    4 bytes len
+//!    4 bytes ident
+//!    1 byte version
+//!    3 bytes flags
+//!    4 bytes frame length
+//!    1 byte compatible version
+//!    1 byte bit depth
+//!    1 byte rice history mult
+//!    1 byte rice initial history
+//!    1 byte rice parameter limit
+//!    1 byte channel count
+//!    2 bytes max run
+//!    4 bytes max frame bytes
+//!    4 bytes average bitrate
+//!    4 bytes sample rate
+//! ```
+
+use super::*;
+use crate::{ChannelConfig, SampleRate};
+
+/// A struct representing an Apple Lossless Audio Codec sample entry (`alac`). The fields are read
+/// from the magic cookie, which may differ from the generic fields of the sample entry.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Alac {
+    pub channel_config: Option<ChannelConfig>,
+    pub sample_rate: Option<SampleRate>,
+    pub avg_bitrate: Option<u32>,
+    pub bit_depth: Option<u8>,
+}
+
+impl Atom for Alac {
+    const FOURCC: Fourcc = APPLE_LOSSLESS_AUDIO;
+}
+
+impl ParseAtom for Alac {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
+        let bounds = find_bounds(reader, size)?;
+        let mut alac = Self::default();
+
+        // QuickTime sound sample descriptions of version 1 and 2 contain additional fields
+        reader.seek(SeekFrom::Current(8))?;
+        let desc_len = match reader.read_u16()? {
+            0 => 28,
+            1 => 28 + 16,
+            2 => 28 + 36,
+            v => {
+                ctx.warn(|| format!("Unknown alac sample entry version {}", v));
+                seek_to_end(reader, &bounds)?;
+                return Ok(alac);
+            }
+        };
+        reader.seek(SeekFrom::Current(desc_len - 10))?;
+
+        // the audio information is optional, so an invalid magic cookie is skipped with a warning
+        let remaining = size.content_len().saturating_sub(desc_len as u64);
+        let head = match remaining {
+            0..=7 => None,
+            _ => parse_head(reader, remaining).ok(),
+        };
+        match head {
+            Some(h) if h.fourcc() == APPLE_LOSSLESS_AUDIO && h.content_len() >= 28 => {
+                let (version, _) = parse_full_head(reader)?;
+                if version == 0 {
+                    reader.seek(SeekFrom::Current(5))?;
+                    alac.bit_depth = Some(reader.read_u8()?);
+                    reader.seek(SeekFrom::Current(3))?;
+                    alac.channel_config = ChannelConfig::from_channel_count(reader.read_u8()?);
+                    reader.seek(SeekFrom::Current(6))?;
+                    alac.avg_bitrate = Some(reader.read_u32()?).filter(|b| *b != 0);
+                    alac.sample_rate = SampleRate::from_hz(reader.read_u32()?);
+                } else {
+                    ctx.warn(|| format!("Unknown alac magic cookie version {}", version));
+                }
+            }
+            _ => ctx.warn(|| "Missing or invalid alac magic cookie".to_owned()),
+        }
+
+        seek_to_end(reader, &bounds)?;
+
+        Ok(alac)
+    }
+}
@@ -0,0 +1,93 @@
+use super::*;
+
+/// 3GPP asset information atoms containing a string, and the metadata atoms they correspond to.
+const STRING_ASSETS: [(Fourcc, Fourcc); 7] = [
+    (Fourcc(*b"titl"), TITLE),
+    (Fourcc(*b"auth"), ARTIST),
+    (Fourcc(*b"perf"), ARTIST),
+    (Fourcc(*b"albm"), ALBUM),
+    (Fourcc(*b"dscp"), DESCRIPTION),
+    (Fourcc(*b"cprt"), COPYRIGHT),
+    (Fourcc(*b"gnre"), CUSTOM_GENRE),
+];
+
+/// (`yrrc`) Identifier of the 3GPP asset information atom containing the recording year.
+const RECORDING_YEAR: Fourcc = Fourcc(*b"yrrc");
+
+/// A struct representing a 3GPP asset information atom stored inside the user data atom (`udta`),
+/// like the title (`titl`) or author (`auth`), which some QuickTime exports and phone recordings
+/// contain instead of an item list. Apart from the recording year (`yrrc`), which is a 16 bit
+/// integer, they contain a packed ISO 639-2/T language code followed by a null terminated utf-8
+/// or utf-16 string.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Asset {
+    /// The identifier of the metadata atom corresponding to the asset.
+    pub ident: Fourcc,
+    /// The packed language code, or 0 if the asset has none.
+    pub language: u16,
+    pub value: String,
+}
+
+impl Asset {
+    /// Returns the identifier of the metadata atom corresponding to the asset, if the fourcc is
+    /// the one of a 3GPP asset information atom.
+    pub fn ident_of(fourcc: Fourcc) -> Option<Fourcc> {
+        match fourcc {
+            RECORDING_YEAR => Some(YEAR),
+            f => STRING_ASSETS.iter().find(|(a, _)| *a == f).map(|(_, i)| *i),
+        }
+    }
+
+    /// Attempts to parse the asset information atom. Returns `None` if its content isn't valid,
+    /// so it can be skipped, since the asset information isn't essential.
+    pub fn parse(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        head: Head,
+    ) -> crate::Result<Option<Self>> {
+        ctx.record_atom();
+        let ident = match Self::ident_of(head.fourcc()) {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+        let content = reader.read_u8_vec(head.content_len())?;
+
+        // the content starts with the version and flags
+        let asset = match content.get(4..) {
+            Some([c0, c1, rest @ ..]) => {
+                let code = u16::from_be_bytes([*c0, *c1]);
+                match head.fourcc() {
+                    RECORDING_YEAR => Some(Self { ident, language: 0, value: code.to_string() }),
+                    _ => {
+                        let value = decode_string(rest).filter(|s| !s.is_empty());
+                        value.map(|value| Self { ident, language: code & 0x7FFF, value })
+                    }
+                }
+            }
+            _ => None,
+        };
+        if asset.is_none() {
+            ctx.warn(|| format!("Invalid content of 3GPP asset '{}'", head.fourcc()));
+        }
+        Ok(asset)
+    }
+}
+
+/// Decodes a null terminated string, which is utf-16 if it starts with a byte order mark and
+/// utf-8 otherwise. Trailing bytes, like the track number of an album (`albm`), are ignored.
+fn decode_string(bytes: &[u8]) -> Option<String> {
+    match bytes {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .take_while(|u| *u != 0)
+                .collect();
+            String::from_utf16(&units).ok()
+        }
+        _ => {
+            let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            String::from_utf8(bytes[..end].to_vec()).ok()
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Read, Seek};
 
 use super::*;
 
@@ -14,7 +14,11 @@ impl Atom for Co64 {
 }
 
 impl ParseAtom for Co64 {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        _ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         let (version, _) = parse_full_head(reader)?;
 
         match version {
@@ -27,7 +31,7 @@ impl ParseAtom for Co64 {
                     ));
                 }
 
-                let table_pos = reader.seek(SeekFrom::Current(0))?;
+                let table_pos = reader.stream_position()?;
                 let mut offsets = Vec::with_capacity(entries as usize);
                 for _ in 0..entries {
                     let offset = reader.read_u64()?;
@@ -60,8 +64,7 @@ impl FindAtom for Co64 {
     type Bounds = Co64Bounds;
 
     fn find_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self::Bounds> {
-        let bounds = find_bounds(reader, size)?;
-        seek_to_end(reader, &bounds)?;
+        let bounds = skip_bounds(reader, size)?;
         Ok(Self::Bounds { bounds })
     }
 }
@@ -1,5 +1,6 @@
 use std::fmt;
 use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
 
 use crate::{Img, ImgBuf, ImgFmt, ImgMut, ImgRef};
 
@@ -7,9 +8,9 @@ use crate::{Img, ImgBuf, ImgFmt, ImgMut, ImgRef};
 /// Reserved for use where no type needs to be indicated.
 const RESERVED: u32 = 0;
 /// UTF-8 without any count or NULL terminator.
-const UTF8: u32 = 1;
+pub(crate) const UTF8: u32 = 1;
 /// UTF-16 also known as UTF-16BE.
-const UTF16: u32 = 2;
+pub(crate) const UTF16: u32 = 2;
 /// UTF-8 variant storage of a string for sorting only.
 #[allow(unused)]
 const UTF8_SORT: u32 = 4;
@@ -26,10 +27,8 @@ const BE_SIGNED: u32 = 21;
 #[allow(unused)]
 const BE_UNSIGNED: u32 = 22;
 /// A big-endian 32-bit floating point value (`IEEE754`).
-#[allow(unused)]
 const BE_F32: u32 = 23;
 /// A big-endian 64-bit floating point value (`IEEE754`).
-#[allow(unused)]
 const BE_F64: u32 = 24;
 /// Windows bitmap format graphics.
 #[allow(unused)]
@@ -85,7 +84,10 @@ const AFFINE_TRANSFORM_F64: u32 = 79;
 
 /// An enum that holds different types of data defined by
 /// [Table 3-5 Well-known data types](https://developer.apple.com/library/archive/documentation/QuickTime/QTFF/Metadata/Metadata.html#//apple_ref/doc/uid/TP40000939-CH1-SW34).
-#[derive(Clone, Eq, PartialEq)]
+///
+/// Images are compared by their format and data, regardless of whether they are shared or not.
+/// Floating point values are compared by their bit representation.
+#[derive(Clone)]
 pub enum Data {
     /// A value containing reserved type data inside a `Vec<u8>`.
     Reserved(Vec<u8>),
@@ -101,8 +103,46 @@ pub enum Data {
     BeSigned(Vec<u8>),
     /// A value containing bmp byte data inside a `Vec<u8>`.
     Bmp(Vec<u8>),
+    /// A value containing an image inside an `Arc<ImgBuf>`, which can be shared between multiple
+    /// tags. The image is only copied if it is mutated while being shared.
+    SharedImage(Arc<ImgBuf>),
+    /// A value containing a big endian 32 bit floating point number.
+    BeFloat32(f32),
+    /// A value containing a big endian 64 bit floating point number.
+    BeFloat64(f64),
+    /// A value of a datatype that isn't interpreted, containing the datatype code and the raw
+    /// bytes, which are written back with the original code.
+    Unknown {
+        /// The datatype code.
+        datatype: u32,
+        /// The raw bytes.
+        data: Vec<u8>,
+    },
+}
+
+impl PartialEq for Data {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Reserved(a), Self::Reserved(b)) => a == b,
+            (Self::Utf8(a), Self::Utf8(b)) => a == b,
+            (Self::Utf16(a), Self::Utf16(b)) => a == b,
+            (Self::BeSigned(a), Self::BeSigned(b)) => a == b,
+            (Self::BeFloat32(a), Self::BeFloat32(b)) => a.to_bits() == b.to_bits(),
+            (Self::BeFloat64(a), Self::BeFloat64(b)) => a.to_bits() == b.to_bits(),
+            (
+                Self::Unknown { datatype: t1, data: b1 },
+                Self::Unknown { datatype: t2, data: b2 },
+            ) => t1 == t2 && b1 == b2,
+            _ => match (self.image(), other.image()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+        }
+    }
 }
 
+impl Eq for Data {}
+
 impl fmt::Debug for Data {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -113,6 +153,12 @@ impl fmt::Debug for Data {
             Self::Png(_) => write!(f, "Data::Png"),
             Self::BeSigned(d) => write!(f, "Data::BeSigned({:?})", d),
             Self::Bmp(_) => write!(f, "Data::Bmp"),
+            Self::SharedImage(i) => write!(f, "Data::SharedImage({:?})", i.fmt),
+            Self::BeFloat32(d) => write!(f, "Data::BeFloat32({:?})", d),
+            Self::BeFloat64(d) => write!(f, "Data::BeFloat64({:?})", d),
+            Self::Unknown { datatype, data } => {
+                write!(f, "Data::Unknown {{ datatype: {}, data: {:?} }}", datatype, data)
+            }
         }
     }
 }
@@ -133,11 +179,15 @@ impl Data {
         (match self {
             Self::Reserved(v) => v.len(),
             Self::Utf8(s) => s.len(),
-            Self::Utf16(s) => s.encode_utf16().count(),
+            Self::Utf16(s) => 2 * s.encode_utf16().count(),
             Self::Jpeg(v) => v.len(),
             Self::Png(v) => v.len(),
             Self::BeSigned(v) => v.len(),
             Self::Bmp(v) => v.len(),
+            Self::SharedImage(i) => i.data.len(),
+            Self::BeFloat32(_) => 4,
+            Self::BeFloat64(_) => 8,
+            Self::Unknown { data, .. } => data.len(),
         }) as u64
     }
 
@@ -156,10 +206,10 @@ impl Data {
         matches!(self, Self::Utf8(_) | Self::Utf16(_))
     }
 
-    /// Returns true if `self` is of type [`Self::Jpeg`], [`Self::Png`] or [`Self::Bmp`] false
-    /// otherwise.
+    /// Returns true if `self` is of type [`Self::Jpeg`], [`Self::Png`], [`Self::Bmp`] or
+    /// [`Self::SharedImage`] false otherwise.
     pub const fn is_image(&self) -> bool {
-        matches!(self, Self::Jpeg(_) | Self::Png(_) | Self::Bmp(_))
+        matches!(self, Self::Jpeg(_) | Self::Png(_) | Self::Bmp(_) | Self::SharedImage(_))
     }
 
     /// Returns true if `self` is of type [`Self::Reserved`] false otherwise.
@@ -197,6 +247,22 @@ impl Data {
         matches!(self, Self::Bmp(_))
     }
 
+    /// Returns true if `self` is of type [`Self::SharedImage`] false otherwise.
+    pub const fn is_shared_image(&self) -> bool {
+        matches!(self, Self::SharedImage(_))
+    }
+
+    /// Returns true if `self` is of type [`Self::BeFloat32`] or [`Self::BeFloat64`] false
+    /// otherwise.
+    pub const fn is_float(&self) -> bool {
+        matches!(self, Self::BeFloat32(_) | Self::BeFloat64(_))
+    }
+
+    /// Returns true if `self` is of type [`Self::Unknown`] false otherwise.
+    pub const fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown { .. })
+    }
+
     /// Returns a reference to byte data if `self` is of type [`Self::Reserved`] or
     /// [`Self::BeSigned`].
     pub fn bytes(&self) -> Option<&[u8]> {
@@ -255,57 +321,126 @@ impl Data {
         }
     }
 
-    /// Returns a reference to an image if `self` is of type [`Self::Jpeg`], [`Self::Png`] or
-    /// [`Self::Bmp`].
+    /// Returns a reference to an image if `self` is of type [`Self::Jpeg`], [`Self::Png`],
+    /// [`Self::Bmp`] or [`Self::SharedImage`].
     pub fn image(&self) -> Option<ImgRef> {
         match self {
             Self::Jpeg(v) => Some(Img::new(ImgFmt::Jpeg, v)),
             Self::Png(v) => Some(Img::new(ImgFmt::Png, v)),
             Self::Bmp(v) => Some(Img::new(ImgFmt::Bmp, v)),
+            Self::SharedImage(i) => Some(Img::new(i.fmt.clone(), &i.data)),
             _ => None,
         }
     }
 
-    /// Returns a mutable reference to an image if `self` is of type [`Self::Jpeg`], [`Self::Png`]
-    /// or [`Self::Bmp`].
+    /// Returns a mutable reference to an image if `self` is of type [`Self::Jpeg`], [`Self::Png`],
+    /// [`Self::Bmp`] or [`Self::SharedImage`]. A shared image is copied if it is still shared.
     pub fn image_mut(&mut self) -> Option<ImgMut> {
         match self {
             Self::Jpeg(v) => Some(Img::new(ImgFmt::Jpeg, v)),
             Self::Png(v) => Some(Img::new(ImgFmt::Png, v)),
             Self::Bmp(v) => Some(Img::new(ImgFmt::Bmp, v)),
+            Self::SharedImage(i) => {
+                let i = Arc::make_mut(i);
+                Some(Img::new(i.fmt.clone(), &mut i.data))
+            }
             _ => None,
         }
     }
 
-    /// Consumes `self` and returns an image if `self` is of type [`Self::Jpeg`], [`Self::Png`] or
-    /// [`Self::Bmp`].
+    /// Consumes `self` and returns an image if `self` is of type [`Self::Jpeg`], [`Self::Png`],
+    /// [`Self::Bmp`] or [`Self::SharedImage`]. A shared image is copied if it is still shared.
     pub fn into_image(self) -> Option<ImgBuf> {
         match self {
             Self::Jpeg(v) => Some(Img::new(ImgFmt::Jpeg, v)),
             Self::Png(v) => Some(Img::new(ImgFmt::Png, v)),
             Self::Bmp(v) => Some(Img::new(ImgFmt::Bmp, v)),
+            Self::SharedImage(i) => Some(Arc::try_unwrap(i).unwrap_or_else(|i| (*i).clone())),
             _ => None,
         }
     }
 
-    /// Returns a reference to image data if `self` is of type [`Self::Jpeg`], [`Self::Png`] or
-    /// [`Self::Bmp`].
+    /// Returns a reference to image data if `self` is of type [`Self::Jpeg`], [`Self::Png`],
+    /// [`Self::Bmp`] or [`Self::SharedImage`].
     pub fn image_data(&self) -> Option<&[u8]> {
         self.image().map(|i| i.data)
     }
 
-    /// Returns a mutable reference to image data if `self` is of type [`Self::Jpeg`], [`Self::Png`]
-    /// or [`Self::Bmp`].
+    /// Returns a mutable reference to image data if `self` is of type [`Self::Jpeg`], [`Self::Png`],
+    /// [`Self::Bmp`] or [`Self::SharedImage`]. A shared image is copied if it is still shared.
     pub fn image_data_mut(&mut self) -> Option<&mut Vec<u8>> {
         self.image_mut().map(|i| i.data)
     }
 
-    /// Consumes `self` and returns image data if `self` is of type [`Self::Jpeg`], [`Self::Png`]
-    /// or [`Self::Bmp`].
+    /// Consumes `self` and returns image data if `self` is of type [`Self::Jpeg`], [`Self::Png`],
+    /// [`Self::Bmp`] or [`Self::SharedImage`]. A shared image is copied if it is still shared.
     pub fn into_image_data(self) -> Option<Vec<u8>> {
         self.into_image().map(|i| i.data)
     }
 
+    /// Converts image data into a [`Self::SharedImage`], so clones of `self` share the same image.
+    /// Other data is left unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Data, Img};
+    ///
+    /// let mut data = Data::Jpeg(b"image".to_vec());
+    /// data.share_image();
+    /// assert!(data.is_shared_image());
+    /// assert_eq!(data, Data::Jpeg(b"image".to_vec()));
+    /// ```
+    pub fn share_image(&mut self) {
+        if let Self::Jpeg(_) | Self::Png(_) | Self::Bmp(_) = self {
+            let image = std::mem::replace(self, Self::Reserved(Vec::new())).into_image();
+            if let Some(i) = image {
+                *self = Self::SharedImage(Arc::new(i));
+            }
+        }
+    }
+
+    /// Returns whether the content of `self` and `other` is equal. Unlike `==`, image, reserved
+    /// and unknown data is compared by its bytes only, regardless of the type, so images with a
+    /// wrong format label compare equal to the correctly labeled image. All other data, including
+    /// [`Self::BeSigned`] integers, is compared using `==`.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Data;
+    ///
+    /// let jpeg = Data::Jpeg(b"\xff\xd8\xffimage".to_vec());
+    /// let png = Data::Png(b"\xff\xd8\xffimage".to_vec());
+    /// assert_ne!(jpeg, png);
+    /// assert!(jpeg.content_eq(&png));
+    /// assert!(jpeg.content_eq(&Data::Reserved(b"\xff\xd8\xffimage".to_vec())));
+    /// assert!(!jpeg.content_eq(&Data::Utf8("\u{ff}image".to_owned())));
+    /// assert!(!Data::BeSigned(vec![1]).content_eq(&Data::Reserved(vec![1])));
+    /// ```
+    pub fn content_eq(&self, other: &Self) -> bool {
+        match (self.raw_bytes(), other.raw_bytes()) {
+            (Some(a), Some(b)) => a == b,
+            _ => self == other,
+        }
+    }
+
+    /// Returns the bytes of image, reserved and unknown data.
+    fn raw_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Unknown { data, .. } => Some(data),
+            _ => self.reserved().or_else(|| self.image_data()),
+        }
+    }
+
+    /// Returns a floating point number if `self` is of type [`Self::BeFloat32`] or
+    /// [`Self::BeFloat64`].
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::BeFloat32(f) => Some(*f as f64),
+            Self::BeFloat64(f) => Some(*f),
+            _ => None,
+        }
+    }
+
     /// Returns a reference to byte data if `self` is of type [`Self::Reserved`].
     pub fn reserved(&self) -> Option<&[u8]> {
         match self {
@@ -330,18 +465,22 @@ impl Data {
         }
     }
 
-    /// Returns a reference to image data if `self` is of type [`Self::Jpeg`].
+    /// Returns a reference to image data if `self` is of type [`Self::Jpeg`], or a
+    /// [`Self::SharedImage`] in the jpeg format.
     pub fn jpeg(&self) -> Option<&[u8]> {
         match self {
             Self::Jpeg(v) => Some(v),
+            Self::SharedImage(i) if i.fmt == ImgFmt::Jpeg => Some(&i.data),
             _ => None,
         }
     }
 
-    /// Returns a reference to image data if `self` is of type [`Self::Png`].
+    /// Returns a reference to image data if `self` is of type [`Self::Png`], or a
+    /// [`Self::SharedImage`] in the png format.
     pub fn png(&self) -> Option<&[u8]> {
         match self {
             Self::Png(v) => Some(v),
+            Self::SharedImage(i) if i.fmt == ImgFmt::Png => Some(&i.data),
             _ => None,
         }
     }
@@ -354,16 +493,22 @@ impl Data {
         }
     }
 
-    /// Returns a reference to byte data if `self` is of type [`Self::Bmp`].
+    /// Returns a reference to image data if `self` is of type [`Self::Bmp`], or a
+    /// [`Self::SharedImage`] in the bmp format.
     pub fn bmp(&self) -> Option<&[u8]> {
         match self {
             Self::Bmp(v) => Some(v),
+            Self::SharedImage(i) if i.fmt == ImgFmt::Bmp => Some(&i.data),
             _ => None,
         }
     }
 
-    /// Attempts to write type header followed by the data to the writer.
-    pub(crate) fn write_typed(&self, writer: &mut impl Write) -> crate::Result<()> {
+    /// Attempts to write type header and the locale indicator followed by the data to the writer.
+    pub(crate) fn write_typed(
+        &self,
+        writer: &mut impl Write,
+        locale: [u8; 4],
+    ) -> crate::Result<()> {
         let datatype = match self {
             Self::Reserved(_) => RESERVED,
             Self::Utf8(_) => UTF8,
@@ -372,11 +517,18 @@ impl Data {
             Self::Png(_) => PNG,
             Self::BeSigned(_) => BE_SIGNED,
             Self::Bmp(_) => BMP,
+            Self::SharedImage(i) => match i.fmt {
+                ImgFmt::Bmp => BMP,
+                ImgFmt::Jpeg => JPEG,
+                ImgFmt::Png => PNG,
+            },
+            Self::BeFloat32(_) => BE_F32,
+            Self::BeFloat64(_) => BE_F64,
+            Self::Unknown { datatype, .. } => *datatype,
         };
 
         writer.write_all(&datatype.to_be_bytes())?;
-        // Writing 4 byte locale indicator
-        writer.write_all(&[0u8; 4])?;
+        writer.write_all(&locale)?;
 
         self.write_raw(writer)?;
 
@@ -409,12 +561,26 @@ impl Data {
             Self::Bmp(v) => {
                 writer.write_all(v)?;
             }
+            Self::SharedImage(i) => {
+                writer.write_all(&i.data)?;
+            }
+            Self::BeFloat32(f) => {
+                writer.write_all(&f.to_be_bytes())?;
+            }
+            Self::BeFloat64(f) => {
+                writer.write_all(&f.to_be_bytes())?;
+            }
+            Self::Unknown { data, .. } => {
+                writer.write_all(data)?;
+            }
         }
 
         Ok(())
     }
 
     /// Parses data based on [Table 3-5 Well-known data types](https://developer.apple.com/library/archive/documentation/QuickTime/QTFF/Metadata/Metadata.html#//apple_ref/doc/uid/TP40000939-CH1-SW34).
+    /// Data of datatypes that aren't interpreted, or floating point numbers of an unexpected
+    /// length, is stored as [`Self::Unknown`].
     pub(crate) fn parse(reader: &mut impl Read, datatype: u32, len: u64) -> crate::Result<Data> {
         Ok(match datatype {
             RESERVED => Data::Reserved(reader.read_u8_vec(len)?),
@@ -424,16 +590,45 @@ impl Data {
             PNG => Data::Png(reader.read_u8_vec(len)?),
             BE_SIGNED => Data::BeSigned(reader.read_u8_vec(len)?),
             BMP => Data::Bmp(reader.read_u8_vec(len)?),
-            _ => {
-                return Err(crate::Error::new(
-                    crate::ErrorKind::UnknownDataType(datatype),
-                    "Unknown datatype code".to_owned(),
-                ));
-            }
+            BE_F32 if len == 4 => Data::BeFloat32(f32::from_bits(reader.read_u32()?)),
+            BE_F64 if len == 8 => Data::BeFloat64(f64::from_bits(reader.read_u64()?)),
+            _ => Data::Unknown { datatype, data: reader.read_u8_vec(len)? },
         })
     }
 }
 
+/// Returns the image format of the datatype, if it is one.
+pub(crate) fn image_fmt(datatype: u32) -> Option<ImgFmt> {
+    match datatype {
+        JPEG => Some(ImgFmt::Jpeg),
+        PNG => Some(ImgFmt::Png),
+        BMP => Some(ImgFmt::Bmp),
+        _ => None,
+    }
+}
+
+/// Returns the image format of artwork data of the datatype. Since some taggers store artwork with
+/// the reserved datatype or an unknown one, the format is then sniffed from the signature at the
+/// start of the data. An image datatype is kept, so an explicitly set format survives a round
+/// trip. Data of the string, integer and floating point datatypes is never considered an image.
+pub(crate) fn artwork_fmt(datatype: u32, data: &[u8]) -> Option<ImgFmt> {
+    match datatype {
+        UTF8 | UTF16 | BE_SIGNED | BE_F32 | BE_F64 => None,
+        _ => image_fmt(datatype).or_else(|| ImgFmt::sniff(data)),
+    }
+}
+
+/// Parses artwork data of the datatype. Data that isn't an image, see [`artwork_fmt`], is parsed
+/// as usual, except for reserved data, which is stored as [`Data::Unknown`], since it most likely
+/// is an image of an unsupported format.
+pub(crate) fn parse_artwork(datatype: u32, data: Vec<u8>) -> crate::Result<Data> {
+    Ok(match artwork_fmt(datatype, &data) {
+        Some(fmt) => Data::from(Img::new(fmt, data)),
+        None if datatype == RESERVED => Data::Unknown { datatype, data },
+        None => Data::parse(&mut data.as_slice(), datatype, data.len() as u64)?,
+    })
+}
+
 pub trait ReadData: Read {
     /// Attempts to read an unsigned 8 bit integer from the reader.
     fn read_u8(&mut self) -> io::Result<u8> {
@@ -477,7 +672,8 @@ pub trait ReadData: Read {
         Ok(String::from_utf8(data)?)
     }
 
-    /// Attempts to read a utf-16 string from the reader.
+    /// Attempts to read a utf-16 string from the reader. A leading byte order mark (`0xFEFF`) is
+    /// stripped.
     fn read_utf16(&mut self, len: u64) -> crate::Result<String> {
         let mut buf = vec![0u8; len as usize];
 
@@ -485,13 +681,57 @@ pub trait ReadData: Read {
 
         let data: Vec<u16> =
             buf.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+        let data = data.strip_prefix(&[0xfeff]).unwrap_or(&data);
 
-        Ok(String::from_utf16(&data)?)
+        Ok(String::from_utf16(data)?)
     }
 }
 
 impl<T: Read> ReadData for T {}
 
+/// A writer that keeps track of the length of the region written to since its creation. Seeking is
+/// supported, so the written region might be overwritten without changing its length.
+pub struct CountingWriter<W> {
+    inner: W,
+    start: u64,
+    pos: u64,
+    len: u64,
+}
+
+impl<W: Write + Seek> CountingWriter<W> {
+    /// Creates a new counting writer starting at the current position of the inner writer.
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        let start = inner.stream_position()?;
+        Ok(Self { inner, start, pos: 0, len: 0 })
+    }
+
+    /// Returns the length of the region written to.
+    pub const fn count(&self) -> u64 {
+        self.len
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.pos += n as u64;
+        self.len = self.len.max(self.pos);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Seek> Seek for CountingWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = self.inner.seek(pos)?;
+        self.pos = new_pos.saturating_sub(self.start);
+        Ok(new_pos)
+    }
+}
+
 /// Attempts to read the remaining stream length and returns to the starting position.
 pub fn remaining_stream_len(reader: &mut impl Seek) -> io::Result<u64> {
     let current_pos = reader.seek(SeekFrom::Current(0))?;
@@ -560,4 +800,23 @@ mod test {
         assert_eq!(bytes[4], 2u8);
         assert_eq!(bytes[5], 12u8);
     }
+
+    #[test]
+    fn counting_writer() {
+        use std::io::{Cursor, Seek, SeekFrom, Write};
+
+        let mut cursor = Cursor::new(vec![0u8; 4]);
+        cursor.seek(SeekFrom::End(0)).unwrap();
+
+        let mut writer = super::CountingWriter::new(&mut cursor).unwrap();
+        writer.write_all(&[1, 2, 3, 4, 5, 6]).unwrap();
+        writer.seek(SeekFrom::Start(6)).unwrap();
+        writer.write_all(&[7, 7]).unwrap();
+        assert_eq!(writer.count(), 6);
+
+        writer.seek(SeekFrom::End(0)).unwrap();
+        writer.write_all(&[8]).unwrap();
+        assert_eq!(writer.count(), 7);
+        assert_eq!(cursor.into_inner(), vec![0, 0, 0, 0, 1, 2, 7, 7, 5, 6, 8]);
+    }
 }
@@ -0,0 +1,32 @@
+use super::*;
+
+/// A struct representing an extended language atom (`elng`), containing a BCP 47 language tag
+/// like `en-US`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Elng {
+    pub language: String,
+}
+
+impl Atom for Elng {
+    const FOURCC: Fourcc = EXTENDED_LANGUAGE;
+}
+
+impl ParseAtom for Elng {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        _ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
+        let bounds = find_bounds(reader, size)?;
+        parse_full_head(reader)?;
+
+        let mut language = reader.read_utf8(size.content_len().saturating_sub(4))?;
+        if let Some(i) = language.find('\0') {
+            language.truncate(i);
+        }
+
+        seek_to_end(reader, &bounds)?;
+
+        Ok(Self { language })
+    }
+}
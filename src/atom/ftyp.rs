@@ -1,3 +1,5 @@
+use std::io::ErrorKind::UnexpectedEof;
+
 use super::*;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -5,10 +7,19 @@ pub struct Ftyp(pub String);
 
 impl Ftyp {
     pub fn parse(reader: &mut (impl Read + Seek)) -> crate::Result<Self> {
-        let head = parse_head(reader)?;
-        if head.fourcc() != FILETYPE {
-            return Err(crate::Error::new(ErrorKind::NoTag, "No filetype atom found.".to_owned()));
-        }
+        let len = data::remaining_stream_len(reader)?;
+        let head = match parse_head(reader, len) {
+            Ok(h) if h.fourcc() == FILETYPE => h,
+            Err(e) if matches!(&e.kind, ErrorKind::Io(i) if i.kind() != UnexpectedEof) => {
+                return Err(e);
+            }
+            _ => {
+                return Err(crate::Error::new(
+                    ErrorKind::NotAnMp4,
+                    "No filetype atom found.".to_owned(),
+                ));
+            }
+        };
 
         let ftyp = reader.read_utf8(head.content_len())?;
 
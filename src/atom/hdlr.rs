@@ -22,13 +22,25 @@ impl Atom for Hdlr {
 }
 
 impl ParseAtom for Hdlr {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        _ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         Ok(Self(reader.read_u8_vec(size.content_len())?))
     }
 }
 
+impl Hdlr {
+    /// Returns the component subtype, which for a media handler is the media type.
+    pub fn subtype(&self) -> Option<Fourcc> {
+        let subtype = self.0.get(8..12)?;
+        Some(Fourcc([subtype[0], subtype[1], subtype[2], subtype[3]]))
+    }
+}
+
 impl WriteAtom for Hdlr {
-    fn write_atom(&self, writer: &mut impl Write) -> crate::Result<()> {
+    fn write_atom(&self, writer: &mut (impl Write + Seek)) -> crate::Result<()> {
         self.write_head(writer)?;
         writer.write_all(&self)?;
         Ok(())
@@ -55,8 +67,7 @@ impl FindAtom for Hdlr {
     type Bounds = HdlrBounds;
 
     fn find_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self::Bounds> {
-        let bounds = find_bounds(reader, size)?;
-        seek_to_end(reader, &bounds)?;
+        let bounds = skip_bounds(reader, size)?;
         Ok(Self::Bounds { bounds })
     }
 }
@@ -16,10 +16,26 @@ pub struct Size {
     len: u64,
 }
 
+#[cfg(test)]
+thread_local! {
+    /// The maximum length of an atom with a standard head, which unit tests can lower to write
+    /// extended heads without gigabytes of data.
+    pub static MAX_STANDARD_LEN: std::cell::Cell<u64> =
+        const { std::cell::Cell::new(u32::MAX as u64) };
+}
+
+/// Returns whether an atom of the length, including a standard head, requires an extended head.
+pub fn needs_ext(len: u64) -> bool {
+    #[cfg(test)]
+    return len > MAX_STANDARD_LEN.with(|l| l.get());
+    #[cfg(not(test))]
+    return len > u32::MAX as u64;
+}
+
 impl Size {
-    pub const fn from(content_len: u64) -> Self {
+    pub fn from(content_len: u64) -> Self {
         let mut len = content_len + 8;
-        let ext = len > u32::MAX as u64;
+        let ext = needs_ext(len);
         if ext {
             len += 8;
         }
@@ -91,14 +107,111 @@ impl Head {
 
 /// Attempts to parse the atom's head containing a 32 bit unsigned integer determining the size of
 /// the atom in bytes and the following 4 byte identifier from the reader. If the 32 len is set to
-/// 1 an extended 64 bit length is read.
+/// 1 an extended 64 bit length is read. If the 32 bit len is set to 0 the atom extends to the end
+/// of the file, or rather `max_len`, the remaining length of the parent atom or stream. If the
+/// length exceeds `max_len` an error of kind [`ErrorKind::Malformed`] is returned.
 ///
 /// ```md
 /// 4 bytes standard length
 /// 4 bytes identifier
 /// 8 bytes optional extended length
 /// ```
-pub fn parse_head(reader: &mut impl Read) -> crate::Result<Head> {
+pub fn parse_head(reader: &mut (impl Read + Seek), max_len: u64) -> crate::Result<Head> {
+    parse_bounded_head(reader, max_len, false)
+}
+
+/// Attempts to parse the head of a top level atom, see [`parse_head`]. If the length exceeds
+/// `remaining_len`, the remaining length of the stream, the file is most likely truncated, for
+/// example by an incomplete download, and an error of kind [`ErrorKind::TruncatedAtom`] is
+/// returned.
+pub fn parse_top_level_head(
+    reader: &mut (impl Read + Seek),
+    remaining_len: u64,
+) -> crate::Result<Head> {
+    parse_bounded_head(reader, remaining_len, true)
+}
+
+/// Attempts to parse the head of an atom like [`parse_head`], but a length exceeding `max_len` is
+/// clamped to it instead of returning an error. Returns the head and the length it declared.
+pub fn parse_clamped_head(
+    reader: &mut (impl Read + Seek),
+    max_len: u64,
+) -> crate::Result<(Head, u64)> {
+    let head = parse_unchecked_head(reader)?;
+    if head.len() > max_len && max_len >= head.head_len() {
+        return Ok((Head::new(head.ext(), max_len, head.fourcc()), head.len()));
+    }
+
+    let head = check_head(reader, head, max_len, false)?;
+    Ok((head, head.len()))
+}
+
+fn parse_bounded_head(
+    reader: &mut (impl Read + Seek),
+    max_len: u64,
+    top_level: bool,
+) -> crate::Result<Head> {
+    let head = parse_unchecked_head(reader)?;
+    check_head(reader, head, max_len, top_level)
+}
+
+fn check_head(
+    reader: &mut (impl Read + Seek),
+    mut head: Head,
+    max_len: u64,
+    top_level: bool,
+) -> crate::Result<Head> {
+    if head.len() == 0 {
+        if max_len < 8 {
+            let pos = reader.stream_position()? - head.head_len();
+            return Err(crate::Error::new(
+                crate::ErrorKind::Malformed(pos),
+                format!(
+                    "'{}' at offset {} extending to the end of its parent is less than 8 bytes: {}",
+                    head.fourcc(),
+                    pos,
+                    max_len,
+                ),
+            ));
+        }
+        head = Head::new(false, max_len, head.fourcc());
+    }
+
+    if head.len() > max_len && top_level {
+        return Err(crate::Error::new(
+            crate::ErrorKind::TruncatedAtom {
+                fourcc: head.fourcc(),
+                expected: head.len(),
+                available: max_len,
+            },
+            format!(
+                "Length of '{}' exceeds the remaining length of the file, which is most likely \
+                truncated: {} > {}",
+                head.fourcc(),
+                head.len(),
+                max_len,
+            ),
+        ));
+    }
+
+    if head.len() > max_len {
+        let pos = reader.stream_position()? - head.head_len();
+        return Err(crate::Error::new(
+            crate::ErrorKind::Malformed(pos),
+            format!(
+                "Length of '{}' at offset {} exceeds the remaining length of its parent: {} > {}",
+                head.fourcc(),
+                pos,
+                head.len(),
+                max_len,
+            ),
+        ));
+    }
+
+    Ok(head)
+}
+
+fn parse_unchecked_head(reader: &mut impl Read) -> crate::Result<Head> {
     let len = match reader.read_u32() {
         Ok(l) => l as u64,
         Err(e) => {
@@ -118,13 +231,17 @@ pub fn parse_head(reader: &mut impl Read) -> crate::Result<Head> {
 
     if len == 1 {
         match reader.read_u64() {
+            Ok(l) if l < 16 => Err(crate::Error::new(
+                crate::ErrorKind::Parsing,
+                format!("Read extended length of '{}' which is less than 16 bytes: {}", ident, l),
+            )),
             Ok(l) => Ok(Head::new(true, l, ident)),
             Err(e) => Err(crate::Error::new(
                 ErrorKind::Io(e),
                 "Error reading extended atom length".to_owned(),
             )),
         }
-    } else if len < 8 {
+    } else if len != 0 && len < 8 {
         Err(crate::Error::new(
             crate::ErrorKind::Parsing,
             format!("Read length of '{}' which is less than 8 bytes: {}", ident, len),
@@ -134,6 +251,27 @@ pub fn parse_head(reader: &mut impl Read) -> crate::Result<Head> {
     }
 }
 
+/// Checks that the content of the atom is at least `min_len` bytes long. Otherwise an error of kind
+/// [`ErrorKind::Malformed`] is returned. The reader is expected to be positioned right after the
+/// head.
+pub fn check_content_len(reader: &mut impl Seek, head: Head, min_len: u64) -> crate::Result<()> {
+    if head.content_len() < min_len {
+        let pos = reader.stream_position()? - head.head_len();
+        return Err(crate::Error::new(
+            crate::ErrorKind::Malformed(pos),
+            format!(
+                "Content length of '{}' at offset {} is less than {} bytes: {}",
+                head.fourcc(),
+                pos,
+                min_len,
+                head.content_len(),
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
 pub fn write_head(writer: &mut impl Write, head: Head) -> crate::Result<()> {
     if head.ext {
         writer.write_all(&u32::to_be_bytes(1))?;
@@ -195,6 +333,10 @@ impl Deref for AtomBounds {
 }
 
 impl AtomBounds {
+    pub const fn new(pos: u64, size: Size) -> Self {
+        Self { pos, size }
+    }
+
     pub const fn pos(&self) -> u64 {
         self.pos
     }
@@ -213,13 +355,18 @@ impl AtomBounds {
 }
 
 pub fn find_bounds(reader: &mut impl Seek, size: Size) -> crate::Result<AtomBounds> {
-    let pos = reader.seek(SeekFrom::Current(0))? - size.head_len();
+    let pos = reader.stream_position()? - size.head_len();
     Ok(AtomBounds { pos, size })
 }
 
+/// Skips the content of an atom whose head was just parsed and returns its bounds, using a single
+/// relative seek.
+pub fn skip_bounds(reader: &mut impl Seek, size: Size) -> crate::Result<AtomBounds> {
+    let end = reader.seek(SeekFrom::Current(size.content_len() as i64))?;
+    Ok(AtomBounds { pos: end - size.len(), size })
+}
+
 pub fn seek_to_end(reader: &mut impl Seek, bounds: &AtomBounds) -> crate::Result<()> {
-    let current = reader.seek(SeekFrom::Current(0))?;
-    let diff = bounds.end() - current;
-    reader.seek(SeekFrom::Current(diff as i64))?;
+    reader.seek(SeekFrom::Start(bounds.end()))?;
     Ok(())
 }
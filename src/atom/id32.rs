@@ -0,0 +1,49 @@
+use super::*;
+
+/// A struct representing an ID3v2 atom (`ID32`), which embeds a complete ID3v2 tag inside a
+/// metadata atom (`meta`) with an `ID32` handler. The tag is preceded by a packed ISO 639-2/T
+/// language code.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Id32 {
+    pub language: u16,
+    pub data: Vec<u8>,
+}
+
+impl Atom for Id32 {
+    const FOURCC: Fourcc = ID3V2;
+}
+
+impl ParseAtom for Id32 {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        _ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
+        if size.content_len() < 6 {
+            return Err(crate::Error::new(
+                crate::ErrorKind::Parsing,
+                "ID3v2 (ID32) atom is too short to contain a language".to_owned(),
+            ));
+        }
+
+        parse_full_head(reader)?;
+        let language = reader.read_u16()? & 0x7FFF;
+        let data = reader.read_u8_vec(size.content_len() - 6)?;
+
+        Ok(Self { language, data })
+    }
+}
+
+impl WriteAtom for Id32 {
+    fn write_atom(&self, writer: &mut (impl Write + Seek)) -> crate::Result<()> {
+        self.write_head(writer)?;
+        write_full_head(writer, 0, [0; 3])?;
+        writer.write_all(&self.language.to_be_bytes())?;
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+
+    fn size(&self) -> Size {
+        Size::from(6 + self.data.len() as u64)
+    }
+}
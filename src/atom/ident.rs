@@ -1,5 +1,6 @@
 use std::fmt;
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
 
 /// (`ftyp`) Identifier of an atom information about the filetype.
 pub(crate) const FILETYPE: Fourcc = Fourcc(*b"ftyp");
@@ -13,6 +14,10 @@ pub(crate) const MOVIE_HEADER: Fourcc = Fourcc(*b"mvhd");
 pub(crate) const TRACK: Fourcc = Fourcc(*b"trak");
 /// (`mdia`) Identifier of an atom containing information about a tracks media type and data.
 pub(crate) const MEDIA: Fourcc = Fourcc(*b"mdia");
+/// (`mdhd`) Identifier of an atom containing information about a tracks media, like its language.
+pub(crate) const MEDIA_HEADER: Fourcc = Fourcc(*b"mdhd");
+/// (`elng`) Identifier of an atom containing the extended language tag of a tracks media.
+pub(crate) const EXTENDED_LANGUAGE: Fourcc = Fourcc(*b"elng");
 /// (`minf`)
 pub(crate) const MEDIA_INFORMATION: Fourcc = Fourcc(*b"minf");
 /// (`stbl`)
@@ -23,8 +28,18 @@ pub(crate) const SAMPLE_TABLE_CHUNK_OFFSET: Fourcc = Fourcc(*b"stco");
 pub(crate) const SAMPLE_TABLE_CHUNK_OFFSET_64: Fourcc = Fourcc(*b"co64");
 /// (`stsd`)
 pub(crate) const SAMPLE_TABLE_SAMPLE_DESCRIPTION: Fourcc = Fourcc(*b"stsd");
+/// (`stsz`)
+pub(crate) const SAMPLE_TABLE_SAMPLE_SIZE: Fourcc = Fourcc(*b"stsz");
+/// (`stts`)
+pub(crate) const SAMPLE_TABLE_TIME_TO_SAMPLE: Fourcc = Fourcc(*b"stts");
 /// (`mp4a`)
 pub(crate) const MP4_AUDIO: Fourcc = Fourcc(*b"mp4a");
+/// (`alac`) Identifier of a sample entry of Apple Lossless Audio, and of its magic cookie.
+pub(crate) const APPLE_LOSSLESS_AUDIO: Fourcc = Fourcc(*b"alac");
+/// (`drms`) Identifier of a sample entry of FairPlay protected MPEG-4 audio.
+pub(crate) const DRM_AUDIO: Fourcc = Fourcc(*b"drms");
+/// (`enca`) Identifier of a sample entry of encrypted audio.
+pub(crate) const ENCRYPTED_AUDIO: Fourcc = Fourcc(*b"enca");
 /// (`esds`)
 pub(crate) const ELEMENTARY_STREAM_DESCRIPTION: Fourcc = Fourcc(*b"esds");
 /// (`udta`) Identifier of an atom containing user metadata.
@@ -33,8 +48,14 @@ pub(crate) const USER_DATA: Fourcc = Fourcc(*b"udta");
 pub(crate) const METADATA: Fourcc = Fourcc(*b"meta");
 /// (`hdlr`) Identifier of an atom specifying the handler component that should interpret the medias data.
 pub(crate) const HANDLER_REFERENCE: Fourcc = Fourcc(*b"hdlr");
+/// (`soun`) Identifier of the handler of sound media.
+pub(crate) const SOUND_HANDLER: Fourcc = Fourcc(*b"soun");
 /// (`ilst`) Identifier of an atom containing a list of metadata atoms.
 pub(crate) const ITEM_LIST: Fourcc = Fourcc(*b"ilst");
+/// (`keys`) Identifier of an atom containing the keys of QuickTime metadata items.
+pub(crate) const METADATA_KEYS: Fourcc = Fourcc(*b"keys");
+/// (`mdta`) Identifier of the QuickTime metadata handler and key namespace.
+pub(crate) const QUICKTIME_METADATA: Fourcc = Fourcc(*b"mdta");
 /// (`data`) Identifier of an atom containing typed data.
 pub(crate) const DATA: Fourcc = Fourcc(*b"data");
 /// (`mean`)
@@ -43,6 +64,12 @@ pub(crate) const MEAN: Fourcc = Fourcc(*b"mean");
 pub(crate) const NAME: Fourcc = Fourcc(*b"name");
 /// (`free`)
 pub(crate) const FREE: Fourcc = Fourcc(*b"free");
+/// (`skip`)
+pub(crate) const SKIP: Fourcc = Fourcc(*b"skip");
+/// (`uuid`) Identifier of an atom containing user extension data identified by a uuid.
+pub(crate) const USER_EXTENSION: Fourcc = Fourcc(*b"uuid");
+/// (`ID32`) Identifier of an atom containing an ID3v2 tag, and of the handler of its metadata atom.
+pub(crate) const ID3V2: Fourcc = Fourcc(*b"ID32");
 
 /// (`----`)
 pub const FREEFORM: Fourcc = Fourcc(*b"----");
@@ -78,6 +105,8 @@ pub const ENCODER: Fourcc = Fourcc(*b"\xa9too");
 pub const STANDARD_GENRE: Fourcc = Fourcc(*b"gnre");
 /// (`©nam`)
 pub const TITLE: Fourcc = Fourcc(*b"\xa9nam");
+/// (`©st3`)
+pub const TRACK_SUBTITLE: Fourcc = Fourcc(*b"\xa9st3");
 /// (`trkn`)
 pub const TRACK_NUMBER: Fourcc = Fourcc(*b"trkn");
 /// (`©day`)
@@ -104,6 +133,8 @@ pub const PODCAST_URL: Fourcc = Fourcc(*b"purl");
 // iTunes 5.0
 /// (`desc`)
 pub const DESCRIPTION: Fourcc = Fourcc(*b"desc");
+/// (`ldes`)
+pub const LONG_DESCRIPTION: Fourcc = Fourcc(*b"ldes");
 /// (`©lyr`)
 pub const LYRICS: Fourcc = Fourcc(*b"\xa9lyr");
 
@@ -139,6 +170,18 @@ pub const WORK: Fourcc = Fourcc(*b"\xa9wrk");
 /// (`shwm`)
 pub const SHOW_MOVEMENT: Fourcc = Fourcc(*b"shwm");
 
+// QuickTime credits
+/// (`©dir`)
+pub const DIRECTOR: Fourcc = Fourcc(*b"\xa9dir");
+/// (`©prf`)
+pub const PERFORMER: Fourcc = Fourcc(*b"\xa9prf");
+/// (`©prd`)
+pub const PRODUCER: Fourcc = Fourcc(*b"\xa9prd");
+
+// QuickTime user data
+/// (`©xyz`)
+pub const LOCATION: Fourcc = Fourcc(*b"\xa9xyz");
+
 // Freeform
 /// Mean string of most freeform identifiers (`com.apple.iTunes`)
 pub const APPLE_ITUNES_MEAN: &str = "com.apple.iTunes";
@@ -154,15 +197,32 @@ pub trait Ident: PartialEq<DataIdent> {
     fn fourcc(&self) -> Option<Fourcc>;
     /// Returns a freeform identifier.
     fn freeform(&self) -> Option<FreeformIdent>;
+    /// Returns a QuickTime metadata key.
+    fn key(&self) -> Option<&str> {
+        None
+    }
 }
 
 // TODO: figure out how to implement PartialEq for Ident or require an implementation as a trait bound.
 /// Returns wheter the identifiers match.
 pub fn idents_match(a: &impl Ident, b: &impl Ident) -> bool {
-    a.fourcc() == b.fourcc() && a.freeform() == b.freeform()
+    a.fourcc() == b.fourcc() && a.freeform() == b.freeform() && a.key() == b.key()
 }
 
 /// A 4 byte atom identifier (four character code).
+///
+/// Identifiers are compared byte by byte, so spaces are significant and the `©` of identifiers
+/// like `©nam` has to be the `0xA9` byte, not its utf-8 encoding. Use the constants in the
+/// [`ident`](crate::ident) module or the [`FromStr`] implementation to construct identifiers.
+///
+/// # Example
+/// ```
+/// use mp4ameta::{ident, Fourcc};
+///
+/// assert_eq!(Fourcc(*b"\xa9ART"), ident::ARTIST);
+/// assert_ne!(Fourcc(*b"aART"), Fourcc(*b"aAR "));
+/// assert_ne!("©nam".as_bytes(), ident::TITLE.as_slice());
+/// ```
 #[derive(Clone, Copy, Default, Eq, PartialEq)]
 pub struct Fourcc(pub [u8; 4]);
 
@@ -184,7 +244,7 @@ impl PartialEq<DataIdent> for Fourcc {
     fn eq(&self, other: &DataIdent) -> bool {
         match other {
             DataIdent::Fourcc(f) => self == f,
-            DataIdent::Freeform { .. } => false,
+            _ => false,
         }
     }
 }
@@ -201,13 +261,92 @@ impl Ident for Fourcc {
 
 impl fmt::Debug for Fourcc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Fourcc({})", self.0.iter().map(|b| char::from(*b)).collect::<String>())
+        write!(f, "Fourcc({})", self)
     }
 }
 
+/// Formats the identifier writing printable ascii characters as is, the `0xA9` byte as `©` and all
+/// other bytes as escape sequences in the form of `\xNN`.
+///
+/// # Example
+/// ```
+/// use mp4ameta::Fourcc;
+///
+/// assert_eq!(Fourcc(*b"\xa9nam").to_string(), "©nam");
+/// assert_eq!(Fourcc(*b"M4A ").to_string(), "M4A ");
+/// assert_eq!(Fourcc(*b"\x00\x01ab").to_string(), "\\x00\\x01ab");
+/// ```
 impl fmt::Display for Fourcc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0.iter().map(|b| char::from(*b)).collect::<String>())
+        for b in self.0.iter() {
+            match b {
+                0xA9 => write!(f, "©")?,
+                b'\\' => write!(f, "\\x5C")?,
+                0x20..=0x7E => write!(f, "{}", char::from(*b))?,
+                _ => write!(f, "\\x{:02X}", b)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses an identifier from a string in the form produced by the [`Display`](fmt::Display)
+/// implementation. The `©` character is interpreted as the `0xA9` byte and escape sequences in the
+/// form of `\xNN` as the byte they represent.
+///
+/// # Example
+/// ```
+/// use mp4ameta::{ident, Fourcc};
+///
+/// assert_eq!("©nam".parse::<Fourcc>().unwrap(), ident::TITLE);
+/// assert_eq!("\\xA9nam".parse::<Fourcc>().unwrap(), ident::TITLE);
+/// assert_eq!("aART".parse::<Fourcc>().unwrap(), ident::ALBUM_ARTIST);
+/// assert_eq!("M4A ".parse::<Fourcc>().unwrap(), Fourcc(*b"M4A "));
+/// assert!("M4A".parse::<Fourcc>().is_err());
+/// assert!(" ©nam".parse::<Fourcc>().is_err());
+/// assert!("name".parse::<Fourcc>().is_ok());
+/// assert!("names".parse::<Fourcc>().is_err());
+/// ```
+impl FromStr for Fourcc {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bytes = Vec::with_capacity(4);
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            let b = match c {
+                '\\' => {
+                    let escape: String = chars.by_ref().take(3).collect();
+                    let hex = escape.strip_prefix('x').filter(|h| h.len() == 2);
+                    match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                        Some(b) => b,
+                        None => {
+                            return Err(crate::Error::new(
+                                crate::ErrorKind::Parsing,
+                                format!("Invalid escape sequence in identifier: {}", s),
+                            ))
+                        }
+                    }
+                }
+                _ if (c as u32) <= 0xFF => c as u8,
+                _ => {
+                    return Err(crate::Error::new(
+                        crate::ErrorKind::Parsing,
+                        format!("Invalid character '{}' in identifier: {}", c, s),
+                    ))
+                }
+            };
+            bytes.push(b);
+        }
+
+        match bytes.as_slice() {
+            &[a, b, c, d] => Ok(Self([a, b, c, d])),
+            _ => Err(crate::Error::new(
+                crate::ErrorKind::Parsing,
+                format!("Identifier is not 4 bytes long: {}", s),
+            )),
+        }
     }
 }
 
@@ -223,8 +362,8 @@ pub struct FreeformIdent<'a> {
 impl PartialEq<DataIdent> for FreeformIdent<'_> {
     fn eq(&self, other: &DataIdent) -> bool {
         match other {
-            DataIdent::Fourcc(_) => false,
             DataIdent::Freeform { mean, name } => self.mean == mean && self.name == name,
+            _ => false,
         }
     }
 }
@@ -264,20 +403,30 @@ pub enum DataIdent {
         /// The name string used to identify the freeform atom.
         name: String,
     },
+    /// An identifier of a QuickTime metadata item, stored in the metadata item keys (`keys`) atom
+    /// and typically in reverse domain notation, for example `com.apple.quicktime.title`.
+    Key(String),
 }
 
 impl Ident for DataIdent {
     fn fourcc(&self) -> Option<Fourcc> {
         match self {
             Self::Fourcc(i) => Some(*i),
-            Self::Freeform { .. } => None,
+            _ => None,
         }
     }
 
     fn freeform(&self) -> Option<FreeformIdent> {
         match self {
-            Self::Fourcc(_) => None,
             Self::Freeform { mean, name } => Some(FreeformIdent::new(mean.as_str(), name.as_str())),
+            _ => None,
+        }
+    }
+
+    fn key(&self) -> Option<&str> {
+        match self {
+            Self::Key(key) => Some(key.as_str()),
+            _ => None,
         }
     }
 }
@@ -287,6 +436,7 @@ impl fmt::Display for DataIdent {
         match self {
             Self::Fourcc(ident) => write!(f, "{}", ident),
             Self::Freeform { mean, name } => write!(f, "----:{}:{}", mean, name),
+            Self::Key(key) => write!(f, "{}", key),
         }
     }
 }
@@ -321,4 +471,9 @@ impl DataIdent {
     pub const fn fourcc(bytes: [u8; 4]) -> Self {
         Self::Fourcc(Fourcc(bytes))
     }
+
+    /// Creates a new identifier of type [`DataIdent::Key`] containing the owned key string.
+    pub fn key(key: impl Into<String>) -> Self {
+        Self::Key(key.into())
+    }
 }
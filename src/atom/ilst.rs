@@ -22,23 +22,78 @@ impl Atom for Ilst<'_> {
 }
 
 impl ParseAtom for Ilst<'_> {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
+        let (ilst, _) = Self::parse_items(reader, ctx, size.content_len(), size.content_len())?;
+        Ok(ilst)
+    }
+}
+
+impl WriteAtom for Ilst<'_> {
+    fn write_atom(&self, writer: &mut (impl Write + Seek)) -> crate::Result<()> {
+        self.write_padded(writer, 0)
+    }
+
+    fn size(&self) -> Size {
+        let content_len = self.fourcc_atoms().map(|a| a.len()).sum();
+        Size::from(content_len)
+    }
+}
+
+impl Ilst<'_> {
+    /// Attempts to parse an item list, whose length may be slightly off, as some encoders write.
+    /// The last item may extend beyond the declared length up to `max_len`, the remaining length
+    /// of the parent, and parsing stops before trailing bytes too short to contain an item, which
+    /// most likely belong to the next sibling. Returns the item list and its actual length.
+    pub fn parse_lenient(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        head: Head,
+        max_len: u64,
+    ) -> crate::Result<(Self, u64)> {
+        ctx.record_atom();
+        let max_content_len = max_len.saturating_sub(head.head_len());
+        let (ilst, len) = Self::parse_items(reader, ctx, head.content_len(), max_content_len)?;
+        if len != head.content_len() {
+            ctx.warn(|| {
+                format!(
+                    "Length of item list (ilst) doesn't match its items: {} != {}",
+                    head.content_len(),
+                    len,
+                )
+            });
+        }
+        Ok((ilst, head.head_len() + len))
+    }
+
+    fn parse_items(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        len: u64,
+        max_len: u64,
+    ) -> crate::Result<(Self, u64)> {
         let mut ilst = Vec::<AtomData>::new();
         let mut parsed_bytes = 0;
 
-        while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+        while parsed_bytes < len {
+            let remaining = len - parsed_bytes;
+            if ctx.lenient && remaining < 8 {
+                break;
+            }
+
+            let head = parse_head(reader, max_len - parsed_bytes)?;
 
             match head.fourcc() {
-                FREE => {
-                    reader.seek(SeekFrom::Current(head.content_len() as i64))?;
-                }
+                FREE | SKIP => ctx.skip_atom(reader, head)?,
                 _ => {
-                    let atom = AtomData::parse(reader, head.fourcc(), head.content_len())?;
+                    let atom = AtomData::parse(reader, ctx, head.fourcc(), head.content_len())?;
                     let other = ilst.iter_mut().find(|o| atom.ident == o.ident);
 
                     match other {
-                        Some(other) => other.data.extend(atom.data),
+                        Some(other) => other.append(atom),
                         None => ilst.push(atom),
                     }
                 }
@@ -47,26 +102,63 @@ impl ParseAtom for Ilst<'_> {
             parsed_bytes += head.len();
         }
 
-        Ok(Self::Owned(ilst))
+        Ok((Self::Owned(ilst), parsed_bytes))
     }
-}
 
-impl WriteAtom for Ilst<'_> {
-    fn write_atom(&self, writer: &mut impl Write) -> crate::Result<()> {
-        self.write_head(writer)?;
-        for a in self.iter() {
-            a.write(writer)?;
-        }
-        Ok(())
+    /// Returns the atoms identified by a fourcc or freeform identifier, which are stored in a
+    /// classic item list. QuickTime metadata items identified by a key are skipped.
+    pub fn fourcc_atoms(&self) -> impl Iterator<Item = &AtomData> {
+        self.iter().filter(|a| !matches!(a.ident, DataIdent::Key(_)))
     }
 
-    fn size(&self) -> Size {
-        let content_len = self.iter().map(|a| a.len()).sum();
+    /// Attempts to write the item list to the writer, followed by a free atom (`free`) of the
+    /// padding length inside of it. No free atom is written if the padding length is 0.
+    pub fn write_padded(
+        &self,
+        writer: &mut (impl Write + Seek),
+        padding_len: u64,
+    ) -> crate::Result<()> {
+        let size = Size::from(self.size().content_len() + padding_len);
+        write_container(writer, Self::FOURCC, size, |writer| {
+            for a in self.fourcc_atoms() {
+                a.write(writer)?;
+            }
+            if padding_len > 0 {
+                write_head(writer, Head::new(false, padding_len, FREE))?;
+                writer.write_all(&vec![0; (padding_len - 8) as usize])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns the QuickTime metadata items identified by a key.
+    pub fn key_atoms(&self) -> impl Iterator<Item = &AtomData> {
+        self.iter().filter(|a| matches!(a.ident, DataIdent::Key(_)))
+    }
+
+    /// Attempts to write the QuickTime metadata items to the writer, each identified by the index
+    /// of its key.
+    pub fn write_keyed(&self, writer: &mut (impl Write + Seek), keys: &Keys) -> crate::Result<()> {
+        write_container(writer, Self::FOURCC, self.keyed_size(), |writer| {
+            for a in self.key_atoms() {
+                let index = a.ident.key().and_then(|k| keys.index_of(k)).ok_or_else(|| {
+                    crate::Error::new(
+                        crate::ErrorKind::UnwritableData,
+                        format!("Missing key of QuickTime metadata item '{}'", a.ident),
+                    )
+                })?;
+                a.write_indexed(writer, index)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns the size of the item list containing the QuickTime metadata items.
+    pub fn keyed_size(&self) -> Size {
+        let content_len = self.key_atoms().map(|a| a.len()).sum();
         Size::from(content_len)
     }
-}
 
-impl Ilst<'_> {
     pub fn owned(self) -> Option<Vec<AtomData>> {
         match self {
             Self::Owned(a) => Some(a),
@@ -87,12 +179,49 @@ impl Deref for IlstBounds {
     }
 }
 
+impl IlstBounds {
+    /// Returns the length of the free atoms (`free`, `skip`) at the end of the item list, which
+    /// can be preserved as padding.
+    pub fn padding_len(&self, reader: &mut (impl Read + Seek)) -> crate::Result<u64> {
+        reader.seek(SeekFrom::Start(self.content_pos()))?;
+        let mut padding_len = 0;
+        let mut parsed_bytes = 0;
+
+        while parsed_bytes < self.content_len() {
+            let head = parse_head(reader, self.content_len() - parsed_bytes)?;
+
+            match head.fourcc() {
+                FREE | SKIP => padding_len += head.len(),
+                _ => padding_len = 0,
+            }
+            reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+
+            parsed_bytes += head.len();
+        }
+
+        Ok(padding_len)
+    }
+
+    /// Returns the length of the padding following items of the length, so the item list keeps
+    /// its length. If there is no padding, or the items don't fit, the padding keeps its length.
+    pub fn resized_padding_len(
+        &self,
+        reader: &mut (impl Read + Seek),
+        items_len: u64,
+    ) -> crate::Result<u64> {
+        let padding_len = self.padding_len(reader)?;
+        match self.content_len().checked_sub(items_len) {
+            Some(l) if padding_len > 0 && (l == 0 || (8..=u32::MAX as u64).contains(&l)) => Ok(l),
+            _ => Ok(padding_len),
+        }
+    }
+}
+
 impl FindAtom for Ilst<'_> {
     type Bounds = IlstBounds;
 
     fn find_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self::Bounds> {
-        let bounds = find_bounds(reader, size)?;
-        seek_to_end(reader, &bounds)?;
+        let bounds = skip_bounds(reader, size)?;
         Ok(Self::Bounds { bounds })
     }
 }
@@ -0,0 +1,102 @@
+use super::*;
+
+/// A struct representing a metadata item keys atom (`keys`). The key at index `i` is referenced by
+/// the item list entry with the identifier `i + 1` as a 32 bit big endian integer.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Keys(pub Vec<String>);
+
+impl Deref for Keys {
+    type Target = Vec<String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Atom for Keys {
+    const FOURCC: Fourcc = METADATA_KEYS;
+}
+
+impl ParseAtom for Keys {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
+        let bounds = find_bounds(reader, size)?;
+        let (version, _) = parse_full_head(reader)?;
+
+        if version != 0 {
+            return Err(crate::Error::new(
+                ErrorKind::UnknownVersion(version),
+                "Unknown metadata item keys (keys) version".to_owned(),
+            ));
+        }
+
+        let entries = reader.read_u32()?;
+        let mut keys = Vec::new();
+        let mut parsed_bytes = 8;
+
+        for _ in 0..entries {
+            let head = parse_head(reader, size.content_len().saturating_sub(parsed_bytes))?;
+            if head.fourcc() == QUICKTIME_METADATA {
+                keys.push(reader.read_utf8(head.content_len())?);
+            } else {
+                // keep the index of the following keys, items referencing this one are skipped
+                ctx.warn(|| format!("Unknown metadata item key namespace '{}'", head.fourcc()));
+                reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+                keys.push(String::new());
+            }
+            parsed_bytes += head.len();
+        }
+
+        seek_to_end(reader, &bounds)?;
+
+        Ok(Self(keys))
+    }
+}
+
+impl WriteAtom for Keys {
+    fn write_atom(&self, writer: &mut (impl Write + Seek)) -> crate::Result<()> {
+        self.write_head(writer)?;
+        write_full_head(writer, 0, [0; 3])?;
+        writer.write_all(&u32::to_be_bytes(self.0.len() as u32))?;
+        for k in self.0.iter() {
+            write_head(writer, Head::new(false, 8 + k.len() as u64, QUICKTIME_METADATA))?;
+            writer.write_all(k.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> Size {
+        let content_len: u64 = self.0.iter().map(|k| 8 + k.len() as u64).sum();
+        Size::from(8 + content_len)
+    }
+}
+
+impl Keys {
+    /// Creates a table of the distinct keys of the metadata items identified by a key.
+    pub fn from_atoms(atoms: &[AtomData]) -> Self {
+        let mut keys: Vec<String> = Vec::new();
+        for a in atoms.iter() {
+            if let DataIdent::Key(k) = &a.ident {
+                if !keys.contains(k) {
+                    keys.push(k.clone());
+                }
+            }
+        }
+        Self(keys)
+    }
+
+    /// Returns the item list entry identifier referencing the key.
+    pub fn index_of(&self, key: &str) -> Option<u32> {
+        self.0.iter().position(|k| k == key).map(|i| i as u32 + 1)
+    }
+
+    /// Returns the key referenced by the item list entry identifier. Keys of an unknown namespace
+    /// are empty.
+    pub fn get(&self, fourcc: Fourcc) -> Option<&str> {
+        let index = u32::from_be_bytes(*fourcc) as usize;
+        self.0.get(index.checked_sub(1)?).map(|k| k.as_str())
+    }
+}
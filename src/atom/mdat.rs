@@ -22,8 +22,7 @@ impl FindAtom for Mdat {
     type Bounds = MdatBounds;
 
     fn find_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self::Bounds> {
-        let bounds = find_bounds(reader, size)?;
-        seek_to_end(reader, &bounds)?;
+        let bounds = skip_bounds(reader, size)?;
         Ok(Self::Bounds { bounds })
     }
 }
@@ -0,0 +1,70 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use super::*;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Mdhd {
+    /// The ISO 639-2/T language code of the media.
+    pub language: Option<String>,
+}
+
+impl Atom for Mdhd {
+    const FOURCC: Fourcc = MEDIA_HEADER;
+}
+
+impl ParseAtom for Mdhd {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        _ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
+        let bounds = find_bounds(reader, size)?;
+        let mut mdhd = Self::default();
+
+        let (version, _) = parse_full_head(reader)?;
+        let skipped = match version {
+            0 => {
+                // # Version 0
+                // 1 byte version
+                // 3 bytes flags
+                // 4 bytes creation time
+                // 4 bytes motification time
+                // 4 bytes time scale
+                // 4 bytes duration
+                // 2 bytes language
+                // ...
+                16
+            }
+            1 => {
+                // # Version 1
+                // 1 byte version
+                // 3 bytes flags
+                // 8 bytes creation time
+                // 8 bytes motification time
+                // 4 bytes time scale
+                // 8 bytes duration
+                // 2 bytes language
+                // ...
+                28
+            }
+            v => {
+                return Err(crate::Error::new(
+                    crate::ErrorKind::UnknownVersion(version),
+                    format!("Error unknown media header (mdhd) version {}", v),
+                ))
+            }
+        };
+
+        if size.content_len() >= 4 + skipped + 2 {
+            reader.seek(SeekFrom::Current(skipped as i64))?;
+            let language = reader.read_u16()? & 0x7FFF;
+            if Some(language) != pack_language("und") {
+                mdhd.language = unpack_language(language);
+            }
+        }
+
+        seek_to_end(reader, &bounds)?;
+
+        Ok(mdhd)
+    }
+}
@@ -2,6 +2,9 @@ use super::*;
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Mdia {
+    pub mdhd: Option<Mdhd>,
+    pub hdlr: Option<Hdlr>,
+    pub elng: Option<Elng>,
     pub minf: Option<Minf>,
 }
 
@@ -10,18 +13,35 @@ impl Atom for Mdia {
 }
 
 impl ParseAtom for Mdia {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
+        let bounds = find_bounds(reader, size)?;
         let mut mdia = Self::default();
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
-                MEDIA_INFORMATION => mdia.minf = Some(Minf::parse(reader, head.size())?),
-                _ => {
-                    reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+                MEDIA_HEADER => mdia.mdhd = Some(Mdhd::parse(reader, ctx, head.size())?),
+                HANDLER_REFERENCE => {
+                    let hdlr = Hdlr::parse(reader, ctx, head.size())?;
+                    let included = ctx.includes_handler(hdlr.subtype());
+                    mdia.hdlr = Some(hdlr);
+                    if !included {
+                        seek_to_end(reader, &bounds)?;
+                        break;
+                    }
+                }
+                EXTENDED_LANGUAGE => {
+                    check_content_len(reader, head, 4)?;
+                    mdia.elng = Some(Elng::parse(reader, ctx, head.size())?);
                 }
+                MEDIA_INFORMATION => mdia.minf = Some(Minf::parse(reader, ctx, head.size())?),
+                _ => ctx.skip_atom(reader, head)?,
             }
 
             parsed_bytes += head.len();
@@ -45,7 +65,7 @@ impl FindAtom for Mdia {
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
                 MEDIA_INFORMATION => minf = Some(Minf::find(reader, head.size())?),
@@ -3,7 +3,9 @@ use super::*;
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Meta<'a> {
     pub hdlr: Option<Hdlr>,
+    pub keys: Option<Keys>,
     pub ilst: Option<Ilst<'a>>,
+    pub id32: Option<Id32>,
 }
 
 impl Atom for Meta<'_> {
@@ -11,56 +13,111 @@ impl Atom for Meta<'_> {
 }
 
 impl ParseAtom for Meta<'_> {
-    fn parse_atom(reader: &'_ mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
-        let (version, _) = parse_full_head(reader)?;
-
-        if version != 0 {
-            return Err(crate::Error::new(
-                ErrorKind::UnknownVersion(version),
-                "Unknown metadata (meta) version".to_owned(),
-            ));
-        }
-
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         let mut meta = Self::default();
-        let mut parsed_bytes = 4;
+        let mut parsed_bytes = parse_meta_head(reader, size)?;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let remaining = size.content_len() - parsed_bytes;
+            let head = match ctx.lenient {
+                true => {
+                    let (head, declared_len) = parse_clamped_head(reader, remaining)?;
+                    if declared_len != head.len() {
+                        ctx.warn(|| {
+                            format!(
+                                "Length of '{}' exceeds the remaining length of its parent: {} > {}",
+                                head.fourcc(),
+                                declared_len,
+                                remaining,
+                            )
+                        });
+                    }
+                    head
+                }
+                false => parse_head(reader, remaining)?,
+            };
 
             match head.fourcc() {
-                ITEM_LIST => meta.ilst = Some(Ilst::parse(reader, head.size())?),
-                _ => {
-                    reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+                METADATA_KEYS => meta.keys = Some(Keys::parse(reader, ctx, head.size())?),
+                ITEM_LIST if ctx.lenient => {
+                    let (ilst, len) = Ilst::parse_lenient(reader, ctx, head, remaining)?;
+                    meta.ilst = Some(ilst);
+                    parsed_bytes += len;
+                    continue;
                 }
+                ITEM_LIST => meta.ilst = Some(Ilst::parse(reader, ctx, head.size())?),
+                ID3V2 => meta.id32 = Some(Id32::parse(reader, ctx, head.size())?),
+                _ => ctx.skip_atom(reader, head)?,
             }
 
             parsed_bytes += head.len();
         }
 
+        // QuickTime metadata items are identified by an index into the keys atom
+        if let (Some(keys), Some(Ilst::Owned(ilst))) = (&meta.keys, &mut meta.ilst) {
+            let mut atoms: Vec<AtomData> = Vec::new();
+            for mut a in std::mem::take(ilst) {
+                if let Some(key) = a.ident.fourcc().and_then(|f| keys.get(f)) {
+                    if key.is_empty() {
+                        continue;
+                    }
+                    a.ident = DataIdent::Key(key.to_owned());
+                }
+
+                match atoms.iter_mut().find(|o| a.ident == o.ident) {
+                    Some(other) => other.append(a),
+                    None => atoms.push(a),
+                }
+            }
+            *ilst = atoms;
+        }
+
         Ok(meta)
     }
 }
 
 impl WriteAtom for Meta<'_> {
-    fn write_atom(&self, writer: &mut impl Write) -> crate::Result<()> {
-        self.write_head(writer)?;
-        write_full_head(writer, 0, [0; 3])?;
-        if let Some(a) = &self.hdlr {
-            a.write(writer)?;
-        }
-        if let Some(a) = &self.ilst {
-            a.write(writer)?;
-        }
-        Ok(())
+    fn write_atom(&self, writer: &mut (impl Write + Seek)) -> crate::Result<()> {
+        write_container(writer, Self::FOURCC, self.size(), |writer| {
+            if !self.is_quicktime() {
+                write_full_head(writer, 0, [0; 3])?;
+            }
+            if let Some(a) = &self.hdlr {
+                a.write(writer)?;
+            }
+            if let Some(a) = &self.keys {
+                a.write(writer)?;
+            }
+            match (&self.keys, &self.ilst) {
+                (Some(keys), Some(a)) => a.write_keyed(writer, keys)?,
+                (None, Some(a)) => a.write(writer)?,
+                (_, None) => (),
+            }
+            if let Some(a) = &self.id32 {
+                a.write(writer)?;
+            }
+            Ok(())
+        })
     }
 
     fn size(&self) -> Size {
-        let content_len = self.hdlr.len_or_zero() + self.ilst.len_or_zero();
-        Size::from(content_len + 4)
+        let ilst_len = match (&self.keys, &self.ilst) {
+            (Some(_), Some(a)) => a.keyed_size().len(),
+            (None, a) => a.len_or_zero(),
+            (_, None) => 0,
+        };
+        let head_len = if self.is_quicktime() { 0 } else { 4 };
+        let content_len =
+            self.hdlr.len_or_zero() + self.keys.len_or_zero() + ilst_len + self.id32.len_or_zero();
+        Size::from(head_len + content_len)
     }
 }
 
-impl Meta<'_> {
+impl<'a> Meta<'a> {
     pub fn hdlr() -> Hdlr {
         Hdlr(vec![
             0x00, 0x00, 0x00, 0x00, // version + flags
@@ -72,6 +129,51 @@ impl Meta<'_> {
             0x00, // component name
         ])
     }
+
+    pub fn quicktime_hdlr() -> Hdlr {
+        Hdlr(vec![
+            0x00, 0x00, 0x00, 0x00, // version + flags
+            0x00, 0x00, 0x00, 0x00, // component type
+            0x6d, 0x64, 0x74, 0x61, // component subtype
+            0x00, 0x00, 0x00, 0x00, // component manufacturer
+            0x00, 0x00, 0x00, 0x00, // component flags
+            0x00, 0x00, 0x00, 0x00, // component flags mask
+            0x00, // component name
+        ])
+    }
+
+    pub fn id3v2_hdlr() -> Hdlr {
+        Hdlr(vec![
+            0x00, 0x00, 0x00, 0x00, // version + flags
+            0x00, 0x00, 0x00, 0x00, // component type
+            0x49, 0x44, 0x33, 0x32, // component subtype
+            0x00, 0x00, 0x00, 0x00, // component manufacturer
+            0x00, 0x00, 0x00, 0x00, // component flags
+            0x00, 0x00, 0x00, 0x00, // component flags mask
+            0x00, // component name
+        ])
+    }
+
+    /// Creates a metadata atom with an ID3v2 handler (`ID32`) containing the ID3v2 atom.
+    pub fn id3v2(id32: Id32) -> Self {
+        Self { hdlr: Some(Self::id3v2_hdlr()), keys: None, ilst: None, id32: Some(id32) }
+    }
+
+    /// Returns whether this is a QuickTime metadata atom, which contains a table of keys and, unlike
+    /// other metadata atoms, is written without a version and flags.
+    fn is_quicktime(&self) -> bool {
+        self.keys.is_some()
+    }
+
+    /// Creates a QuickTime metadata atom containing the metadata items identified by a key.
+    pub fn quicktime(atoms: &'a [AtomData]) -> Self {
+        Self {
+            hdlr: Some(Self::quicktime_hdlr()),
+            keys: Some(Keys::from_atoms(atoms)),
+            ilst: Some(Ilst::Borrowed(atoms)),
+            id32: None,
+        }
+    }
 }
 
 pub struct MetaBounds {
@@ -93,21 +195,12 @@ impl FindAtom for Meta<'_> {
 
     fn find_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self::Bounds> {
         let bounds = find_bounds(reader, size)?;
-        let (version, _) = parse_full_head(reader)?;
-
-        if version != 0 {
-            return Err(crate::Error::new(
-                ErrorKind::UnknownVersion(version),
-                "Unknown metadata (meta) version".to_owned(),
-            ));
-        }
-
         let mut hdlr = None;
         let mut ilst = None;
-        let mut parsed_bytes = 4;
+        let mut parsed_bytes = parse_meta_head(reader, size)?;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
                 HANDLER_REFERENCE => hdlr = Some(Hdlr::find(reader, head.size())?),
@@ -123,3 +216,27 @@ impl FindAtom for Meta<'_> {
         Ok(Self::Bounds { bounds, hdlr, ilst })
     }
 }
+
+/// Attempts to parse the version and flags of a metadata atom and returns their length. QuickTime
+/// metadata atoms (`moov.meta`) have no version and flags and directly start with the handler
+/// reference atom (`hdlr`), in which case nothing is consumed.
+fn parse_meta_head(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<u64> {
+    if size.content_len() >= 8 {
+        let mut buf = [0; 8];
+        reader.read_exact(&mut buf)?;
+        reader.seek(SeekFrom::Current(-8))?;
+        if Fourcc([buf[4], buf[5], buf[6], buf[7]]) == HANDLER_REFERENCE {
+            return Ok(0);
+        }
+    }
+
+    let (version, _) = parse_full_head(reader)?;
+    if version != 0 {
+        return Err(crate::Error::new(
+            ErrorKind::UnknownVersion(version),
+            "Unknown metadata (meta) version".to_owned(),
+        ));
+    }
+
+    Ok(4)
+}
@@ -10,18 +10,20 @@ impl Atom for Minf {
 }
 
 impl ParseAtom for Minf {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         let mut minf = Self::default();
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
-                SAMPLE_TABLE => minf.stbl = Some(Stbl::parse(reader, head.size())?),
-                _ => {
-                    reader.seek(SeekFrom::Current(head.content_len() as i64))?;
-                }
+                SAMPLE_TABLE => minf.stbl = Some(Stbl::parse(reader, ctx, head.size())?),
+                _ => ctx.skip_atom(reader, head)?,
             }
 
             parsed_bytes += head.len();
@@ -45,7 +47,7 @@ impl FindAtom for Minf {
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
                 SAMPLE_TABLE => stbl = Some(Stbl::find(reader, head.size())?),
@@ -9,39 +9,68 @@
 //! │  ├─ tkhd
 //! │  └─ mdia
 //! │     ├─ mdhd
+//! │     ├─ hdlr
+//! │     ├─ elng
 //! │     └─ minf
 //! │        └─ stbl
 //! │           ├─ stsd
-//! │           │  └─ mp4a
+//! │           │  ├─ mp4a
+//! │           │  └─ alac
+//! │           ├─ stts
+//! │           ├─ stsz
 //! │           ├─ stco
 //! │           └─ co64
-//! └─ udta
-//!    └─ meta
-//!       ├─ hdlr
-//!       └─ ilst
-//!          ├─ **** (any fourcc)
-//!          │  └─ data
-//!          └─ ---- (freeform fourcc)
-//!             ├─ mean
-//!             ├─ name
-//!             └─ data
+//! ├─ udta
+//! │  ├─ meta
+//! │  │  ├─ hdlr
+//! │  │  └─ ilst
+//! │  │     ├─ **** (any fourcc)
+//! │  │     │  └─ data
+//! │  │     └─ ---- (freeform fourcc)
+//! │  │        ├─ mean
+//! │  │        ├─ name
+//! │  │        └─ data
+//! │  └─ titl, auth, perf, albm, ... (3GPP asset information)
+//! └─ meta (QuickTime metadata)
+//!    ├─ hdlr (mdta)
+//!    ├─ keys
+//!    └─ ilst
+//!       └─ **** (1 based index into keys)
+//!          └─ data
+//! meta (file level metadata)
+//! ├─ ilst
+//! └─ ID32 (ID3v2)
+//! uuid (XMP)
 //! ```
 
+use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
 use std::ops::{Deref, DerefMut};
 
-use crate::{AudioInfo, ErrorKind, Tag};
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::{
+    AudioInfo, ErrorKind, Id3v2Style, ImgFmt, MetadataStyle, MovieHeader, ReadConfig, ReadStats,
+    Tag, WriteConfig,
+};
 
 use data::*;
 use head::*;
 
+use alac::*;
+use asset::*;
 use co64::*;
+use elng::*;
 use ftyp::*;
 use hdlr::*;
+use id32::*;
 use ilst::*;
+use keys::*;
 use mdat::*;
+use mdhd::*;
 use mdia::*;
 use meta::*;
 use minf::*;
@@ -51,8 +80,11 @@ use mvhd::*;
 use stbl::*;
 use stco::*;
 use stsd::*;
+use stsz::*;
+use stts::*;
 use trak::*;
 use udta::*;
+use uuid::*;
 
 pub use data::Data;
 pub use ident::*;
@@ -64,11 +96,17 @@ pub mod ident;
 
 mod head;
 
+mod alac;
+mod asset;
 mod co64;
+mod elng;
 mod ftyp;
 mod hdlr;
+mod id32;
 mod ilst;
+mod keys;
 mod mdat;
+mod mdhd;
 mod mdia;
 mod meta;
 mod minf;
@@ -78,16 +116,125 @@ mod mvhd;
 mod stbl;
 mod stco;
 mod stsd;
+mod stsz;
+mod stts;
 mod trak;
 mod udta;
+mod uuid;
+
+/// The read configuration, an optional collector of statistics and an optional progress
+/// callback, which are passed to the parsing functions of all atoms.
+#[derive(Clone, Copy)]
+pub(crate) struct ReadCtx<'a> {
+    cfg: &'a ReadConfig,
+    stats: Option<&'a RefCell<ReadStats>>,
+    progress: Option<&'a RefCell<dyn FnMut(u64, u64) + 'a>>,
+}
+
+impl Deref for ReadCtx<'_> {
+    type Target = ReadConfig;
+
+    fn deref(&self) -> &Self::Target {
+        self.cfg
+    }
+}
+
+impl<'a> ReadCtx<'a> {
+    pub(crate) const fn new(cfg: &'a ReadConfig) -> Self {
+        Self { cfg, stats: None, progress: None }
+    }
+
+    /// Creates a context reporting the number of bytes of the top level atoms that have been
+    /// traversed and the total number of bytes to the callback.
+    pub(crate) fn with_progress(
+        cfg: &'a ReadConfig,
+        progress: &'a RefCell<dyn FnMut(u64, u64) + 'a>,
+    ) -> Self {
+        Self { cfg, stats: None, progress: Some(progress) }
+    }
+
+    /// Reports the progress of traversing the top level atoms.
+    fn report_progress(&self, bytes_done: u64, bytes_total: u64) {
+        if let Some(p) = self.progress {
+            (p.borrow_mut())(bytes_done, bytes_total);
+        }
+    }
+
+    /// Records that an atom was visited.
+    fn record_atom(&self) {
+        if let Some(s) = self.stats {
+            s.borrow_mut().atoms_visited += 1;
+        }
+    }
+
+    /// Skips the content of an atom that isn't interpreted, and records it.
+    fn skip_atom(&self, reader: &mut impl Seek, head: Head) -> crate::Result<()> {
+        if let Some(s) = self.stats {
+            let mut stats = s.borrow_mut();
+            stats.atoms_visited += 1;
+            stats.skipped_atoms.push(head.fourcc());
+        }
+        reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+        Ok(())
+    }
+
+    /// Records a warning about malformed data that was recovered from.
+    fn warn(&self, warning: impl FnOnce() -> String) {
+        if let Some(s) = self.stats {
+            s.borrow_mut().warnings.push(warning());
+        }
+    }
+
+    /// Decodes a utf-8 string of the atom, falling back to the configured encoding if the bytes
+    /// aren't valid utf-8.
+    #[cfg_attr(not(feature = "encoding_rs"), allow(unused_variables))]
+    fn decode_utf8(&self, bytes: Vec<u8>, fourcc: Fourcc) -> crate::Result<String> {
+        let err = match String::from_utf8(bytes) {
+            Ok(s) => return Ok(s),
+            Err(e) => e,
+        };
+
+        #[cfg(feature = "encoding_rs")]
+        if let Some(encoding) = self.fallback_encoding {
+            self.warn(|| format!("Decoded invalid utf-8 of '{}' as {}", fourcc, encoding.name()));
+            let (s, _) = encoding.decode_without_bom_handling(err.as_bytes());
+            return Ok(s.into_owned());
+        }
+
+        Err(err.into())
+    }
+}
+
+/// A reader counting the bytes read and the seeks performed.
+struct StatsReader<'a, R> {
+    inner: &'a mut R,
+    bytes_read: u64,
+    seeks: u64,
+}
+
+impl<R: Read> Read for StatsReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for StatsReader<'_, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.seeks += 1;
+        self.inner.seek(pos)
+    }
+}
 
 trait Atom: Sized {
     const FOURCC: Fourcc;
 }
 
 trait ParseAtom: Atom {
-    fn parse(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
-        match Self::parse_atom(reader, size) {
+    fn parse(reader: &mut (impl Read + Seek), ctx: &ReadCtx, size: Size) -> crate::Result<Self> {
+        ctx.record_atom();
+        match Self::parse_atom(reader, ctx, size) {
             Err(mut e) => {
                 e.description = format!("Error parsing {}: {}", Self::FOURCC, e.description);
                 Err(e)
@@ -96,7 +243,11 @@ trait ParseAtom: Atom {
         }
     }
 
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self>;
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self>;
 }
 
 trait FindAtom: Atom {
@@ -116,7 +267,7 @@ trait FindAtom: Atom {
 }
 
 trait WriteAtom: Atom {
-    fn write(&self, writer: &mut impl Write) -> crate::Result<()> {
+    fn write(&self, writer: &mut (impl Write + Seek)) -> crate::Result<()> {
         match self.write_atom(writer) {
             Err(mut e) => {
                 e.description = format!("Error writing {}: {}", Self::FOURCC, e.description);
@@ -135,11 +286,42 @@ trait WriteAtom: Atom {
         self.size().len()
     }
 
-    fn write_atom(&self, writer: &mut impl Write) -> crate::Result<()>;
+    fn write_atom(&self, writer: &mut (impl Write + Seek)) -> crate::Result<()>;
 
     fn size(&self) -> Size;
 }
 
+/// Attempts to write a container atom by writing a placeholder head followed by the children, and
+/// then backpatching the length using the number of bytes written. The expected size only
+/// determines whether the placeholder is an extended head, since the head has to be written
+/// before the children.
+fn write_container<W: Write + Seek>(
+    writer: &mut W,
+    fourcc: Fourcc,
+    size: Size,
+    write_children: impl FnOnce(&mut CountingWriter<&mut W>) -> crate::Result<()>,
+) -> crate::Result<()> {
+    let start = writer.stream_position()?;
+    writer.write_all(&vec![0; size.head_len() as usize])?;
+
+    let mut counter = CountingWriter::new(&mut *writer)?;
+    write_children(&mut counter)?;
+    let len = size.head_len() + counter.count();
+
+    if !size.ext() && needs_ext(len) {
+        return Err(crate::Error::new(
+            crate::ErrorKind::UnwritableData,
+            format!("Container atom '{}' is too large to be written", fourcc),
+        ));
+    }
+
+    writer.seek(SeekFrom::Start(start))?;
+    write_head(writer, Head::new(size.ext(), len, fourcc))?;
+    writer.seek(SeekFrom::Start(start + len))?;
+
+    Ok(())
+}
+
 trait LenOrZero {
     fn len_or_zero(&self) -> u64;
 }
@@ -157,46 +339,83 @@ pub struct AtomData {
     pub ident: DataIdent,
     /// The data contained in the atom.
     pub data: Vec<Data>,
+    /// The localized data contained in the atom, along with its locale indicator consisting of a
+    /// 2 byte country code and a 2 byte language code. The first data atom is always read as
+    /// regular data, and its locale isn't preserved.
+    pub localized: Vec<([u8; 4], Data)>,
 }
 
 impl AtomData {
     /// Creates atom data with the identifier and data.
     pub const fn new(ident: DataIdent, data: Vec<Data>) -> Self {
-        Self { ident, data }
+        Self { ident, data, localized: Vec::new() }
     }
 
     /// Returns the external length of the atom in bytes.
     pub fn len(&self) -> u64 {
-        let parent_len = 8;
-        let data_len: u64 = self.data.iter().map(|d| 16 + d.len()).sum();
+        self.size().len()
+    }
+
+    /// Returns the size of the atom, whose head, like the heads of the data atoms, is extended if
+    /// its length exceeds the maximum length of a standard head.
+    fn size(&self) -> Size {
+        let data_len: u64 = self.data.iter().map(|d| data_size(d).len()).sum::<u64>()
+            + self.localized.iter().map(|(_, d)| data_size(d).len()).sum::<u64>();
 
         match &self.ident {
-            DataIdent::Fourcc(_) => parent_len + data_len,
+            DataIdent::Fourcc(_) | DataIdent::Key(_) => Size::from(data_len),
             DataIdent::Freeform { mean, name } => {
                 let mean_len = 12 + mean.len() as u64;
                 let name_len = 12 + name.len() as u64;
 
-                parent_len + mean_len + name_len + data_len
+                Size::from(mean_len + name_len + data_len)
             }
         }
     }
 
     /// Returns whether the inner data atom is empty.
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty() || self.data.iter().all(|d| d.is_empty())
+        self.data.iter().all(|d| d.is_empty()) && self.localized.iter().all(|(_, d)| d.is_empty())
     }
 
-    fn parse(reader: &mut (impl Read + Seek), parent: Fourcc, len: u64) -> crate::Result<Self> {
+    /// Appends the data of the other atom, which is expected to have the same identifier.
+    pub fn append(&mut self, other: AtomData) {
+        self.data.extend(other.data);
+        self.localized.extend(other.localized);
+    }
+
+    fn parse(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        parent: Fourcc,
+        len: u64,
+    ) -> crate::Result<Self> {
+        ctx.record_atom();
+        if ctx.lenient && parent != FREEFORM && !starts_with_child(reader, len)? {
+            // some broken files store the string directly, without a wrapping data atom
+            if let Ok(s) = ctx.decode_utf8(reader.read_u8_vec(len)?, parent) {
+                let s = s.trim_end_matches('\0').to_owned();
+                return Ok(AtomData::new(DataIdent::Fourcc(parent), vec![Data::Utf8(s)]));
+            }
+            return Err(crate::Error::new(
+                crate::ErrorKind::AtomNotFound(DATA),
+                format!("Error constructing atom data '{}', missing data atom", parent),
+            ));
+        }
+
         let mut data = Vec::new();
+        let mut localized = Vec::new();
         let mut mean: Option<String> = None;
         let mut name: Option<String> = None;
         let mut parsed_bytes = 0;
 
         while parsed_bytes < len {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, len - parsed_bytes)?;
 
             match head.fourcc() {
                 DATA => {
+                    ctx.record_atom();
+                    check_content_len(reader, head, 8)?;
                     let (version, flags) = parse_full_head(reader)?;
                     if version != 0 {
                         return Err(crate::Error::new(
@@ -207,12 +426,56 @@ impl AtomData {
                     let [b2, b1, b0] = flags;
                     let datatype = u32::from_be_bytes([0, b2, b1, b0]);
 
-                    // Skipping 4 byte locale indicator
-                    reader.seek(SeekFrom::Current(4))?;
-
-                    data.push(Data::parse(reader, datatype, head.content_len() - 8)?);
+                    let mut locale = [0u8; 4];
+                    reader.read_exact(&mut locale)?;
+
+                    let data_len = head.content_len() - 8;
+                    let d = match ctx.types.get(parent) {
+                        Some(c) if parent != FREEFORM => {
+                            c.decode(datatype, reader.read_u8_vec(data_len)?)?
+                        }
+                        _ if datatype == data::UTF16 && data_len % 2 != 0 && ctx.lenient => {
+                            let pos = reader.stream_position()? - 8 - head.head_len();
+                            ctx.warn(|| {
+                                format!(
+                                    "Truncated odd length utf-16 data of '{}' at offset {}",
+                                    parent, pos,
+                                )
+                            });
+                            let d = Data::parse(reader, datatype, data_len - 1)?;
+                            reader.seek(SeekFrom::Current(1))?;
+                            d
+                        }
+                        _ if datatype == data::UTF16 && data_len % 2 != 0 => {
+                            // decoding would silently drop the last byte
+                            let pos = reader.stream_position()? - 8 - head.head_len();
+                            return Err(crate::Error::new(
+                                crate::ErrorKind::Malformed(pos),
+                                format!(
+                                    "Utf-16 data of '{}' at offset {} has an odd length: {}",
+                                    parent, pos, data_len,
+                                ),
+                            ));
+                        }
+                        _ if ctx.artwork_atoms.contains(&parent) => {
+                            data::parse_artwork(datatype, reader.read_u8_vec(data_len)?)?
+                        }
+                        _ if datatype == data::UTF8 => {
+                            Data::Utf8(ctx.decode_utf8(reader.read_u8_vec(data_len)?, parent)?)
+                        }
+                        _ => Data::parse(reader, datatype, data_len)?,
+                    };
+                    // the first value is also the regular one, even if it is localized, so
+                    // files only containing values of a language are readable as usual
+                    let first = data.is_empty() && localized.is_empty();
+                    match first || is_default_locale(locale) {
+                        true => data.push(d),
+                        false => localized.push((locale, d)),
+                    }
                 }
                 MEAN => {
+                    ctx.record_atom();
+                    check_content_len(reader, head, 4)?;
                     let (version, _) = parse_full_head(reader)?;
                     if version != 0 {
                         return Err(crate::Error::new(
@@ -224,6 +487,8 @@ impl AtomData {
                     mean = Some(reader.read_utf8(head.content_len() - 4)?);
                 }
                 NAME => {
+                    ctx.record_atom();
+                    check_content_len(reader, head, 4)?;
                     let (version, _) = parse_full_head(reader)?;
                     if version != 0 {
                         return Err(crate::Error::new(
@@ -234,9 +499,7 @@ impl AtomData {
 
                     name = Some(reader.read_utf8(head.content_len() - 4)?);
                 }
-                _ => {
-                    reader.seek(SeekFrom::Current(head.content_len() as i64))?;
-                }
+                _ => ctx.skip_atom(reader, head)?,
             }
 
             parsed_bytes += head.len();
@@ -244,31 +507,26 @@ impl AtomData {
 
         let ident = match (parent, mean, name) {
             (FREEFORM, Some(mean), Some(name)) => DataIdent::Freeform { mean, name },
+            (fourcc, _, _) if ctx.artwork_atoms.contains(&fourcc) => DataIdent::Fourcc(ARTWORK),
             (fourcc, _, _) => DataIdent::Fourcc(fourcc),
         };
 
-        if data.is_empty() {
+        if data.is_empty() && localized.is_empty() {
             return Err(crate::Error::new(
                 crate::ErrorKind::AtomNotFound(DATA),
                 format!("Error constructing atom data '{}', missing data atom", parent),
             ));
         }
 
-        Ok(AtomData { ident, data })
+        Ok(AtomData { ident, data, localized })
     }
 
     /// Attempts to write the atom data to the writer.
     pub fn write(&self, writer: &mut impl Write) -> crate::Result<()> {
-        writer.write_all(&u32::to_be_bytes(self.len() as u32))?;
-
         match &self.ident {
-            DataIdent::Fourcc(ident) => writer.write_all(ident.deref())?,
-            _ => {
-                let (mean, name) = match &self.ident {
-                    DataIdent::Freeform { mean, name } => (mean.as_str(), name.as_str()),
-                    DataIdent::Fourcc(_) => unreachable!(),
-                };
-                writer.write_all(FREEFORM.deref())?;
+            DataIdent::Fourcc(ident) => write_head(writer, Head::from(self.size(), *ident))?,
+            DataIdent::Freeform { mean, name } => {
+                write_head(writer, Head::from(self.size(), FREEFORM))?;
 
                 let mean_len: u32 = 12 + mean.len() as u32;
                 writer.write_all(&u32::to_be_bytes(mean_len))?;
@@ -282,78 +540,546 @@ impl AtomData {
                 writer.write_all(&[0u8; 4])?;
                 writer.write_all(name.as_bytes())?;
             }
+            DataIdent::Key(key) => {
+                return Err(crate::Error::new(
+                    crate::ErrorKind::UnwritableData,
+                    format!("Unable to write QuickTime metadata item '{}' without keys atom", key),
+                ));
+            }
         }
 
+        self.write_data(writer)
+    }
+
+    /// Attempts to write the atom data to the writer, identified by the 1 based index of its key
+    /// in the keys atom.
+    pub fn write_indexed(&self, writer: &mut impl Write, index: u32) -> crate::Result<()> {
+        write_head(writer, Head::from(self.size(), Fourcc(u32::to_be_bytes(index))))?;
+        self.write_data(writer)
+    }
+
+    fn write_data(&self, writer: &mut impl Write) -> crate::Result<()> {
         for d in self.data.iter() {
-            let data_len: u32 = 16 + d.len() as u32;
-            writer.write_all(&u32::to_be_bytes(data_len))?;
-            writer.write_all(DATA.deref())?;
-            d.write_typed(writer)?;
+            write_head(writer, Head::from(data_size(d), DATA))?;
+            d.write_typed(writer, [0; 4])?;
+        }
+        for (locale, d) in self.localized.iter() {
+            write_head(writer, Head::from(data_size(d), DATA))?;
+            d.write_typed(writer, *locale)?;
         }
 
         Ok(())
     }
 }
 
-/// Attempts to read MPEG-4 audio metadata from the reader.
-pub(crate) fn read_tag_from(reader: &mut (impl Read + Seek)) -> crate::Result<Tag> {
+/// Returns the size of a data atom (`data`) containing the data, preceded by its 4 byte type and
+/// 4 byte locale indicator.
+fn data_size(data: &Data) -> Size {
+    Size::from(8 + data.len())
+}
+
+/// Returns whether the length of the atom is stored as 0, meaning it extends to the end of the
+/// file.
+fn has_zero_len(reader: &mut (impl Read + Seek), bounds: &AtomBounds) -> crate::Result<bool> {
+    reader.seek(SeekFrom::Start(bounds.pos()))?;
+    Ok(reader.read_u32()? == 0)
+}
+
+/// Returns whether the content of an item list entry of the length starts with a child atom
+/// (`data`, `mean` or `name`), without advancing the reader.
+fn starts_with_child(reader: &mut (impl Read + Seek), len: u64) -> crate::Result<bool> {
+    if len < 8 {
+        return Ok(false);
+    }
+
+    let child_len = reader.read_u32()? as u64;
+    let mut fourcc = Fourcc::default();
+    reader.read_exact(&mut *fourcc)?;
+    reader.seek(SeekFrom::Current(-8))?;
+
+    let fits = matches!(child_len, 0 | 1) || (8..=len).contains(&child_len);
+    Ok(fits && matches!(fourcc, DATA | MEAN | NAME))
+}
+
+/// Returns whether the locale indicator doesn't specify a language, or specifies the
+/// undetermined (`und`) language. Country codes of such data aren't preserved.
+fn is_default_locale(locale: [u8; 4]) -> bool {
+    let language = u16::from_be_bytes([locale[2], locale[3]]);
+    language == 0 || Some(language) == pack_language("und")
+}
+
+/// Returns the packed ISO 639-2/T language code of the 3 letter lowercase language, as it is
+/// stored in the locale indicator.
+pub(crate) fn pack_language(lang: &str) -> Option<u16> {
+    match lang.as_bytes() {
+        &[a, b, c] if lang.bytes().all(|b| b.is_ascii_lowercase()) => {
+            let [a, b, c] = [a - 0x60, b - 0x60, c - 0x60].map(u16::from);
+            Some(a << 10 | b << 5 | c)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the 3 letter ISO 639-2/T language of the packed language code, if it is one.
+pub(crate) fn unpack_language(code: u16) -> Option<String> {
+    let chars = [code >> 10 & 0x1F, code >> 5 & 0x1F, code & 0x1F];
+    let lang: String = chars.iter().map(|c| (*c as u8 + 0x60) as char).collect();
+    match lang.bytes().all(|b| b.is_ascii_lowercase()) {
+        true => Some(lang),
+        false => None,
+    }
+}
+
+/// Attempts to read MPEG-4 audio metadata from the reader. Of multiple movie atoms (`moov`) the
+/// first one containing a movie header (`mvhd`) is used.
+pub(crate) fn read_tag_from(reader: &mut (impl Read + Seek), ctx: &ReadCtx) -> crate::Result<Tag> {
     let Ftyp(ftyp) = Ftyp::parse(reader)?;
+    ctx.record_atom();
 
     let len = data::remaining_stream_len(reader)?;
+    let ftyp_len = 8 + ftyp.len() as u64;
+    ctx.report_progress(ftyp_len, ftyp_len + len);
+
+    let mut moov = None;
+    let mut meta: Option<Meta> = None;
+    let mut xmp = None;
     let mut parsed_bytes = 0;
-    let moov = loop {
-        if parsed_bytes >= len {
-            return Err(crate::Error::new(
-                ErrorKind::AtomNotFound(MOVIE),
-                "Missing necessary data, no movie (moov) atom found".to_owned(),
-            ));
-        }
 
-        let head = parse_head(reader)?;
+    while parsed_bytes < len {
+        let head = parse_top_level_head(reader, len - parsed_bytes)?;
 
         match head.fourcc() {
-            MOVIE => {
-                break Moov::parse(reader, head.size())?;
+            MOVIE if !matches!(moov, Some(Moov { mvhd: Some(_), .. })) => {
+                let bounds = find_bounds(reader, head.size())?;
+                let m = match Moov::parse(reader, ctx, head.size()) {
+                    Ok(m) => m,
+                    // A movie atom with a length of 0, extending to the end of the file, that
+                    // can't be parsed was most likely only partially written.
+                    Err(_) if has_zero_len(reader, &bounds)? => {
+                        seek_to_end(reader, &bounds)?;
+                        Moov::default()
+                    }
+                    Err(e) => return Err(e),
+                };
+                if moov.is_none() || m.mvhd.is_some() {
+                    moov = Some(m);
+                }
             }
-            _ => {
-                reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+            METADATA => {
+                // the ID3v2 tag is usually stored in a separate metadata atom
+                let m = Meta::parse(reader, ctx, head.size())?;
+                match &mut meta {
+                    Some(first) => {
+                        first.ilst = first.ilst.take().or(m.ilst);
+                        first.id32 = first.id32.take().or(m.id32);
+                    }
+                    None => meta = Some(m),
+                }
+            }
+            USER_EXTENSION => {
+                let uuid = Uuid::parse(reader, ctx, head.size())?;
+                if uuid.uuid == XMP_UUID {
+                    xmp = Some(uuid.data);
+                }
             }
+            _ => ctx.skip_atom(reader, head)?,
         }
 
         parsed_bytes += head.len();
-    };
+        ctx.report_progress(ftyp_len + parsed_bytes, ftyp_len + len);
+    }
+
+    let moov = moov.ok_or_else(|| {
+        crate::Error::new(
+            ErrorKind::NotAnMp4,
+            "Missing necessary data, no movie (moov) atom found".to_owned(),
+        )
+    })?;
+    check_audio_track(ctx, &moov)?;
+
+    let mut tag = tag_from_moov(ctx, ftyp, moov, meta);
+    if let Some(x) = xmp {
+        tag.set_xmp(x);
+    }
+
+    Ok(tag)
+}
+
+/// Attempts to read MPEG-4 audio metadata from the reader, see [`read_tag_from`], and collects
+/// statistics about the reading process.
+pub(crate) fn read_tag_with_stats<R: Read + Seek>(
+    reader: &mut R,
+    cfg: &ReadConfig,
+) -> crate::Result<(Tag, ReadStats)> {
+    let stats = RefCell::new(ReadStats::default());
+    let mut reader = StatsReader { inner: reader, bytes_read: 0, seeks: 0 };
+    let tag = read_tag_from(&mut reader, &ReadCtx { cfg, stats: Some(&stats), progress: None })?;
+
+    let mut stats = stats.into_inner();
+    stats.bytes_read = reader.bytes_read;
+    stats.seeks = reader.seeks;
+    Ok((tag, stats))
+}
+
+/// Attempts to read MPEG-4 audio metadata from the reader, starting with the atom at the offset.
+/// If it is the filetype atom (`ftyp`), the metadata is read as usual. If it is the movie atom
+/// (`moov`), only it is read, the filetype is left empty and no XMP metadata is read.
+pub(crate) fn read_tag_from_at(
+    reader: &mut (impl Read + Seek),
+    ctx: &ReadCtx,
+    offset: u64,
+) -> crate::Result<Tag> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let len = data::remaining_stream_len(reader)?;
+    let head = parse_top_level_head(reader, len)?;
+
+    match head.fourcc() {
+        FILETYPE => {
+            reader.seek(SeekFrom::Start(offset))?;
+            read_tag_from(reader, ctx)
+        }
+        MOVIE => {
+            reader.seek(SeekFrom::Start(offset))?;
+            read_tag_from_moov(reader, ctx)
+        }
+        f => Err(crate::Error::new(
+            ErrorKind::AtomNotFound(MOVIE),
+            format!(
+                "Expected a filetype (ftyp) or movie (moov) atom at offset {}, found '{}'",
+                offset, f,
+            ),
+        )),
+    }
+}
+
+/// Attempts to read MPEG-4 audio metadata from the movie atom (`moov`) at the current position of
+/// the reader. The filetype is left empty and no XMP metadata is read.
+pub(crate) fn read_tag_from_moov(
+    reader: &mut (impl Read + Seek),
+    ctx: &ReadCtx,
+) -> crate::Result<Tag> {
+    let len = data::remaining_stream_len(reader)?;
+    let head = parse_top_level_head(reader, len)?;
+    if head.fourcc() != MOVIE {
+        return Err(crate::Error::new(
+            ErrorKind::AtomNotFound(MOVIE),
+            format!("Expected a movie atom (moov), found '{}'", head.fourcc()),
+        ));
+    }
 
+    let moov = Moov::parse(reader, ctx, head.size())?;
+    check_audio_track(ctx, &moov)?;
+    Ok(tag_from_moov(ctx, String::new(), moov, None))
+}
+
+/// Attempts to read MPEG-4 audio metadata from the bytes, which have to contain exactly one movie
+/// atom (`moov`), see [`read_tag_from_moov`].
+pub(crate) fn read_tag_from_moov_bytes(bytes: &[u8], ctx: &ReadCtx) -> crate::Result<Tag> {
+    let mut reader = Cursor::new(bytes);
+    let tag = read_tag_from_moov(&mut reader, ctx)?;
+
+    reader.set_position(0);
+    let head = parse_top_level_head(&mut reader, bytes.len() as u64)?;
+    if head.len() < bytes.len() as u64 {
+        return Err(crate::Error::new(
+            ErrorKind::Parsing,
+            format!(
+                "Expected only a movie atom (moov), found {} trailing bytes",
+                bytes.len() as u64 - head.len(),
+            ),
+        ));
+    }
+
+    Ok(tag)
+}
+
+/// Returns an error of kind [`ErrorKind::NoAudioTrack`] if an audio track is required, all tracks
+/// declare a media handler, none of which is a sound handler (`soun`), and no track was skipped.
+fn check_audio_track(ctx: &ReadCtx, moov: &Moov) -> crate::Result<()> {
+    let handlers: Option<Vec<Fourcc>> = moov.trak.iter().map(Trak::handler).collect();
+
+    match handlers {
+        _ if !ctx.require_audio_track || moov.trak_skipped => Ok(()),
+        Some(h) if !h.is_empty() && !h.contains(&SOUND_HANDLER) => Err(crate::Error::new(
+            ErrorKind::NoAudioTrack,
+            format!("No audio track found, the media handlers of the tracks are {:?}", h),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Creates a tag from the parsed movie atom and a top level metadata atom (`meta`), whose items
+/// are only used if the movie atom doesn't contain an item with the same identifier.
+#[cfg_attr(not(feature = "id3"), allow(unused_variables))]
+fn tag_from_moov(ctx: &ReadCtx, ftyp: String, moov: Moov, mut meta: Option<Meta>) -> Tag {
     let mvhd = moov.mvhd;
-    let mp4a = moov.trak.into_iter().find_map(|trak| {
-        trak.mdia
-            .and_then(|mdia| mdia.minf)
-            .and_then(|minf| minf.stbl)
-            .and_then(|stbl| stbl.stsd)
-            .and_then(|stsd| stsd.mp4a)
+    let drm_protected = moov.trak.iter().any(|trak| {
+        let stsd = trak
+            .mdia
+            .as_ref()
+            .and_then(|mdia| mdia.minf.as_ref())
+            .and_then(|minf| minf.stbl.as_ref())
+            .and_then(|stbl| stbl.stsd.as_ref());
+        matches!(stsd, Some(stsd) if stsd.protected)
     });
-    let ilst = moov
-        .udta
-        .and_then(|udta| udta.meta)
-        .and_then(|meta| meta.ilst)
-        .and_then(|ilst| ilst.owned())
-        .unwrap_or_default();
-
-    let mut info = AudioInfo::default();
+    let audio = moov.trak.into_iter().find_map(|trak| {
+        let mdia = trak.mdia?;
+        let stbl = mdia.minf?.stbl?;
+        let stsd = stbl.stsd?;
+        let (mp4a, bit_depth) = match (stsd.mp4a, stsd.alac) {
+            (Some(a), _) => (a, None),
+            (None, Some(a)) => {
+                let Alac { channel_config, sample_rate, avg_bitrate, bit_depth } = a;
+                (Mp4a { channel_config, sample_rate, max_bitrate: None, avg_bitrate }, bit_depth)
+            }
+            (None, None) => return None,
+        };
+        Some((mp4a, bit_depth, mdia.mdhd, mdia.elng, stbl.stsz, stbl.stts))
+    });
+    let (mut udta_meta, assets) = match moov.udta {
+        Some(udta) => (udta.meta, udta.assets),
+        None => (None, Vec::new()),
+    };
+    let mut qt_meta = moov.meta;
+    // the ID3v2 atom of the file level metadata takes precedence over the ones of the movie
+    let id32 = meta
+        .as_mut()
+        .and_then(|m| m.id32.take())
+        .or_else(|| qt_meta.as_mut()?.id32.take())
+        .or_else(|| udta_meta.as_mut()?.id32.take());
+    let mut ilst =
+        udta_meta.and_then(|meta| meta.ilst).and_then(|ilst| ilst.owned()).unwrap_or_default();
+    let qt_ilst = qt_meta.and_then(|meta| meta.ilst).and_then(|ilst| ilst.owned());
+    for a in qt_ilst.unwrap_or_default() {
+        match ilst.iter_mut().find(|o| a.ident == o.ident) {
+            Some(other) => other.append(a),
+            None => ilst.push(a),
+        }
+    }
+    let file_ilst = meta.and_then(|meta| meta.ilst).and_then(|ilst| ilst.owned());
+    for a in file_ilst.unwrap_or_default() {
+        if !ilst.iter().any(|o| a.ident == o.ident) {
+            ilst.push(a);
+        }
+    }
+    // 3GPP asset information is only used for metadata that isn't stored in an item list. Assets
+    // of a language are localized values, and also the value if they are the first of an atom.
+    let mut asset_atoms: Vec<AtomData> = Vec::new();
+    for Asset { ident, language, value } in assets {
+        let [l1, l0] = language.to_be_bytes();
+        let locale = [0, 0, l1, l0];
+        let atom = match asset_atoms.iter().position(|o| ident == o.ident) {
+            Some(i) => &mut asset_atoms[i],
+            None => {
+                asset_atoms.push(AtomData::new(DataIdent::Fourcc(ident), Vec::new()));
+                asset_atoms.last_mut().unwrap()
+            }
+        };
+        let data = Data::Utf8(value);
+        if is_default_locale(locale) {
+            atom.data.push(data);
+        } else if !atom.localized.iter().any(|(l, _)| *l == locale) {
+            if atom.data.is_empty() && atom.localized.is_empty() {
+                atom.data.push(data.clone());
+            }
+            atom.localized.push((locale, data));
+        }
+    }
+    for a in asset_atoms {
+        if !ilst.iter().any(|o| a.ident == o.ident) {
+            ilst.push(a);
+        }
+    }
+
+    let mut info = AudioInfo { drm_protected, ..Default::default() };
     if let Some(i) = mvhd {
         info.duration = Some(i.duration);
+        info.movie_header = Some(i.header);
     }
-    if let Some(i) = mp4a {
+    if let Some((i, bit_depth, mdhd, elng, stsz, stts)) = audio {
         info.channel_config = i.channel_config;
         info.sample_rate = i.sample_rate;
         info.max_bitrate = i.max_bitrate;
         info.avg_bitrate = i.avg_bitrate;
+        info.bit_depth = bit_depth;
+        info.language = mdhd.and_then(|a| a.language);
+        info.extended_language = elng.map(|a| a.language).filter(|l| !l.is_empty());
+        info.sample_count = stsz.map(|a| a.sample_count);
+        info.sample_durations = stts.map(|a| a.entries);
     }
 
-    Ok(Tag::new(ftyp, info, ilst))
+    let mut tag = Tag::new(ftyp, info, ilst);
+    if let Some(Id32 { language, data }) = id32 {
+        tag.set_id3v2_with_language(language, data);
+        // the ID3v2 tag is only used if there is no other metadata
+        #[cfg(feature = "id3")]
+        if tag.is_empty() {
+            if let Err(e) = tag.read_id3v2_box() {
+                ctx.warn(|| format!("Error reading ID3v2 atom (ID32): {}", e));
+            }
+        }
+    }
+
+    tag
 }
 
-/// Attempts to write the metadata atoms to the file inside the item list atom.
-pub(crate) fn write_tag_to(file: &File, atoms: &[AtomData]) -> crate::Result<()> {
+/// Attempts to copy the data of the artwork image at the index directly from the reader to the
+/// writer, by only locating the item list (`ilst`) and seeking to the data. Returns the image
+/// format, or `None` if there is no artwork image at the index.
+pub(crate) fn extract_artwork_to(
+    reader: &mut (impl Read + Seek),
+    index: usize,
+    writer: &mut impl Write,
+) -> crate::Result<Option<ImgFmt>> {
+    Ftyp::parse(reader)?;
+
+    let len = data::remaining_stream_len(reader)?;
+    let mut moov = None;
+    let mut parsed_bytes = 0;
+
+    while parsed_bytes < len {
+        let head = parse_top_level_head(reader, len - parsed_bytes)?;
+
+        match head.fourcc() {
+            MOVIE if !matches!(moov, Some(MoovBounds { mvhd: Some(_), .. })) => {
+                let m = Moov::find(reader, head.size())?;
+                if moov.is_none() || m.mvhd.is_some() {
+                    moov = Some(m);
+                }
+            }
+            _ => {
+                reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+            }
+        }
+
+        parsed_bytes += head.len();
+    }
+
+    let ilst = moov.and_then(|moov| moov.udta?.meta?.ilst);
+    let ilst = match ilst {
+        Some(i) => i,
+        None => return Ok(None),
+    };
+
+    reader.seek(SeekFrom::Start(ilst.content_pos()))?;
+    let mut remaining = index;
+    let mut parsed_bytes = 0;
+
+    while parsed_bytes < ilst.content_len() {
+        let head = parse_head(reader, ilst.content_len() - parsed_bytes)?;
+        parsed_bytes += head.len();
+
+        if head.fourcc() != ARTWORK {
+            reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+            continue;
+        }
+
+        let mut parsed_data_bytes = 0;
+        while parsed_data_bytes < head.content_len() {
+            let data_head = parse_head(reader, head.content_len() - parsed_data_bytes)?;
+            parsed_data_bytes += data_head.len();
+
+            // empty placeholder images are skipped, like in `Tag::artworks`
+            if data_head.fourcc() != DATA || data_head.content_len() <= 8 {
+                reader.seek(SeekFrom::Current(data_head.content_len() as i64))?;
+                continue;
+            }
+
+            let (_, [b2, b1, b0]) = parse_full_head(reader)?;
+            let datatype = u32::from_be_bytes([0, b2, b1, b0]);
+            let mut locale = [0u8; 4];
+            reader.read_exact(&mut locale)?;
+
+            let data_len = data_head.content_len() - 8;
+            let mut signature = [0; 8];
+            let signature_len = std::cmp::min(data_len, 8) as usize;
+            reader.read_exact(&mut signature[..signature_len])?;
+            reader.seek(SeekFrom::Current(-(signature_len as i64)))?;
+
+            let fmt = match data::artwork_fmt(datatype, &signature[..signature_len]) {
+                Some(f) if is_default_locale(locale) && remaining == 0 => f,
+                Some(_) if is_default_locale(locale) => {
+                    remaining -= 1;
+                    reader.seek(SeekFrom::Current(data_len as i64))?;
+                    continue;
+                }
+                _ => {
+                    reader.seek(SeekFrom::Current(data_len as i64))?;
+                    continue;
+                }
+            };
+
+            let data_pos = reader.stream_position()?;
+            let copied = std::io::copy(&mut reader.by_ref().take(data_len), writer)?;
+            if copied != data_len {
+                return Err(crate::Error::new(
+                    ErrorKind::Malformed(data_pos),
+                    "Artwork image data exceeds the end of the file".to_owned(),
+                ));
+            }
+            return Ok(Some(fmt));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Attempts to asynchronously read MPEG-4 audio metadata from the reader. Only the filetype atom
+/// and the atoms that might contain metadata are read into memory, all others, including the
+/// media data, are skipped. The buffered atoms are then parsed using [`read_tag_from`].
+#[cfg(feature = "tokio")]
+pub(crate) async fn read_tag_from_async(
+    reader: &mut (impl AsyncRead + AsyncSeek + Unpin),
+    cfg: &ReadConfig,
+) -> crate::Result<Tag> {
+    let start = reader.seek(SeekFrom::Current(0)).await?;
+    let end = reader.seek(SeekFrom::End(0)).await?;
+    reader.seek(SeekFrom::Start(start)).await?;
+
+    let mut buf = Vec::new();
+    let mut pos = start;
+
+    while pos < end {
+        let mut head_buf = vec![0; 8];
+        reader.read_exact(&mut head_buf).await?;
+        if head_buf[..4] == u32::to_be_bytes(1) {
+            head_buf.resize(16, 0);
+            reader.read_exact(&mut head_buf[8..]).await?;
+        }
+        let head = parse_top_level_head(&mut Cursor::new(&head_buf), end - pos)?;
+
+        match head.fourcc() {
+            _ if pos == start => (),
+            MOVIE | METADATA | USER_EXTENSION => (),
+            _ => {
+                pos = reader.seek(SeekFrom::Current(head.content_len() as i64)).await?;
+                continue;
+            }
+        }
+
+        write_head(&mut buf, head)?;
+        let content_pos = buf.len();
+        buf.resize(content_pos + head.content_len() as usize, 0);
+        reader.read_exact(&mut buf[content_pos..]).await?;
+        pos += head.len();
+    }
+
+    read_tag_from(&mut Cursor::new(buf), &ReadCtx::new(cfg))
+}
+
+/// Attempts to write the metadata atoms to the file inside the item list atom, the XMP metadata
+/// inside a user extension atom and, depending on the configuration, the ID3v2 tag with the packed
+/// language inside an ID3v2 atom. If present, the number of bytes written and the total number of
+/// bytes to write are reported to the progress callback.
+pub(crate) fn write_tag_to(
+    file: &File,
+    atoms: &[AtomData],
+    xmp: Option<&[u8]>,
+    id3v2: Option<(u16, &[u8])>,
+    strip_private: bool,
+    cfg: &WriteConfig,
+    mut progress: Option<&mut dyn FnMut(u64, u64)>,
+) -> crate::Result<()> {
     let mut reader = BufReader::new(file);
     let reader = &mut reader;
 
@@ -362,14 +1088,21 @@ pub(crate) fn write_tag_to(file: &File, atoms: &[AtomData]) -> crate::Result<()>
     let len = data::remaining_stream_len(reader)?;
     let mut moov = None;
     let mut mdat = None;
+    let mut free = Vec::new();
     let mut parsed_bytes = 0;
 
     while parsed_bytes < len {
-        let head = parse_head(reader)?;
+        let head = parse_top_level_head(reader, len - parsed_bytes)?;
 
         match head.fourcc() {
-            MOVIE => moov = Some(Moov::find(reader, head.size())?),
+            MOVIE if !matches!(moov, Some(MoovBounds { mvhd: Some(_), .. })) => {
+                let m = Moov::find(reader, head.size())?;
+                if moov.is_none() || m.mvhd.is_some() {
+                    moov = Some(m);
+                }
+            }
             MEDIA_DATA => mdat = Some(Mdat::find(reader, head.size())?),
+            FREE | SKIP => free.push(skip_bounds(reader, head.size())?),
             _ => {
                 reader.seek(SeekFrom::Current(head.content_len() as i64))?;
             }
@@ -381,153 +1114,503 @@ pub(crate) fn write_tag_to(file: &File, atoms: &[AtomData]) -> crate::Result<()>
     let mdat_pos = mdat.map_or(0, |a| a.pos());
     let moov = moov.ok_or_else(|| {
         crate::Error::new(
-            crate::ErrorKind::AtomNotFound(MOVIE),
+            crate::ErrorKind::NotAnMp4,
             "Missing necessary data, no movie (moov) atom found".to_owned(),
         )
     })?;
+
+    // free atoms directly following the moov atom, which can be used as padding
+    let mut padding_end = moov.end();
+    for a in free.iter() {
+        if a.pos() == padding_end {
+            padding_end = a.end();
+        }
+    }
+    let padding_len = padding_end - moov.end();
     let udta = &moov.udta;
     let meta = udta.as_ref().and_then(|a| a.meta.as_ref());
     let hdlr = meta.as_ref().and_then(|a| a.hdlr.as_ref());
     let ilst = meta.as_ref().and_then(|a| a.ilst.as_ref());
 
-    let mut new_atoms_start = 0;
-    let mut moved_data_start = 0;
-    let mut len_diff = 0;
+    // The moov atom is rebuilt in memory by replacing ranges of it with new atoms. Containers
+    // of replaced ranges are updated by the length difference of the ranges they contain.
+    let mut replacements: Vec<Replacement> = Vec::new();
+    let mut container_diffs: Vec<(&AtomBounds, i64)> = Vec::new();
 
-    let mut update_atoms = Vec::new();
-    let mut new_udta = None;
-    let mut new_meta = None;
-    let mut new_hdlr = None;
     let new_ilst = Ilst::Borrowed(atoms);
-
-    if hdlr.is_none() {
-        new_hdlr = Some(Meta::hdlr());
-    }
-    if let Some(ilst) = ilst {
-        new_atoms_start = ilst.pos();
-        moved_data_start = ilst.end();
-        len_diff -= ilst.len() as i64;
-    }
-
-    match meta {
-        Some(meta) => {
-            update_atoms.push(&meta.bounds);
-            if ilst.is_none() {
-                new_atoms_start = meta.end();
-                moved_data_start = meta.end();
+    let new_hdlr = if hdlr.is_none() { Some(Meta::hdlr()) } else { None };
+    let mut new_data = Cursor::new(Vec::new());
+    let (start, end) = match (udta, meta, ilst) {
+        (Some(_), Some(_), Some(ilst)) => {
+            let padding_len = match cfg.keep_ilst_padding {
+                true => ilst.resized_padding_len(reader, new_ilst.size().content_len())?,
+                false => 0,
+            };
+            if let Some(a) = new_hdlr {
+                a.write(&mut new_data)?;
             }
+            new_ilst.write_padded(&mut new_data, padding_len)?;
+            (ilst.pos(), ilst.end())
+        }
+        (Some(_), Some(meta), None) => {
+            if let Some(a) = new_hdlr {
+                a.write(&mut new_data)?;
+            }
+            new_ilst.write(&mut new_data)?;
+            (meta.end(), meta.end())
+        }
+        (Some(udta), None, _) => {
+            let new_meta = Meta { hdlr: new_hdlr, keys: None, ilst: Some(new_ilst), id32: None };
+            new_meta.write(&mut new_data)?;
+            (udta.end(), udta.end())
         }
-        None => {
-            new_meta = Some(Meta { hdlr: new_hdlr.take(), ilst: Some(new_ilst.clone()) });
+        (None, _, _) => {
+            metadata_udta(atoms).write(&mut new_data)?;
+            (moov.end(), moov.end())
         }
+    };
+    let new_data = new_data.into_inner();
+    let mut udta_diff = new_data.len() as i64 - (end - start) as i64;
+    replacements.push(Replacement { start, end, data: new_data });
+    if let Some(meta) = meta {
+        udta_diff = extend_head(&mut replacements, METADATA, &meta.bounds, udta_diff);
+        container_diffs.push((&meta.bounds, udta_diff));
     }
-    match udta {
-        Some(udta) => {
-            update_atoms.push(&udta.bounds);
-            if meta.is_none() {
-                new_atoms_start = udta.end();
-                moved_data_start = udta.end();
-            }
+    if let Some(udta) = udta {
+        udta_diff = extend_head(&mut replacements, USER_DATA, &udta.bounds, udta_diff);
+        container_diffs.push((&udta.bounds, udta_diff));
+
+        // The items of 3GPP assets were read into the tag and are now written to the item list.
+        // The assets are turned into free atoms, so removed items don't reappear.
+        for a in udta.assets.iter() {
+            let start = a.pos() + 4;
+            replacements.push(Replacement { start, end: start + 4, data: FREE.to_vec() });
         }
-        None => {
-            new_udta = Some(Udta { meta: new_meta.take() });
-            new_atoms_start = moov.end();
-            moved_data_start = moov.end();
+
+        // The location is overwritten, so it doesn't remain inside the free atom.
+        if let Some(a) = udta.location.as_ref().filter(|_| strip_private) {
+            let start = a.pos() + 4;
+            replacements.push(Replacement { start, end: start + 4, data: FREE.to_vec() });
+            let zeros = vec![0; a.size().content_len() as usize];
+            replacements.push(Replacement { start: a.content_pos(), end: a.end(), data: zeros });
         }
     }
-    update_atoms.push(&moov.bounds);
 
-    let new_atom_len = if let Some(a) = &new_udta {
-        a.len()
-    } else if let Some(a) = &new_meta {
-        a.len()
-    } else {
-        new_hdlr.len_or_zero() + new_ilst.len()
-    };
-    len_diff += new_atom_len as i64;
+    let rewrite_quicktime = strip_private && moov.meta.is_some();
+    if cfg.metadata_style == MetadataStyle::QuickTimeKeys || rewrite_quicktime {
+        let mut new_data = Cursor::new(Vec::new());
+        if atoms.iter().any(|a| matches!(a.ident, DataIdent::Key(_))) {
+            Meta::quicktime(atoms).write(&mut new_data)?;
+        }
+        let new_data = new_data.into_inner();
+        let (start, end) = match &moov.meta {
+            Some(meta) => (meta.pos(), meta.end()),
+            None => (moov.end(), moov.end()),
+        };
+        replacements.push(Replacement { start, end, data: new_data });
+    }
 
-    // reading moved data
-    let old_file_len = reader.seek(SeekFrom::End(0))?;
-    let moved_data_len = old_file_len - moved_data_start;
-    let mut moved_data = Vec::with_capacity(moved_data_len as usize);
-    reader.seek(SeekFrom::Start(moved_data_start))?;
-    reader.read_to_end(&mut moved_data)?;
+    let len_diff: i64 = replacements.iter().map(|r| r.len_diff()).sum();
+    let len_diff = extend_head(&mut replacements, MOVIE, &moov.bounds, len_diff);
+    container_diffs.push((&moov.bounds, len_diff));
 
-    let mut writer = BufWriter::new(file);
+    // If the moov atom is followed by free atoms, they are resized to compensate for the length
+    // difference, so the following atoms, and therefore chunk offsets, don't need to be moved.
+    let new_padding_len = padding_len as i64 - len_diff;
+    let use_padding = padding_len > 0
+        && (new_padding_len == 0 || (8..=u32::MAX as i64).contains(&new_padding_len));
+
+    // reading the moov atom, the data following it is moved later on
+    let old_file_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(moov.pos()))?;
+    let mut moov_data = reader.read_u8_vec(moov.len())?;
+    let moved_len = match use_padding || len_diff == 0 {
+        true => 0,
+        false => old_file_len - moov.end(),
+    };
 
     // adjusting sample table chunk offsets
-    if mdat_pos > moov.pos() {
+    if mdat_pos > moov.pos() && !use_padding {
         let stbl_atoms = moov.trak.iter().filter_map(|a| {
             a.mdia.as_ref().and_then(|a| a.minf.as_ref()).and_then(|a| a.stbl.as_ref())
         });
+        let mut moov_reader = Cursor::new(&mut moov_data);
+        let read_cfg = ReadConfig::default();
+        let ctx = ReadCtx::new(&read_cfg);
 
         for stbl in stbl_atoms {
             if let Some(a) = &stbl.stco {
-                reader.seek(SeekFrom::Start(a.content_pos()))?;
-                let chunk_offset = Stco::parse(reader, a.size())?;
+                moov_reader.set_position(a.content_pos() - moov.pos());
+                let chunk_offset = Stco::parse(&mut moov_reader, &ctx, a.size())?;
 
-                writer.seek(SeekFrom::Start(chunk_offset.table_pos))?;
+                moov_reader.set_position(chunk_offset.table_pos);
                 for co in chunk_offset.offsets.iter() {
                     let new_offset = (*co as i64 + len_diff) as u32;
-                    writer.write_all(&u32::to_be_bytes(new_offset))?;
+                    moov_reader.write_all(&u32::to_be_bytes(new_offset))?;
                 }
-                writer.flush()?;
             }
             if let Some(a) = &stbl.co64 {
-                reader.seek(SeekFrom::Start(a.content_pos()))?;
-                let chunk_offset = Co64::parse(reader, a.size())?;
+                moov_reader.set_position(a.content_pos() - moov.pos());
+                let chunk_offset = Co64::parse(&mut moov_reader, &ctx, a.size())?;
 
-                writer.seek(SeekFrom::Start(chunk_offset.table_pos))?;
+                moov_reader.set_position(chunk_offset.table_pos);
                 for co in chunk_offset.offsets.iter() {
                     let new_offset = (*co as i64 + len_diff) as u64;
-                    writer.write_all(&u64::to_be_bytes(new_offset))?;
+                    moov_reader.write_all(&u64::to_be_bytes(new_offset))?;
                 }
-                writer.flush()?;
             }
         }
     }
 
-    // update existing ilst hierarchy atom lengths
-    for a in update_atoms.iter().rev() {
-        let new_len = a.len() as i64 + len_diff;
-        writer.seek(SeekFrom::Start(a.pos()))?;
-        if a.ext() {
-            writer.write_all(&u32::to_be_bytes(1))?;
-            writer.seek(SeekFrom::Current(4))?;
-            writer.write_all(&u64::to_be_bytes(new_len as u64))?;
-        } else {
-            writer.write_all(&u32::to_be_bytes(new_len as u32))?;
+    apply_replacements(&mut moov_data, moov.pos(), &container_diffs, replacements);
+
+    let bytes_total = moov_data.len() as u64 + moved_len;
+    let mut report = |bytes_done| {
+        if let Some(p) = progress.as_mut() {
+            p(bytes_done, bytes_total);
+        }
+    };
+
+    // The moved data has to be moved before the new moov atom is written, since they might overlap.
+    move_data(file, moov.end(), moved_len, len_diff, cfg.copy_buf_size, &mut report)?;
+
+    let mut writer = BufWriter::new(file);
+    writer.seek(SeekFrom::Start(moov.pos()))?;
+    writer.write_all(&moov_data)?;
+
+    // writing resized padding
+    if use_padding && new_padding_len > 0 {
+        write_head(&mut writer, Head::new(false, new_padding_len as u64, FREE))?;
+        // the padding now contains the end of the old moov atom, which might contain private data
+        if strip_private && len_diff < 0 {
+            let stale_len = (-len_diff as u64).saturating_sub(8);
+            io::copy(&mut io::repeat(0).take(stale_len), &mut writer)?;
         }
     }
+    writer.flush()?;
+    report(bytes_total);
 
     // adjusting the file length
-    file.set_len((old_file_len as i64 + len_diff) as u64)?;
+    if !use_padding {
+        file.set_len((old_file_len as i64 + len_diff) as u64)?;
+    }
+
+    free_file_level_ilst(file)?;
+    write_xmp_to(file, xmp)?;
+
+    if cfg.id3v2 != Id3v2Style::Keep {
+        write_id3v2_to(file, id3v2)?;
+    }
+
+    Ok(())
+}
+
+/// Attempts to move the range of the file starting at the position by the length difference, by
+/// copying chunks of at most the buffer size, so the data is never loaded into memory at once. When
+/// the data is moved back, the copying starts with the last chunk, so no chunk is overwritten
+/// before it is copied. The number of bytes copied is reported after each chunk.
+fn move_data(
+    mut file: &File,
+    pos: u64,
+    len: u64,
+    diff: i64,
+    buf_size: usize,
+    report: &mut impl FnMut(u64),
+) -> crate::Result<()> {
+    let mut buf = vec![0; (buf_size.max(1) as u64).min(len) as usize];
+    let mut copied = 0;
+    while copied < len {
+        let n = (len - copied).min(buf.len() as u64);
+        let offset = if diff > 0 { len - copied - n } else { copied };
+        let chunk = &mut buf[..n as usize];
+
+        file.seek(SeekFrom::Start(pos + offset))?;
+        file.read_exact(chunk)?;
+        file.seek(SeekFrom::Start((pos as i64 + diff) as u64 + offset))?;
+        file.write_all(chunk)?;
+
+        copied += n;
+        report(copied);
+    }
+    Ok(())
+}
+
+/// A range of the file that is replaced by new data.
+struct Replacement {
+    start: u64,
+    end: u64,
+    data: Vec<u8>,
+}
+
+impl Replacement {
+    fn len_diff(&self) -> i64 {
+        self.data.len() as i64 - (self.end - self.start) as i64
+    }
+}
+
+/// Updates the lengths of the containers of replaced ranges inside the data of the moov atom at the
+/// position, and then applies the replacements.
+fn apply_replacements(
+    moov_data: &mut Vec<u8>,
+    moov_pos: u64,
+    container_diffs: &[(&AtomBounds, i64)],
+    mut replacements: Vec<Replacement>,
+) {
+    for (a, diff) in container_diffs.iter() {
+        let new_len = a.len() as i64 + diff;
+        let pos = (a.pos() - moov_pos) as usize;
+        if !a.ext() && needs_ext(new_len as u64) {
+            // the head is replaced by an extended one
+            continue;
+        } else if a.ext() {
+            moov_data[pos + 8..pos + 16].copy_from_slice(&u64::to_be_bytes(new_len as u64));
+        } else {
+            moov_data[pos..pos + 4].copy_from_slice(&u32::to_be_bytes(new_len as u32));
+        }
+    }
+
+    // replace ranges starting with the last one, so the positions of the others stay valid
+    replacements.sort_by_key(|r| r.start);
+    for r in replacements.into_iter().rev() {
+        let start = (r.start - moov_pos) as usize;
+        let end = (r.end - moov_pos) as usize;
+        moov_data.splice(start..end, r.data);
+    }
+}
 
-    // write missing ilst hierarchy and metadata
-    writer.seek(SeekFrom::Start(new_atoms_start))?;
+/// Replaces the standard head of the container with an extended one, if its length changed by the
+/// difference exceeds the maximum length of a standard head. Returns the length difference of the
+/// container, including the 8 bytes the extended head adds, which also have to be added to the
+/// length differences of its parents.
+fn extend_head(
+    replacements: &mut Vec<Replacement>,
+    fourcc: Fourcc,
+    bounds: &AtomBounds,
+    diff: i64,
+) -> i64 {
+    let new_len = bounds.len() as i64 + diff;
+    if bounds.ext() || !needs_ext(new_len as u64) {
+        return diff;
+    }
+
+    let mut data = u32::to_be_bytes(1).to_vec();
+    data.extend_from_slice(&*fourcc);
+    data.extend_from_slice(&u64::to_be_bytes(new_len as u64 + 8));
+    replacements.push(Replacement { start: bounds.pos(), end: bounds.pos() + 8, data });
+    diff + 8
+}
+
+/// Attempts to turn the item list (`ilst`) of the first top level metadata atom containing one into
+/// a free atom. Its items were read into the tag and are now written to the movie atom, so keeping
+/// them would duplicate them, and let removed items reappear when reading the file again.
+fn free_file_level_ilst(file: &File) -> crate::Result<()> {
+    let mut reader = BufReader::new(file);
+    let reader = &mut reader;
+
+    reader.seek(SeekFrom::Start(0))?;
+    Ftyp::parse(reader)?;
+
+    let len = data::remaining_stream_len(reader)?;
+    let mut ilst = None;
+    let mut parsed_bytes = 0;
 
-    if let Some(a) = new_udta {
-        a.write(&mut writer)?;
-    } else if let Some(a) = new_meta {
-        a.write(&mut writer)?;
-    } else {
-        if let Some(a) = new_hdlr {
-            a.write(&mut writer)?;
+    while parsed_bytes < len && ilst.is_none() {
+        let head = parse_top_level_head(reader, len - parsed_bytes)?;
+        let pos = reader.stream_position()? - head.head_len();
+
+        if head.fourcc() == METADATA {
+            ilst = Meta::find(reader, head.size())?.ilst;
         }
-        new_ilst.write(&mut writer)?;
+        reader.seek(SeekFrom::Start(pos + head.len()))?;
+
+        parsed_bytes += head.len();
+    }
+
+    if let Some(ilst) = ilst {
+        let mut writer = BufWriter::new(file);
+        writer.seek(SeekFrom::Start(ilst.pos() + 4))?;
+        writer.write_all(&*FREE)?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Attempts to write the XMP metadata to the file inside a user extension atom, see
+/// [`write_top_level_atom_to`].
+fn write_xmp_to(file: &File, xmp: Option<&[u8]>) -> crate::Result<()> {
+    let new_uuid = match xmp {
+        Some(d) => Some(encode_atom(&Uuid::xmp(d.to_vec()))?),
+        None => None,
+    };
+    let is_xmp = |reader: &mut BufReader<&File>, head: Head| {
+        Ok(Uuid::find(reader, head.size())?.uuid == XMP_UUID)
+    };
+    write_top_level_atom_to(file, USER_EXTENSION, is_xmp, new_uuid, "XMP metadata")
+}
+
+/// Attempts to write the ID3v2 tag with the packed language to the file inside an ID3v2 atom
+/// (`ID32`), contained in a top level metadata atom with an ID3v2 handler, see
+/// [`write_top_level_atom_to`]. Other top level metadata atoms are left as they are.
+fn write_id3v2_to(file: &File, id3v2: Option<(u16, &[u8])>) -> crate::Result<()> {
+    let new_meta = match id3v2 {
+        Some((language, d)) => {
+            let id32 = Id32 { language, data: d.to_vec() };
+            Some(encode_atom(&Meta::id3v2(id32))?)
+        }
+        None => None,
+    };
+    let is_id3v2 = |reader: &mut BufReader<&File>, head: Head| {
+        let meta = Meta::find(reader, head.size())?;
+        let hdlr = match meta.hdlr {
+            Some(h) if h.size().content_len() >= 12 => h,
+            _ => return Ok(false),
+        };
+        reader.seek(SeekFrom::Start(hdlr.content_pos() + 8))?;
+        let mut subtype = [0u8; 4];
+        reader.read_exact(&mut subtype)?;
+        Ok(Fourcc(subtype) == ID3V2)
+    };
+    write_top_level_atom_to(file, METADATA, is_id3v2, new_meta, "an ID3v2 tag")
+}
+
+/// Attempts to encode the atom.
+fn encode_atom(atom: &impl WriteAtom) -> crate::Result<Vec<u8>> {
+    let mut buf = Cursor::new(Vec::new());
+    atom.write(&mut buf)?;
+    Ok(buf.into_inner())
+}
+
+/// Attempts to replace the first top level atom with the fourcc that matches the predicate with
+/// the new encoded atom, or to remove it if there is none. An existing atom is only overwritten
+/// if its length doesn't change, otherwise it is turned into a free atom and the new one is
+/// appended to the end of the file. This way no chunk offsets have to be adjusted.
+fn write_top_level_atom_to(
+    file: &File,
+    fourcc: Fourcc,
+    is_match: impl Fn(&mut BufReader<&File>, Head) -> crate::Result<bool>,
+    new: Option<Vec<u8>>,
+    description: &str,
+) -> crate::Result<()> {
+    let mut reader = BufReader::new(file);
+    let reader = &mut reader;
+
+    reader.seek(SeekFrom::Start(0))?;
+    Ftyp::parse(reader)?;
+
+    let len = data::remaining_stream_len(reader)?;
+    let mut old = None;
+    let mut last_atom = None;
+    let mut parsed_bytes = 0;
+
+    while parsed_bytes < len {
+        let head = parse_top_level_head(reader, len - parsed_bytes)?;
+        let pos = reader.stream_position()? - head.head_len();
+        let bounds = AtomBounds::new(pos, head.size());
+
+        if head.fourcc() == fourcc && old.is_none() && is_match(reader, head)? {
+            old = Some(bounds.clone());
+        }
+        reader.seek(SeekFrom::Start(bounds.end()))?;
+
+        last_atom = Some(bounds);
+        parsed_bytes += head.len();
+    }
+
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    let mut writer = BufWriter::new(file);
+    let mut appended = None;
+
+    match (old, new) {
+        (Some(old), Some(new)) if old.len() == new.len() as u64 => {
+            writer.seek(SeekFrom::Start(old.pos()))?;
+            writer.write_all(&new)?;
+        }
+        (Some(old), new) => {
+            if old.end() == file_len {
+                file.set_len(old.pos())?;
+                last_atom = None;
+            } else {
+                writer.seek(SeekFrom::Start(old.pos() + 4))?;
+                writer.write_all(&*FREE)?;
+            }
+            appended = new;
+        }
+        (None, new) => appended = new,
+    }
+
+    if let Some(new) = appended {
+        // The last atom might have a length of 0, meaning it extends to the end of the file, which
+        // would then include the appended atom. So its length is written explicitly.
+        if let Some(last) = last_atom.filter(|a| !a.ext()) {
+            if last.len() > u32::MAX as u64 {
+                return Err(crate::Error::new(
+                    crate::ErrorKind::UnwritableData,
+                    format!(
+                        "Unable to append {} after an atom that extends to the end of the file",
+                        description,
+                    ),
+                ));
+            }
+            writer.seek(SeekFrom::Start(last.pos()))?;
+            writer.write_all(&u32::to_be_bytes(last.len() as u32))?;
+        }
+
+        writer.seek(SeekFrom::End(0))?;
+        writer.write_all(&new)?;
     }
 
-    // writing moved data
-    writer.seek(SeekFrom::Start((moved_data_start as i64 + len_diff) as u64))?;
-    writer.write_all(&moved_data)?;
     writer.flush()?;
 
     Ok(())
 }
 
+/// Returns the user data atom containing the metadata atoms, as it is written to a movie atom
+/// that doesn't contain one yet.
+fn metadata_udta(atoms: &[AtomData]) -> Udta<'_> {
+    let meta = Meta {
+        hdlr: Some(Meta::hdlr()),
+        keys: None,
+        ilst: Some(Ilst::Borrowed(atoms)),
+        id32: None,
+    };
+    Udta { meta: Some(meta), assets: Vec::new() }
+}
+
+/// Returns the length of the user data atom containing the metadata atoms, as it would be
+/// written.
+pub(crate) fn metadata_len(atoms: &[AtomData]) -> u64 {
+    metadata_udta(atoms).len()
+}
+
+/// Returns the length of the user extension atom containing the XMP packet, as it would be
+/// written.
+pub(crate) fn xmp_len(xmp: &[u8]) -> u64 {
+    Size::from(16 + xmp.len() as u64).len()
+}
+
+/// Returns the length of the top level metadata atom containing the ID3v2 tag, as it would be
+/// written.
+pub(crate) fn id3v2_len(id3v2: &[u8]) -> u64 {
+    let id32_len = Size::from(6 + id3v2.len() as u64).len();
+    Size::from(4 + Meta::id3v2_hdlr().len() + id32_len).len()
+}
+
+/// Attempts to encode the user data atom containing the metadata atoms, as it would be written.
+pub(crate) fn encode_metadata(atoms: &[AtomData]) -> crate::Result<Vec<u8>> {
+    let mut buf = Cursor::new(Vec::new());
+    metadata_udta(atoms).write(&mut buf)?;
+    Ok(buf.into_inner())
+}
+
 /// Attempts to dump the metadata atoms to the writer. This doesn't include a complete MPEG-4
 /// container hierarchy and won't result in a usable file.
-pub(crate) fn dump_tag_to(writer: &mut impl Write, atoms: &[AtomData]) -> crate::Result<()> {
+pub(crate) fn dump_tag_to(
+    writer: &mut impl Write,
+    atoms: &[AtomData],
+    xmp: Option<&[u8]>,
+    id3v2: Option<(u16, &[u8])>,
+) -> crate::Result<()> {
     let ftyp = Ftyp("M4A \u{0}\u{0}\u{2}\u{0}isomiso2".to_owned());
     #[rustfmt::skip]
     let moov = Moov {
@@ -535,13 +1618,130 @@ pub(crate) fn dump_tag_to(writer: &mut impl Write, atoms: &[AtomData]) -> crate:
             meta: Some(Meta {
                 hdlr: Some(Meta::hdlr()),
                 ilst: Some(Ilst::Borrowed(atoms)),
+                ..Default::default()
             }),
+            ..Default::default()
         }),
         ..Default::default()
     };
 
-    ftyp.write(writer)?;
-    moov.write(writer)?;
+    // the atoms are written to a buffer first, since container lengths are backpatched
+    let mut buf = Cursor::new(Vec::new());
+    ftyp.write(&mut buf)?;
+    moov.write(&mut buf)?;
+    if let Some(d) = xmp {
+        Uuid::xmp(d.to_vec()).write(&mut buf)?;
+    }
+    if let Some((language, d)) = id3v2 {
+        Meta::id3v2(Id32 { language, data: d.to_vec() }).write(&mut buf)?;
+    }
+    writer.write_all(&buf.into_inner())?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns the data of a moov atom at the position containing a udta atom, whose content
+    /// starts with the 8 byte child, and the bounds of both, which declare the lengths.
+    fn moov_with_udta(pos: u64, moov_len: u64, udta_len: u64) -> (Vec<u8>, AtomBounds, AtomBounds) {
+        let mut data = u32::to_be_bytes(moov_len as u32).to_vec();
+        data.extend_from_slice(b"moov");
+        data.extend_from_slice(&u32::to_be_bytes(udta_len as u32));
+        data.extend_from_slice(b"udta");
+        data.extend_from_slice(b"oldchild");
+        let moov = AtomBounds::new(pos, Size::from(moov_len - 8));
+        let udta = AtomBounds::new(pos + 8, Size::from(udta_len - 8));
+        (data, moov, udta)
+    }
+
+    /// Replaces the child of the udta atom with the new data, like the writer does with the
+    /// item list, and returns the new data of the moov atom.
+    fn replace_udta_child(pos: u64, moov_len: u64, udta_len: u64, new: &[u8]) -> Vec<u8> {
+        let (mut data, moov, udta) = moov_with_udta(pos, moov_len, udta_len);
+        let mut replacements =
+            vec![Replacement { start: pos + 16, end: pos + 24, data: new.to_vec() }];
+        let mut container_diffs = Vec::new();
+
+        let udta_diff = extend_head(&mut replacements, USER_DATA, &udta, new.len() as i64 - 8);
+        container_diffs.push((&udta, udta_diff));
+        let len_diff = replacements.iter().map(|r| r.len_diff()).sum();
+        let len_diff = extend_head(&mut replacements, MOVIE, &moov, len_diff);
+        container_diffs.push((&moov, len_diff));
+
+        apply_replacements(&mut data, pos, &container_diffs, replacements);
+        data
+    }
+
+    #[test]
+    fn replace_within_standard_heads() {
+        let data = replace_udta_child(32, 1000, 500, b"newchild plus 16");
+        let mut expected = u32::to_be_bytes(1008).to_vec();
+        expected.extend_from_slice(b"moov");
+        expected.extend_from_slice(&u32::to_be_bytes(508));
+        expected.extend_from_slice(b"udta");
+        expected.extend_from_slice(b"newchild plus 16");
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn replace_extending_heads() {
+        // the udta atom grows beyond the maximum length of a standard head by 4 bytes, and the
+        // moov atom additionally by the 8 bytes the extended udta head adds
+        let max = u32::MAX as u64;
+        let data = replace_udta_child(32, max - 4, max - 12, b"newchild plus 16 bytes!!");
+        let mut expected = u32::to_be_bytes(1).to_vec();
+        expected.extend_from_slice(b"moov");
+        expected.extend_from_slice(&u64::to_be_bytes(max + 28));
+        expected.extend_from_slice(&u32::to_be_bytes(1));
+        expected.extend_from_slice(b"udta");
+        expected.extend_from_slice(&u64::to_be_bytes(max + 12));
+        expected.extend_from_slice(b"newchild plus 16 bytes!!");
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn replace_extending_moov_head() {
+        // only the moov atom exceeds the maximum length of a standard head
+        let max = u32::MAX as u64;
+        let data = replace_udta_child(32, max - 4, 500, b"newchild plus 16");
+        let mut expected = u32::to_be_bytes(1).to_vec();
+        expected.extend_from_slice(b"moov");
+        expected.extend_from_slice(&u64::to_be_bytes(max + 12));
+        expected.extend_from_slice(&u32::to_be_bytes(508));
+        expected.extend_from_slice(b"udta");
+        expected.extend_from_slice(b"newchild plus 16");
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn write_extended_heads() {
+        let path = "target/write_extended_heads_unit.m4a";
+        std::fs::copy("files/sample.m4a", path).unwrap();
+
+        // artwork exceeding a lowered maximum length of a standard head, like artwork of more
+        // than 4 GiB would
+        MAX_STANDARD_LEN.with(|l| l.set(4096));
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.resize(5000, 0);
+        let mut tag = Tag::read_from_path(path).unwrap();
+        tag.add_data(ident::ARTWORK, Data::Png(png.clone()));
+        tag.write_to_path(path).unwrap();
+        MAX_STANDARD_LEN.with(|l| l.set(u32::MAX as u64));
+
+        let data = std::fs::read(path).unwrap();
+        for fourcc in [b"moov", b"udta", b"meta", b"ilst", b"covr"].iter() {
+            let pos = data.windows(4).position(|w| w == *fourcc).unwrap() - 4;
+            assert_eq!(data[pos..pos + 4], [0, 0, 0, 1]);
+        }
+        let png_pos = data.windows(8).rposition(|w| w == &png[..8]).unwrap();
+        assert_eq!(data[png_pos - 24..png_pos - 16], *b"\0\0\0\x01data");
+
+        let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+        assert_eq!(tag.title(), Some("TEST TITLE"));
+        assert_eq!(tag.artwork_count(), 2);
+        assert_eq!(tag.artworks().nth(1).unwrap().data, &png[..]);
+    }
+}
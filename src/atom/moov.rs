@@ -4,7 +4,9 @@ use super::*;
 pub struct Moov<'a> {
     pub mvhd: Option<Mvhd>,
     pub trak: Vec<Trak>,
+    pub trak_skipped: bool,
     pub udta: Option<Udta<'a>>,
+    pub meta: Option<Meta<'a>>,
 }
 
 impl Atom for Moov<'_> {
@@ -12,20 +14,34 @@ impl Atom for Moov<'_> {
 }
 
 impl ParseAtom for Moov<'_> {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         let mut moov = Self::default();
+        let mut parsed_tracks = 0;
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
-                MOVIE_HEADER => moov.mvhd = Some(Mvhd::parse(reader, head.size())?),
-                TRACK => moov.trak.push(Trak::parse(reader, head.size())?),
-                USER_DATA => moov.udta = Some(Udta::parse(reader, head.size())?),
-                _ => {
-                    reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+                MOVIE_HEADER => moov.mvhd = Some(Mvhd::parse(reader, ctx, head.size())?),
+                TRACK if matches!(ctx.max_tracks, Some(m) if parsed_tracks >= m) => {
+                    moov.trak_skipped = true;
+                    ctx.skip_atom(reader, head)?;
+                }
+                TRACK => {
+                    let trak = Trak::parse(reader, ctx, head.size())?;
+                    if ctx.includes_handler(trak.handler()) {
+                        parsed_tracks += 1;
+                    }
+                    moov.trak.push(trak);
                 }
+                USER_DATA => moov.udta = Some(Udta::parse(reader, ctx, head.size())?),
+                METADATA => moov.meta = Some(Meta::parse(reader, ctx, head.size())?),
+                _ => ctx.skip_atom(reader, head)?,
             }
 
             parsed_bytes += head.len();
@@ -36,12 +52,13 @@ impl ParseAtom for Moov<'_> {
 }
 
 impl WriteAtom for Moov<'_> {
-    fn write_atom(&self, writer: &mut impl Write) -> crate::Result<()> {
-        self.write_head(writer)?;
-        if let Some(a) = &self.udta {
-            a.write(writer)?;
-        }
-        Ok(())
+    fn write_atom(&self, writer: &mut (impl Write + Seek)) -> crate::Result<()> {
+        write_container(writer, Self::FOURCC, self.size(), |writer| {
+            if let Some(a) = &self.udta {
+                a.write(writer)?;
+            }
+            Ok(())
+        })
     }
 
     fn size(&self) -> Size {
@@ -51,8 +68,10 @@ impl WriteAtom for Moov<'_> {
 
 pub struct MoovBounds {
     pub bounds: AtomBounds,
+    pub mvhd: Option<AtomBounds>,
     pub trak: Vec<TrakBounds>,
     pub udta: Option<UdtaBounds>,
+    pub meta: Option<MetaBounds>,
 }
 
 impl Deref for MoovBounds {
@@ -68,16 +87,20 @@ impl FindAtom for Moov<'_> {
 
     fn find_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self::Bounds> {
         let bounds = find_bounds(reader, size)?;
+        let mut mvhd = None;
         let mut trak = Vec::new();
         let mut udta = None;
+        let mut meta = None;
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
+                MOVIE_HEADER => mvhd = Some(skip_bounds(reader, head.size())?),
                 TRACK => trak.push(Trak::find(reader, head.size())?),
                 USER_DATA => udta = Some(Udta::find(reader, head.size())?),
+                METADATA => meta = Some(Meta::find(reader, head.size())?),
                 _ => {
                     reader.seek(SeekFrom::Current(head.content_len() as i64))?;
                 }
@@ -86,6 +109,6 @@ impl FindAtom for Moov<'_> {
             parsed_bytes += head.len();
         }
 
-        Ok(Self::Bounds { bounds, trak, udta })
+        Ok(Self::Bounds { bounds, mvhd, trak, udta, meta })
     }
 }
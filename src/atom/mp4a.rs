@@ -71,13 +71,17 @@ impl Atom for Mp4a {
 }
 
 impl ParseAtom for Mp4a {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        _ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         let bounds = find_bounds(reader, size)?;
         let mut mp4a = Self::default();
 
         reader.seek(SeekFrom::Current(28))?;
 
-        let head = parse_head(reader)?;
+        let head = parse_head(reader, size.content_len().saturating_sub(28))?;
         if head.fourcc() != ELEMENTARY_STREAM_DESCRIPTION {
             return Err(crate::Error::new(
                 crate::ErrorKind::AtomNotFound(ELEMENTARY_STREAM_DESCRIPTION),
@@ -127,7 +131,7 @@ fn parse_esds(reader: &mut (impl Read + Seek), info: &mut Mp4a, size: Size) -> c
         ));
     }
 
-    let max_len = size.content_len() - 4 - head_len;
+    let max_len = size.content_len().saturating_sub(4 + head_len);
     parse_es_desc(reader, info, min(desc_len, max_len))?;
 
     Ok(())
@@ -7,6 +7,8 @@ use super::*;
 pub struct Mvhd {
     /// The duration of the track.
     pub duration: Duration,
+    /// The fields of the movie header.
+    pub header: MovieHeader,
 }
 
 impl Atom for Mvhd {
@@ -14,11 +16,16 @@ impl Atom for Mvhd {
 }
 
 impl ParseAtom for Mvhd {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        _ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         let bounds = find_bounds(reader, size)?;
         let mut mvhd = Self::default();
 
         let (version, _) = parse_full_head(reader)?;
+        mvhd.header.version = version;
         match version {
             0 => {
                 // # Version 0
@@ -29,11 +36,10 @@ impl ParseAtom for Mvhd {
                 // 4 bytes time scale
                 // 4 bytes duration
                 // ...
-                reader.seek(SeekFrom::Current(8))?;
-                let timescale = reader.read_u32()? as u64;
-                let duration = reader.read_u32()? as u64;
-
-                mvhd.duration = Duration::from_nanos(duration * 1_000_000_000 / timescale);
+                mvhd.header.creation_time = reader.read_u32()? as u64;
+                mvhd.header.modification_time = reader.read_u32()? as u64;
+                mvhd.header.timescale = reader.read_u32()?;
+                mvhd.header.duration = reader.read_u32()? as u64;
             }
             1 => {
                 // # Version 1
@@ -44,11 +50,10 @@ impl ParseAtom for Mvhd {
                 // 4 bytes time scale
                 // 8 bytes duration
                 // ...
-                reader.seek(SeekFrom::Current(16))?;
-                let timescale = reader.read_u32()? as u64;
-                let duration = reader.read_u64()?;
-
-                mvhd.duration = Duration::from_nanos(duration * 1_000_000_000 / timescale);
+                mvhd.header.creation_time = reader.read_u64()?;
+                mvhd.header.modification_time = reader.read_u64()?;
+                mvhd.header.timescale = reader.read_u32()?;
+                mvhd.header.duration = reader.read_u64()?;
             }
             v => {
                 return Err(crate::Error::new(
@@ -58,6 +63,24 @@ impl ParseAtom for Mvhd {
             }
         }
 
+        let timescale = mvhd.header.timescale as u64;
+        mvhd.duration = Duration::from_nanos(mvhd.header.duration * 1_000_000_000 / timescale);
+
+        // # Both versions
+        // ...
+        // 4 bytes preferred rate
+        // 2 bytes preferred volume
+        // 10 bytes reserved
+        // 36 bytes matrix
+        // 24 bytes pre-defined
+        // 4 bytes next track id
+        if bounds.end() >= reader.stream_position()? + 80 {
+            mvhd.header.rate = reader.read_u32()?;
+            mvhd.header.volume = reader.read_u16()?;
+            reader.seek(SeekFrom::Current(70))?;
+            mvhd.header.next_track_id = reader.read_u32()?;
+        }
+
         seek_to_end(reader, &bounds)?;
 
         Ok(mvhd)
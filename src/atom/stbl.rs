@@ -3,6 +3,8 @@ use super::*;
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Stbl {
     pub stsd: Option<Stsd>,
+    pub stsz: Option<Stsz>,
+    pub stts: Option<Stts>,
 }
 
 impl Atom for Stbl {
@@ -10,20 +12,30 @@ impl Atom for Stbl {
 }
 
 impl ParseAtom for Stbl {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         let mut stbl = Self::default();
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
                 SAMPLE_TABLE_SAMPLE_DESCRIPTION => {
-                    stbl.stsd = Some(Stsd::parse(reader, head.size())?)
+                    stbl.stsd = Some(Stsd::parse(reader, ctx, head.size())?)
                 }
-                _ => {
-                    reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+                SAMPLE_TABLE_SAMPLE_SIZE => {
+                    check_content_len(reader, head, 12)?;
+                    stbl.stsz = Some(Stsz::parse(reader, ctx, head.size())?)
+                }
+                SAMPLE_TABLE_TIME_TO_SAMPLE => {
+                    check_content_len(reader, head, 8)?;
+                    stbl.stts = Some(Stts::parse(reader, ctx, head.size())?)
                 }
+                _ => ctx.skip_atom(reader, head)?,
             }
 
             parsed_bytes += head.len();
@@ -49,7 +61,7 @@ impl FindAtom for Stbl {
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
                 SAMPLE_TABLE_CHUNK_OFFSET => stco = Some(Stco::find(reader, head.size())?),
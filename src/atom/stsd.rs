@@ -3,6 +3,9 @@ use super::*;
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Stsd {
     pub mp4a: Option<Mp4a>,
+    pub alac: Option<Alac>,
+    /// Whether the sample description contains a protected (`drms` or `enca`) sample entry.
+    pub protected: bool,
 }
 
 impl Atom for Stsd {
@@ -10,7 +13,11 @@ impl Atom for Stsd {
 }
 
 impl ParseAtom for Stsd {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         let (version, _) = parse_full_head(reader)?;
 
         if version != 0 {
@@ -26,13 +33,19 @@ impl ParseAtom for Stsd {
         let mut parsed_bytes = 8;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
-                MP4_AUDIO => stsd.mp4a = Some(Mp4a::parse(reader, head.size())?),
-                _ => {
-                    reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+                MP4_AUDIO => stsd.mp4a = Some(Mp4a::parse(reader, ctx, head.size())?),
+                APPLE_LOSSLESS_AUDIO => stsd.alac = Some(Alac::parse(reader, ctx, head.size())?),
+                DRM_AUDIO | ENCRYPTED_AUDIO => {
+                    // protected sample entries share the layout of mp4a, but contain an additional
+                    // protection scheme info (`sinf`) atom, which is skipped
+                    stsd.protected = true;
+                    ctx.record_atom();
+                    stsd.mp4a = Some(Mp4a::parse_atom(reader, ctx, head.size())?);
                 }
+                _ => ctx.skip_atom(reader, head)?,
             }
 
             parsed_bytes += head.len();
@@ -0,0 +1,48 @@
+use super::*;
+
+/// A struct representing a sample size atom (`stsz`).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stsz {
+    /// The size of all samples, or 0 if the samples have different sizes, which are stored in a
+    /// table.
+    pub sample_size: u32,
+    /// The number of samples.
+    pub sample_count: u64,
+}
+
+impl Atom for Stsz {
+    const FOURCC: Fourcc = SAMPLE_TABLE_SAMPLE_SIZE;
+}
+
+impl ParseAtom for Stsz {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        _ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
+        let bounds = find_bounds(reader, size)?;
+        let (version, _) = parse_full_head(reader)?;
+
+        if version != 0 {
+            return Err(crate::Error::new(
+                ErrorKind::UnknownVersion(version),
+                "Unknown sample size (stsz) version".to_owned(),
+            ));
+        }
+
+        // 4 bytes sample size
+        // 4 bytes sample count
+        // if sample size is 0:
+        //     4 bytes sample size of each sample
+        let sample_size = reader.read_u32()?;
+        let mut sample_count = reader.read_u32()? as u64;
+        if sample_size == 0 {
+            // the table might be truncated
+            sample_count = sample_count.min((size.content_len() - 12) / 4);
+        }
+
+        seek_to_end(reader, &bounds)?;
+
+        Ok(Self { sample_size, sample_count })
+    }
+}
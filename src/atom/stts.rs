@@ -0,0 +1,47 @@
+use super::*;
+
+/// A struct representing a time to sample atom (`stts`).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stts {
+    /// The entries of the table, each consisting of a number of consecutive samples and their
+    /// duration in units of the media timescale.
+    pub entries: Vec<(u32, u32)>,
+}
+
+impl Atom for Stts {
+    const FOURCC: Fourcc = SAMPLE_TABLE_TIME_TO_SAMPLE;
+}
+
+impl ParseAtom for Stts {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        _ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
+        let bounds = find_bounds(reader, size)?;
+        let (version, _) = parse_full_head(reader)?;
+
+        if version != 0 {
+            return Err(crate::Error::new(
+                ErrorKind::UnknownVersion(version),
+                "Unknown time to sample (stts) version".to_owned(),
+            ));
+        }
+
+        // 4 bytes entry count
+        // for each entry:
+        //     4 bytes sample count
+        //     4 bytes sample duration
+        let entry_count = reader.read_u32()? as u64;
+        // the table might be truncated
+        let entry_count = entry_count.min((size.content_len() - 8) / 8);
+        let mut entries = Vec::with_capacity(entry_count as usize);
+        for _ in 0..entry_count {
+            entries.push((reader.read_u32()?, reader.read_u32()?));
+        }
+
+        seek_to_end(reader, &bounds)?;
+
+        Ok(Self { entries })
+    }
+}
@@ -10,18 +10,20 @@ impl Atom for Trak {
 }
 
 impl ParseAtom for Trak {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         let mut trak = Self::default();
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
-                MEDIA => trak.mdia = Some(Mdia::parse(reader, head.size())?),
-                _ => {
-                    reader.seek(SeekFrom::Current(head.content_len() as i64))?;
-                }
+                MEDIA => trak.mdia = Some(Mdia::parse(reader, ctx, head.size())?),
+                _ => ctx.skip_atom(reader, head)?,
             }
 
             parsed_bytes += head.len();
@@ -31,6 +33,13 @@ impl ParseAtom for Trak {
     }
 }
 
+impl Trak {
+    /// Returns the media handler type of the track, like `soun` for audio.
+    pub fn handler(&self) -> Option<Fourcc> {
+        self.mdia.as_ref()?.hdlr.as_ref()?.subtype()
+    }
+}
+
 pub struct TrakBounds {
     pub bounds: AtomBounds,
     pub mdia: Option<MdiaBounds>,
@@ -45,7 +54,7 @@ impl FindAtom for Trak {
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
                 MEDIA => mdia = Some(Mdia::find(reader, head.size())?),
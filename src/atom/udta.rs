@@ -3,6 +3,7 @@ use super::*;
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Udta<'a> {
     pub meta: Option<Meta<'a>>,
+    pub assets: Vec<Asset>,
 }
 
 impl Atom for Udta<'_> {
@@ -10,18 +11,23 @@ impl Atom for Udta<'_> {
 }
 
 impl ParseAtom for Udta<'_> {
-    fn parse_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self> {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
         let mut udta = Self::default();
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
-                METADATA => udta.meta = Some(Meta::parse(reader, head.size())?),
-                _ => {
-                    reader.seek(SeekFrom::Current(head.content_len() as i64))?;
+                METADATA => udta.meta = Some(Meta::parse(reader, ctx, head.size())?),
+                f if Asset::ident_of(f).is_some() => {
+                    udta.assets.extend(Asset::parse(reader, ctx, head)?);
                 }
+                _ => ctx.skip_atom(reader, head)?,
             }
 
             parsed_bytes += head.len();
@@ -32,12 +38,13 @@ impl ParseAtom for Udta<'_> {
 }
 
 impl WriteAtom for Udta<'_> {
-    fn write_atom(&self, writer: &mut impl Write) -> crate::Result<()> {
-        self.write_head(writer)?;
-        if let Some(a) = &self.meta {
-            a.write(writer)?;
-        }
-        Ok(())
+    fn write_atom(&self, writer: &mut (impl Write + Seek)) -> crate::Result<()> {
+        write_container(writer, Self::FOURCC, self.size(), |writer| {
+            if let Some(a) = &self.meta {
+                a.write(writer)?;
+            }
+            Ok(())
+        })
     }
 
     fn size(&self) -> Size {
@@ -48,6 +55,10 @@ impl WriteAtom for Udta<'_> {
 pub struct UdtaBounds {
     pub bounds: AtomBounds,
     pub meta: Option<MetaBounds>,
+    /// The bounds of the 3GPP asset information atoms.
+    pub assets: Vec<AtomBounds>,
+    /// The bounds of the QuickTime location atom (`©xyz`).
+    pub location: Option<AtomBounds>,
 }
 
 impl Deref for UdtaBounds {
@@ -64,13 +75,17 @@ impl FindAtom for Udta<'_> {
     fn find_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self::Bounds> {
         let bounds = find_bounds(reader, size)?;
         let mut meta = None;
+        let mut assets = Vec::new();
+        let mut location = None;
         let mut parsed_bytes = 0;
 
         while parsed_bytes < size.content_len() {
-            let head = parse_head(reader)?;
+            let head = parse_head(reader, size.content_len() - parsed_bytes)?;
 
             match head.fourcc() {
                 METADATA => meta = Some(Meta::find(reader, head.size())?),
+                f if Asset::ident_of(f).is_some() => assets.push(skip_bounds(reader, head.size())?),
+                LOCATION => location = Some(skip_bounds(reader, head.size())?),
                 _ => {
                     reader.seek(SeekFrom::Current(head.content_len() as i64))?;
                 }
@@ -79,6 +94,6 @@ impl FindAtom for Udta<'_> {
             parsed_bytes += head.len();
         }
 
-        Ok(Self::Bounds { bounds, meta })
+        Ok(Self::Bounds { bounds, meta, assets, location })
     }
 }
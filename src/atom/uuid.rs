@@ -0,0 +1,94 @@
+use super::*;
+
+/// The uuid of the box containing XMP metadata (`BE7ACFCB-97A9-42E8-9C71-999491E3AFAC`).
+pub const XMP_UUID: [u8; 16] = [
+    0xBE, 0x7A, 0xCF, 0xCB, 0x97, 0xA9, 0x42, 0xE8, 0x9C, 0x71, 0x99, 0x94, 0x91, 0xE3, 0xAF, 0xAC,
+];
+
+/// A struct representing a user extension atom (`uuid`). The content is only read if the uuid is
+/// known.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Uuid {
+    pub uuid: [u8; 16],
+    pub data: Vec<u8>,
+}
+
+impl Atom for Uuid {
+    const FOURCC: Fourcc = USER_EXTENSION;
+}
+
+impl ParseAtom for Uuid {
+    fn parse_atom(
+        reader: &mut (impl Read + Seek),
+        ctx: &ReadCtx,
+        size: Size,
+    ) -> crate::Result<Self> {
+        let bounds = find_bounds(reader, size)?;
+        let mut uuid = Self::default();
+
+        // other user extensions are unrelated to the metadata, so a malformed one is skipped
+        if size.content_len() < 16 {
+            ctx.warn(|| "User extension (uuid) atom is too short to contain a uuid".to_owned());
+            seek_to_end(reader, &bounds)?;
+            return Ok(uuid);
+        }
+
+        reader.read_exact(&mut uuid.uuid)?;
+        if uuid.uuid == XMP_UUID {
+            uuid.data = reader.read_u8_vec(size.content_len() - 16)?;
+        }
+
+        seek_to_end(reader, &bounds)?;
+
+        Ok(uuid)
+    }
+}
+
+impl WriteAtom for Uuid {
+    fn write_atom(&self, writer: &mut (impl Write + Seek)) -> crate::Result<()> {
+        self.write_head(writer)?;
+        writer.write_all(&self.uuid)?;
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+
+    fn size(&self) -> Size {
+        Size::from(16 + self.data.len() as u64)
+    }
+}
+
+impl Uuid {
+    pub fn xmp(data: Vec<u8>) -> Self {
+        Self { uuid: XMP_UUID, data }
+    }
+}
+
+pub struct UuidBounds {
+    pub bounds: AtomBounds,
+    pub uuid: [u8; 16],
+}
+
+impl Deref for UuidBounds {
+    type Target = AtomBounds;
+
+    fn deref(&self) -> &Self::Target {
+        &self.bounds
+    }
+}
+
+impl FindAtom for Uuid {
+    type Bounds = UuidBounds;
+
+    fn find_atom(reader: &mut (impl Read + Seek), size: Size) -> crate::Result<Self::Bounds> {
+        let bounds = find_bounds(reader, size)?;
+        let mut uuid = [0u8; 16];
+
+        if size.content_len() >= 16 {
+            reader.read_exact(&mut uuid)?;
+        }
+
+        seek_to_end(reader, &bounds)?;
+
+        Ok(Self::Bounds { bounds, uuid })
+    }
+}
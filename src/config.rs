@@ -0,0 +1,248 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{ident, Data, Fourcc};
+
+/// A struct configuring how metadata is read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReadConfig {
+    /// Whether malformed metadata that can be recovered is read instead of rejected. For example
+    /// item list entries (`ilst`) that store a string directly, without a wrapping data atom
+    /// (`data`), are read as utf-8 strings, an item list whose length doesn't match its items is
+    /// read up to the bounds of its parent, and the last byte of utf-16 data of an odd length is
+    /// dropped.
+    pub lenient: bool,
+    /// Custom strategies used to decode the data of atoms with a registered fourcc.
+    pub types: TypeRegistry,
+    /// The maximum number of tracks (`trak`) whose media information is parsed. Further tracks are
+    /// skipped. Tracks excluded by the [`handler_filter`](Self::handler_filter) don't count
+    /// towards this limit.
+    pub max_tracks: Option<usize>,
+    /// The media handler types (`hdlr`), like `soun` for audio, of the tracks whose media
+    /// information is parsed. The media information of other tracks is skipped. If empty, all
+    /// tracks are parsed.
+    pub handler_filter: Vec<Fourcc>,
+    /// Whether reading fails with an error of kind
+    /// [`ErrorKind::NoAudioTrack`](crate::ErrorKind::NoAudioTrack) if the file only contains
+    /// tracks of other media types, like video. If `false`, the default, the metadata of such
+    /// files, for example silent videos tagged by iTunes, is read as usual.
+    pub require_audio_track: bool,
+    /// The fourccs of the atoms from which artwork is read, defaulting to only the artwork atom
+    /// (`covr`). Some taggers store artwork in atoms with their own fourcc, like `pict`, which
+    /// can be included here. Images of all of these atoms are read as artwork (`covr`), so they
+    /// are returned by [`Tag::artworks`](crate::Tag::artworks), and written as such.
+    pub artwork_atoms: Vec<Fourcc>,
+    /// The encoding, like `WINDOWS_1252`, used to decode strings of utf-8 data (`data`) that
+    /// aren't valid utf-8, which some older taggers produce by writing latin-1 text. If `None`,
+    /// reading such a string fails. Requires the `encoding_rs` feature.
+    #[cfg(feature = "encoding_rs")]
+    pub fallback_encoding: Option<&'static encoding_rs::Encoding>,
+}
+
+impl Default for ReadConfig {
+    fn default() -> Self {
+        Self {
+            lenient: false,
+            types: TypeRegistry::default(),
+            max_tracks: None,
+            handler_filter: Vec::new(),
+            require_audio_track: false,
+            artwork_atoms: vec![ident::ARTWORK],
+            #[cfg(feature = "encoding_rs")]
+            fallback_encoding: None,
+        }
+    }
+}
+
+impl ReadConfig {
+    /// Returns whether the media information of a track with the handler type is parsed. Tracks
+    /// without a handler are always parsed.
+    pub(crate) fn includes_handler(&self, handler: Option<Fourcc>) -> bool {
+        match handler {
+            Some(h) => self.handler_filter.is_empty() || self.handler_filter.contains(&h),
+            None => true,
+        }
+    }
+}
+
+/// A struct configuring how metadata is written.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WriteConfig {
+    /// The layout in which the metadata is written.
+    pub metadata_style: MetadataStyle,
+    /// How a standard genre (`gnre`) is handled, when a custom genre (`©gen`) is also present.
+    pub genre_policy: GenrePolicy,
+    /// Whether free atoms (`free`, `skip`) at the end of the existing item list (`ilst`) are
+    /// preserved as a single free atom. It shrinks when the metadata grows and grows when the
+    /// metadata shrinks, so the item list keeps its length and edits don't need to move the rest
+    /// of the file, as long as the metadata fits. Otherwise it keeps its length.
+    pub keep_ilst_padding: bool,
+    /// Whether utf-16 strings ([`Data::Utf16`]) are written with a leading byte order mark
+    /// (`0xFEFF`), which some players expect. iTunes doesn't write one. The byte order mark is
+    /// stripped when reading.
+    pub utf16_bom: bool,
+    /// The size of the buffer used to move the data following the movie atom (`moov`), like the
+    /// media data (`mdat`), when the metadata doesn't fit into the existing space. The data is
+    /// copied in chunks of this size, so it is never loaded into memory at once. Defaults to 64
+    /// KiB.
+    pub copy_buf_size: usize,
+    /// The quality, ranging from 1 to 100, with which helpers re-encode artwork as jpeg, like
+    /// `Tag::artworks_to_jpeg`, which requires the `image` feature. Defaults to 85.
+    pub artwork_jpeg_quality: u8,
+    /// Whether an ID3v2 tag is written to an ID3v2 atom (`ID32`).
+    pub id3v2: Id3v2Style,
+    /// Custom strategies used to encode the data of atoms with a registered fourcc.
+    pub types: TypeRegistry,
+}
+
+impl Default for WriteConfig {
+    fn default() -> Self {
+        Self {
+            metadata_style: MetadataStyle::default(),
+            genre_policy: GenrePolicy::default(),
+            keep_ilst_padding: false,
+            utf16_bom: false,
+            copy_buf_size: 64 * 1024,
+            artwork_jpeg_quality: 85,
+            id3v2: Id3v2Style::default(),
+            types: TypeRegistry::default(),
+        }
+    }
+}
+
+/// An enum describing the layout in which metadata is written.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MetadataStyle {
+    /// Metadata items are written to the iTunes style item list (`moov.udta.meta.ilst`), which is
+    /// keyed by fourcc and freeform identifiers. Items identified by a key aren't written and the
+    /// QuickTime metadata of the file is left as is.
+    #[default]
+    ITunes,
+    /// Additionally to the iTunes style item list, metadata items identified by a key are written
+    /// to the QuickTime metadata atom (`moov.meta`), which consists of a `mdta` handler, a table
+    /// of keys (`keys`) and an item list that is keyed by the index of the key. Existing QuickTime
+    /// metadata is replaced.
+    QuickTimeKeys,
+}
+
+/// An enum describing how conflicting standard (`gnre`) and custom (`©gen`) genres are written.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum GenrePolicy {
+    /// If a custom genre is present, standard genres aren't written, so players don't display a
+    /// stale standard genre.
+    #[default]
+    PreferText,
+    /// Both standard and custom genres are written as they are.
+    KeepBoth,
+}
+
+/// An enum describing whether an ID3v2 tag is written to an ID3v2 atom (`ID32`), which is stored
+/// inside a top level metadata atom (`meta`) with an `ID32` handler. The ID3v2 atom carries the
+/// language of the tag read from the file, or `und` if there is none.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Id3v2Style {
+    /// Existing ID3v2 atoms are left as they are.
+    #[default]
+    Keep,
+    /// The raw ID3v2 tag, see [`Tag::id3v2_box`](crate::Tag::id3v2_box), is written alongside the
+    /// item list (`ilst`). If the tag contains none, an existing ID3v2 atom is removed.
+    Raw,
+    /// An ID3v2.4 tag synthesized from the standard fields of the tag, like the title, artist or
+    /// track number, replaces the raw ID3v2 tag and is written alongside the item list. Requires
+    /// the `id3` feature, without which writing returns an error of kind
+    /// [`ErrorKind::UnwritableData`](crate::ErrorKind::UnwritableData).
+    Synthesized,
+    /// Like [`Self::Synthesized`], but the ID3v2 tag is written instead of the item list, which
+    /// is written empty. Requires the `id3` feature, like [`Self::Synthesized`].
+    SynthesizedOnly,
+}
+
+/// A strategy to decode and encode the data of atoms with a specific fourcc, which replaces the
+/// default interpretation based on the datatype code of the data atom (`data`).
+pub trait DataCodec: Send + Sync {
+    /// Decodes the content of a data atom, following its 8 byte header, containing the datatype
+    /// code and locale indicator.
+    fn decode(&self, datatype: u32, bytes: Vec<u8>) -> crate::Result<Data>;
+
+    /// Encodes the data before it is written. To write an arbitrary datatype code, return
+    /// [`Data::Unknown`].
+    fn encode(&self, data: &Data) -> crate::Result<Data>;
+}
+
+/// A registry mapping fourccs to custom strategies decoding and encoding their data.
+///
+/// # Example
+/// ```
+/// use mp4ameta::{ident, Data, DataCodec, Location, ReadConfig, TypeRegistry};
+///
+/// /// Decodes locations as normalized ISO 6709 strings.
+/// struct Iso6709;
+///
+/// impl DataCodec for Iso6709 {
+///     fn decode(&self, _datatype: u32, bytes: Vec<u8>) -> mp4ameta::Result<Data> {
+///         let location: Location = String::from_utf8(bytes)?.parse()?;
+///         Ok(Data::Utf8(location.to_string()))
+///     }
+///
+///     fn encode(&self, data: &Data) -> mp4ameta::Result<Data> {
+///         Ok(data.clone())
+///     }
+/// }
+///
+/// let mut types = TypeRegistry::new();
+/// types.register(ident::LOCATION, Iso6709);
+/// assert!(types.get(ident::LOCATION).is_some());
+///
+/// let cfg = ReadConfig { types, ..Default::default() };
+/// ```
+#[derive(Clone, Default)]
+pub struct TypeRegistry {
+    codecs: Vec<(Fourcc, Arc<dyn DataCodec>)>,
+}
+
+impl fmt::Debug for TypeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.codecs.iter().map(|(f, _)| f)).finish()
+    }
+}
+
+impl PartialEq for TypeRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        self.codecs.len() == other.codecs.len()
+            && self
+                .codecs
+                .iter()
+                .zip(other.codecs.iter())
+                .all(|((f1, c1), (f2, c2))| f1 == f2 && Arc::ptr_eq(c1, c2))
+    }
+}
+
+impl Eq for TypeRegistry {}
+
+impl TypeRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the codec for the fourcc, replacing a previously registered one.
+    pub fn register(&mut self, fourcc: Fourcc, codec: impl DataCodec + 'static) {
+        self.codecs.retain(|(f, _)| *f != fourcc);
+        self.codecs.push((fourcc, Arc::new(codec)));
+    }
+
+    /// Removes the codec registered for the fourcc.
+    pub fn unregister(&mut self, fourcc: Fourcc) {
+        self.codecs.retain(|(f, _)| *f != fourcc);
+    }
+
+    /// Returns the codec registered for the fourcc.
+    pub fn get(&self, fourcc: Fourcc) -> Option<&dyn DataCodec> {
+        self.codecs.iter().find(|(f, _)| *f == fourcc).map(|(_, c)| c.as_ref())
+    }
+
+    /// Returns whether no codec is registered.
+    pub fn is_empty(&self) -> bool {
+        self.codecs.is_empty()
+    }
+}
@@ -8,16 +8,47 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// Kinds of errors that may occur while performing metadata operations.
 #[derive(Debug)]
 pub enum ErrorKind {
+    /// An error kind indicating that artwork image data exceeds a size limit.
+    ArtworkTooLarge {
+        /// The length of the image data in bytes.
+        len: usize,
+        /// The maximum length of the image data in bytes.
+        limit: usize,
+    },
     /// An error kind indicating that an atom could not be found. Contains the atom's identifier.
     AtomNotFound(Fourcc),
     /// An error kind indicating that a descriptor could not be found. Contains the descriptor's tag.
     DescriptorNotFound(u8),
+    /// An error kind indicating that image data doesn't start with the signature of its declared
+    /// image format.
+    InvalidImageData,
     /// An error kind indicating that an IO error has occurred. Contains the original `io::Error`.
     Io(io::Error),
+    /// An error kind indicating that the declared length of an atom exceeds its parent or the
+    /// data it should contain. Contains the offset of the offending atom.
+    Malformed(u64),
+    /// An error kind indicating that the file contains no audio track, for example because it's
+    /// a video file. A file is only considered to have no audio track if all of its tracks
+    /// declare a media handler, none of which is a sound handler (`soun`). Only returned if
+    /// [`ReadConfig::require_audio_track`](crate::ReadConfig::require_audio_track) is set.
+    NoAudioTrack,
     /// An error kind indicating that the reader does not contain mp4 metadata.
     NoTag,
+    /// An error kind indicating that the reader doesn't contain an MPEG-4 file, because no
+    /// filetype atom (`ftyp`) or movie atom (`moov`) was found.
+    NotAnMp4,
     /// An error kind indicating that something wasn't found,
     Parsing,
+    /// An error kind indicating that the declared length of a top level atom exceeds the remaining
+    /// length of the file, which is most likely truncated, for example by an incomplete download.
+    TruncatedAtom {
+        /// The identifier of the atom.
+        fourcc: Fourcc,
+        /// The declared length of the atom in bytes.
+        expected: u64,
+        /// The remaining length of the file in bytes.
+        available: u64,
+    },
     /// An error kind indicating that the channel configuration index is unknown. Contains the
     /// unknown channel configuration index.
     UnknownChannelConfig(u8),
@@ -63,14 +63,18 @@
     unused_import_braces
 )]
 pub use crate::atom::{ident, Data, DataIdent, Fourcc, FreeformIdent, Ident};
+pub use crate::config::{
+    DataCodec, GenrePolicy, Id3v2Style, MetadataStyle, ReadConfig, TypeRegistry, WriteConfig,
+};
 pub use crate::error::{Error, ErrorKind, Result};
-pub use crate::tag::{Tag, STANDARD_GENRES};
+pub use crate::tag::{DataSummary, FieldChange, Tag, DEFAULT_ARTWORK_SIZE_LIMIT, STANDARD_GENRES};
 pub use crate::types::*;
 
 pub(crate) use crate::atom::AtomData;
 
 #[macro_use]
 mod atom;
+mod config;
 mod error;
 mod tag;
 mod types;
@@ -0,0 +1,193 @@
+use std::fmt;
+
+use crate::{Data, DataIdent, ImgFmt, Tag};
+
+/// A change of the data of a single metadata item between two tags.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FieldChange {
+    /// The item only exists in the new tag.
+    Added {
+        /// The identifier of the item.
+        ident: DataIdent,
+        /// The new data.
+        new: Vec<DataSummary>,
+    },
+    /// The item only exists in the old tag.
+    Removed {
+        /// The identifier of the item.
+        ident: DataIdent,
+        /// The old data.
+        old: Vec<DataSummary>,
+    },
+    /// The item exists in both tags but the data differs.
+    Changed {
+        /// The identifier of the item.
+        ident: DataIdent,
+        /// The old data.
+        old: Vec<DataSummary>,
+        /// The new data.
+        new: Vec<DataSummary>,
+    },
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Added { ident, new } => {
+                write!(f, "{}: add ", ident)?;
+                format_summaries(f, new)
+            }
+            Self::Removed { ident, old } => {
+                write!(f, "{}: remove ", ident)?;
+                format_summaries(f, old)
+            }
+            Self::Changed { ident, old, new } => {
+                write!(f, "{}: change ", ident)?;
+                format_summaries(f, old)?;
+                write!(f, " to ")?;
+                format_summaries(f, new)
+            }
+        }
+    }
+}
+
+impl FieldChange {
+    /// Returns the identifier of the changed item.
+    pub const fn ident(&self) -> &DataIdent {
+        match self {
+            Self::Added { ident, .. } => ident,
+            Self::Removed { ident, .. } => ident,
+            Self::Changed { ident, .. } => ident,
+        }
+    }
+}
+
+/// A summary of data contained in a [`FieldChange`]. Images are only described by their format and
+/// size, all other data is stored as is.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataSummary {
+    /// Data that isn't an image.
+    Data(Data),
+    /// An image of the format that is `len` bytes long.
+    Image {
+        /// The image format.
+        fmt: ImgFmt,
+        /// The length of the image data in bytes.
+        len: usize,
+    },
+}
+
+impl From<&Data> for DataSummary {
+    fn from(data: &Data) -> Self {
+        match data.image() {
+            Some(i) => Self::Image { fmt: i.fmt, len: i.data.len() },
+            None => Self::Data(data.clone()),
+        }
+    }
+}
+
+impl fmt::Display for DataSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Data(Data::Utf8(s)) | Self::Data(Data::Utf16(s)) => write!(f, "{:?}", s),
+            Self::Data(d) => write!(f, "{:?}", d),
+            Self::Image { fmt, len } => {
+                let fmt = match fmt {
+                    ImgFmt::Bmp => "bmp",
+                    ImgFmt::Jpeg => "jpeg",
+                    ImgFmt::Png => "png",
+                };
+                write!(f, "{} image ({} bytes)", fmt, len)
+            }
+        }
+    }
+}
+
+fn format_summaries(f: &mut fmt::Formatter, summaries: &[DataSummary]) -> fmt::Result {
+    match summaries {
+        [s] => write!(f, "{}", s),
+        _ => {
+            write!(f, "[")?;
+            for (i, s) in summaries.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", s)?;
+            }
+            write!(f, "]")
+        }
+    }
+}
+
+fn content_eq(a: &[Data], b: &[Data]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.content_eq(b))
+}
+
+fn summarize(data: &[Data]) -> Vec<DataSummary> {
+    data.iter().map(DataSummary::from).collect()
+}
+
+/// ### Diff
+impl Tag {
+    /// Returns the changes that are necessary to turn this tag into the `other` tag. Changes of
+    /// items present in this tag are listed in order of this tag, followed by items that were
+    /// added in order of the `other` tag. Data is compared using [`Data::content_eq`], so
+    /// artwork that only differs in its format label isn't reported as changed.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ident, Data, DataIdent, DataSummary, FieldChange, Tag};
+    ///
+    /// let mut old = Tag::default();
+    /// old.set_title("old title");
+    /// old.set_artist("artist");
+    ///
+    /// let mut new = old.clone();
+    /// new.set_title("new title");
+    /// new.remove_artists();
+    /// new.set_album("album");
+    ///
+    /// let changes = old.diff(&new);
+    /// assert_eq!(changes, vec![
+    ///     FieldChange::Changed {
+    ///         ident: DataIdent::Fourcc(ident::TITLE),
+    ///         old: vec![DataSummary::Data(Data::Utf8("old title".into()))],
+    ///         new: vec![DataSummary::Data(Data::Utf8("new title".into()))],
+    ///     },
+    ///     FieldChange::Removed {
+    ///         ident: DataIdent::Fourcc(ident::ARTIST),
+    ///         old: vec![DataSummary::Data(Data::Utf8("artist".into()))],
+    ///     },
+    ///     FieldChange::Added {
+    ///         ident: DataIdent::Fourcc(ident::ALBUM),
+    ///         new: vec![DataSummary::Data(Data::Utf8("album".into()))],
+    ///     },
+    /// ]);
+    /// assert_eq!(changes[0].to_string(), "©nam: change \"old title\" to \"new title\"");
+    /// ```
+    pub fn diff(&self, other: &Tag) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
+
+        for (ident, old) in self.entries() {
+            match other.entries().find(|(i, _)| *i == ident) {
+                Some((_, new)) if !content_eq(old, new) => changes.push(FieldChange::Changed {
+                    ident: ident.clone(),
+                    old: summarize(old),
+                    new: summarize(new),
+                }),
+                Some(_) => (),
+                None => {
+                    changes.push(FieldChange::Removed { ident: ident.clone(), old: summarize(old) })
+                }
+            }
+        }
+
+        for (ident, new) in other.entries() {
+            if !self.entries().any(|(i, _)| i == ident) {
+                changes.push(FieldChange::Added { ident: ident.clone(), new: summarize(new) });
+            }
+        }
+
+        changes
+    }
+}
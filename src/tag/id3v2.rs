@@ -0,0 +1,215 @@
+use std::borrow::Cow;
+
+use crate::{atom, Id3v2Style, Tag, WriteConfig};
+
+/// ### ID3v2
+///
+/// Some tools, especially in broadcast workflows, embed a complete ID3v2 tag inside an ID3v2
+/// atom (`ID32`), stored in a metadata atom (`meta`) with an `ID32` handler. Whether it is written
+/// is configured by [`WriteConfig::id3v2`].
+impl Tag {
+    /// Returns the raw ID3v2 tag stored inside an ID3v2 atom (`ID32`). If there are multiple, the
+    /// one of the file level metadata atom (`meta`) takes precedence over the ones of the movie
+    /// atom (`moov`).
+    ///
+    /// With the `id3` feature, the standard fields of the ID3v2 tag, like the title or artist,
+    /// are read into this tag, if the file contains no other metadata.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Tag;
+    ///
+    /// let tag = Tag::read_from_path("files/sample.m4a").unwrap();
+    /// assert_eq!(tag.id3v2_box(), None);
+    /// ```
+    pub fn id3v2_box(&self) -> Option<&[u8]> {
+        self.id3v2.as_ref().map(|(_, d)| d.as_slice())
+    }
+
+    /// Returns the 3 letter ISO 639-2/T language of the ID3v2 tag, like `eng`, if it specifies
+    /// one.
+    pub fn id3v2_language(&self) -> Option<String> {
+        self.id3v2.as_ref().and_then(|(l, _)| atom::unpack_language(*l))
+    }
+
+    /// Sets the raw ID3v2 tag, keeping the language of the previous one. The tag is only written
+    /// with [`Id3v2Style::Raw`].
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_id3v2_box(b"ID3\x04\0\0\0\0\0\0".to_vec());
+    /// assert_eq!(tag.id3v2_box(), Some(&b"ID3\x04\0\0\0\0\0\0"[..]));
+    /// assert_eq!(tag.id3v2_language(), None);
+    ///
+    /// tag.remove_id3v2_box();
+    /// assert_eq!(tag.id3v2_box(), None);
+    /// ```
+    pub fn set_id3v2_box(&mut self, id3v2: impl Into<Vec<u8>>) {
+        let language = self.id3v2.as_ref().map_or(0, |(l, _)| *l);
+        self.id3v2 = Some((language, id3v2.into()));
+    }
+
+    /// Removes the raw ID3v2 tag.
+    pub fn remove_id3v2_box(&mut self) {
+        self.id3v2 = None;
+    }
+
+    pub(crate) fn set_id3v2_with_language(&mut self, language: u16, id3v2: Vec<u8>) {
+        self.id3v2 = Some((language, id3v2));
+    }
+
+    /// Returns the ID3v2 tag and its packed language, as it is written using the configuration.
+    /// Tags without a language are written with the undetermined (`und`) language.
+    pub(crate) fn id3v2_to_write(
+        &self,
+        cfg: &WriteConfig,
+    ) -> crate::Result<Option<(u16, Cow<'_, [u8]>)>> {
+        let language = match self.id3v2 {
+            Some((l, _)) if l != 0 => l,
+            _ => atom::pack_language("und").unwrap_or_default(),
+        };
+
+        Ok(match cfg.id3v2 {
+            Id3v2Style::Keep | Id3v2Style::Raw => {
+                self.id3v2.as_ref().map(|(_, d)| (language, Cow::Borrowed(d.as_slice())))
+            }
+            #[cfg(feature = "id3")]
+            Id3v2Style::Synthesized | Id3v2Style::SynthesizedOnly => {
+                Some((language, Cow::Owned(self.synthesize_id3v2()?)))
+            }
+            #[cfg(not(feature = "id3"))]
+            Id3v2Style::Synthesized | Id3v2Style::SynthesizedOnly => {
+                return Err(crate::Error::new(
+                    crate::ErrorKind::UnwritableData,
+                    "Synthesizing an ID3v2 tag requires the id3 feature".to_owned(),
+                ));
+            }
+        })
+    }
+
+    /// Attempts to read the standard fields of the ID3v2 tag into this tag.
+    #[cfg(feature = "id3")]
+    pub(crate) fn read_id3v2_box(&mut self) -> crate::Result<()> {
+        use std::convert::TryFrom;
+        use std::io::Cursor;
+
+        use id3::TagLike;
+
+        let data = match &self.id3v2 {
+            Some((_, d)) => d,
+            None => return Ok(()),
+        };
+        let id3 = id3::Tag::read_from2(Cursor::new(data)).map_err(|e| {
+            crate::Error::new(crate::ErrorKind::Parsing, format!("Invalid ID3v2 tag: {}", e))
+        })?;
+
+        if let Some(v) = id3.title() {
+            self.set_title(v);
+        }
+        if let Some(v) = id3.artists() {
+            self.set_artists(v.into_iter().map(str::to_owned));
+        }
+        if let Some(v) = id3.album() {
+            self.set_album(v);
+        }
+        if let Some(v) = id3.album_artist() {
+            self.set_album_artist(v);
+        }
+        if let Some(v) = id3.get("TCOM").and_then(|f| f.content().text()) {
+            self.set_composer(v);
+        }
+        if let Some(v) = id3.genre_parsed() {
+            self.set_genre(v.into_owned());
+        }
+        match (id3.date_recorded(), id3.year()) {
+            (Some(d), _) => self.set_year(d.to_string()),
+            (None, Some(y)) => self.set_year(y.to_string()),
+            (None, None) => (),
+        }
+        if let Some(v) = id3.track().and_then(|n| u16::try_from(n).ok()) {
+            self.set_track_number(v);
+        }
+        if let Some(v) = id3.total_tracks().and_then(|n| u16::try_from(n).ok()) {
+            self.set_total_tracks(v);
+        }
+        if let Some(v) = id3.disc().and_then(|n| u16::try_from(n).ok()) {
+            self.set_disc_number(v);
+        }
+        if let Some(v) = id3.total_discs().and_then(|n| u16::try_from(n).ok()) {
+            self.set_total_discs(v);
+        }
+        for c in id3.comments() {
+            self.add_comment(c.text.clone());
+        }
+        if let Some(l) = id3.lyrics().next() {
+            self.set_lyrics(l.text.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Attempts to encode an ID3v2.4 tag containing the standard fields of this tag, which are
+    /// read from an ID3v2 tag, see [`Self::read_id3v2_box`].
+    #[cfg(feature = "id3")]
+    fn synthesize_id3v2(&self) -> crate::Result<Vec<u8>> {
+        use id3::frame::{Comment, Lyrics};
+        use id3::TagLike;
+
+        let mut id3 = id3::Tag::new();
+
+        if let Some(v) = self.title() {
+            id3.set_title(v);
+        }
+        if self.artist().is_some() {
+            id3.set_text_values("TPE1", self.artists());
+        }
+        if let Some(v) = self.album() {
+            id3.set_album(v);
+        }
+        if let Some(v) = self.album_artist() {
+            id3.set_album_artist(v);
+        }
+        if let Some(v) = self.composer() {
+            id3.set_text("TCOM", v);
+        }
+        if let Some(v) = self.genre() {
+            id3.set_genre(v);
+        }
+        if let Some(v) = self.year() {
+            id3.set_text("TDRC", v);
+        }
+        if let Some(v) = self.track_number() {
+            id3.set_track(v.into());
+        }
+        if let Some(v) = self.total_tracks() {
+            id3.set_total_tracks(v.into());
+        }
+        if let Some(v) = self.disc_number() {
+            id3.set_disc(v.into());
+        }
+        if let Some(v) = self.total_discs() {
+            id3.set_total_discs(v.into());
+        }
+        // the language of comments and lyrics isn't known, which is indicated by `XXX`
+        if let Some(v) = self.comment() {
+            let (lang, description) = ("XXX".to_owned(), String::new());
+            id3.add_frame(Comment { lang, description, text: v.to_owned() });
+        }
+        if let Some(v) = self.lyrics() {
+            let (lang, description) = ("XXX".to_owned(), String::new());
+            id3.add_frame(Lyrics { lang, description, text: v.to_owned() });
+        }
+
+        let mut buf = Vec::new();
+        id3.write_to(&mut buf, id3::Version::Id3v24).map_err(|e| {
+            crate::Error::new(
+                crate::ErrorKind::UnwritableData,
+                format!("Error encoding ID3v2 tag: {}", e),
+            )
+        })?;
+        Ok(buf)
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{atom, Data, DataIdent, Ident, Tag};
+
+/// The delimiter separating the values of list atoms, like keywords (`keyw`) or categories
+/// (`catg`).
+const DELIMITER: char = ',';
+
+/// ### Delimited lists
+///
+/// Some atoms, like keywords (`keyw`) and categories (`catg`), conventionally store a list of
+/// values inside a single string, separated by commas.
+impl Tag {
+    /// Returns the comma separated values of all strings corresponding to the identifier. The
+    /// values are trimmed of surrounding whitespace and empty values are skipped.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ident, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_keyword("rock, live ,, 1999");
+    /// assert_eq!(tag.list_of(&ident::KEYWORD), ["rock", "live", "1999"]);
+    ///
+    /// tag.set_keyword(" ");
+    /// assert!(tag.list_of(&ident::KEYWORD).is_empty());
+    /// ```
+    pub fn list_of(&self, ident: &impl Ident) -> Vec<String> {
+        self.strings_of(ident).flat_map(split_list).collect()
+    }
+
+    /// Sets the values, trimmed of surrounding whitespace and joined by commas, as the string
+    /// corresponding to the identifier. This will remove all other data corresponding to the
+    /// identifier. If no value is left after skipping empty values, the data is only removed.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ident, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_list(ident::CATEGORY, &[" podcast", "", "news "]);
+    /// assert_eq!(tag.category(), Some("podcast, news"));
+    ///
+    /// tag.set_list(ident::CATEGORY, &[""]);
+    /// assert_eq!(tag.category(), None);
+    /// ```
+    pub fn set_list(
+        &mut self,
+        ident: impl Ident + Into<DataIdent>,
+        values: impl IntoIterator<Item = impl AsRef<str>>,
+    ) {
+        let values: Vec<String> = values
+            .into_iter()
+            .map(|v| v.as_ref().trim().to_owned())
+            .filter(|v| !v.is_empty())
+            .collect();
+
+        match values.is_empty() {
+            true => self.remove_data_of(&ident),
+            false => self.set_data(ident, Data::Utf8(values.join(", "))),
+        }
+    }
+
+    /// Returns the comma separated keywords (`keyw`), see [`Self::list_of`].
+    pub fn keyword_list(&self) -> Vec<String> {
+        self.list_of(&atom::KEYWORD)
+    }
+
+    /// Sets the keywords (`keyw`) as a comma separated list, see [`Self::set_list`].
+    pub fn set_keyword_list(&mut self, keywords: impl IntoIterator<Item = impl AsRef<str>>) {
+        self.set_list(atom::KEYWORD, keywords);
+    }
+
+    /// Returns the comma separated categories (`catg`), see [`Self::list_of`].
+    pub fn category_list(&self) -> Vec<String> {
+        self.list_of(&atom::CATEGORY)
+    }
+
+    /// Sets the categories (`catg`) as a comma separated list, see [`Self::set_list`].
+    pub fn set_category_list(&mut self, categories: impl IntoIterator<Item = impl AsRef<str>>) {
+        self.set_list(atom::CATEGORY, categories);
+    }
+}
+
+fn split_list(s: &str) -> impl Iterator<Item = String> + '_ {
+    s.split(DELIMITER).map(str::trim).filter(|v| !v.is_empty()).map(str::to_owned)
+}
@@ -1,20 +1,37 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
 use std::fs::{File, OpenOptions};
+use std::hash::{Hash, Hasher};
 use std::io::{BufReader, Read, Seek, Write};
+use std::iter::FromIterator;
 use std::path::Path;
 use std::rc::Rc;
+use std::sync::Arc;
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncSeek};
 
 use crate::{
-    atom, ident, AdvisoryRating, AtomData, AudioInfo, Data, DataIdent, Ident, Img, ImgBuf, ImgFmt,
-    ImgMut, ImgRef, MediaType,
+    atom, ident, AdvisoryRating, AtomData, AudioInfo, Data, DataIdent, EncoderSource, Fourcc,
+    GenrePolicy, Ident, Img, ImgBuf, ImgFmt, ImgMut, ImgRef, Locale, Location, MediaType,
+    ReadConfig, ReadStats, StringEncoding, WriteConfig,
 };
 
+pub use diff::*;
 pub use genre::*;
 pub use readonly::*;
 pub use tuple::*;
 
+mod diff;
 mod genre;
+mod id3v2;
+mod list;
+mod normalize;
+mod privacy;
 mod readonly;
 mod tuple;
 
@@ -27,6 +44,13 @@ pub struct Tag {
     info: AudioInfo,
     /// A vector containing metadata atoms
     atoms: Vec<AtomData>,
+    /// The XMP metadata stored inside a user extension (`uuid`) atom.
+    xmp: Option<Vec<u8>>,
+    /// The ID3v2 tag stored inside an ID3v2 (`ID32`) atom, and its packed language.
+    id3v2: Option<(u16, Vec<u8>)>,
+    /// Whether private metadata outside the item lists, like a QuickTime location (`udta.©xyz`),
+    /// is removed when writing, see [`Self::strip_private`].
+    strip_private: bool,
 }
 
 impl fmt::Display for Tag {
@@ -35,8 +59,12 @@ impl fmt::Display for Tag {
         self.format_artists(f)?;
         self.format_composers(f)?;
         self.format_lyricists(f)?;
+        self.format_producers(f)?;
+        self.format_directors(f)?;
+        self.format_performers(f)?;
         self.format_album(f)?;
         self.format_title(f)?;
+        self.format_track_subtitle(f)?;
         self.format_genres(f)?;
         self.format_year(f)?;
         self.format_track(f)?;
@@ -46,10 +74,12 @@ impl fmt::Display for Tag {
         self.format_media_type(f)?;
         self.format_groupings(f)?;
         self.format_descriptions(f)?;
+        self.format_long_description(f)?;
         self.format_comments(f)?;
         self.format_categories(f)?;
         self.format_keywords(f)?;
         self.format_copyright(f)?;
+        self.format_purchase_date(f)?;
         self.format_encoder(f)?;
         self.format_tv_show_name(f)?;
         self.format_tv_network_name(f)?;
@@ -66,13 +96,16 @@ impl fmt::Display for Tag {
         self.format_sample_rate(f)?;
         self.format_avg_bitrate(f)?;
         self.format_max_bitrate(f)?;
+        self.format_bit_depth(f)?;
         self.format_show_movement(f)?;
         self.format_gapless_playback(f)?;
         self.format_compilation(f)?;
+        self.format_podcast(f)?;
         self.format_isrc(f)?;
         self.format_lyrics(f)?;
+        self.format_location(f)?;
         for a in self.atoms.iter() {
-            if let DataIdent::Freeform { .. } = &a.ident {
+            if let DataIdent::Freeform { .. } | DataIdent::Key(_) = &a.ident {
                 writeln!(f, "{}:", a.ident)?;
                 for d in a.data.iter() {
                     writeln!(f, "    {:?}", d)?;
@@ -83,39 +116,392 @@ impl fmt::Display for Tag {
     }
 }
 
+/// Collects a tag from identifier and data pairs. Data of repeated identifiers is accumulated in
+/// the same atom, in the order of the iterator.
+///
+/// # Example
+/// ```
+/// use mp4ameta::{ident, Data, Tag};
+///
+/// let tag: Tag = vec![
+///     (ident::TITLE, Data::Utf8("title".to_owned())),
+///     (ident::ARTIST, Data::Utf8("artist 1".to_owned())),
+///     (ident::ARTIST, Data::Utf8("artist 2".to_owned())),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// assert_eq!(tag.title(), Some("title"));
+/// assert_eq!(tag.artists().collect::<Vec<_>>(), vec!["artist 1", "artist 2"]);
+/// ```
+impl<I: Ident + Into<DataIdent>> FromIterator<(I, Data)> for Tag {
+    fn from_iter<T: IntoIterator<Item = (I, Data)>>(iter: T) -> Self {
+        let mut tag = Self::default();
+        tag.extend(iter);
+        tag
+    }
+}
+
+/// Adds the data to the atoms corresponding to the identifiers, see [`Tag::add_data`].
+impl<I: Ident + Into<DataIdent>> Extend<(I, Data)> for Tag {
+    fn extend<T: IntoIterator<Item = (I, Data)>>(&mut self, iter: T) {
+        for (ident, data) in iter {
+            self.add_data(ident, data);
+        }
+    }
+}
+
 impl Tag {
     /// Creates a new MPEG-4 audio tag containing the atom.
     pub const fn new(ftyp: String, info: AudioInfo, atoms: Vec<AtomData>) -> Self {
-        Self { ftyp, info, atoms }
+        Self { ftyp, info, atoms, xmp: None, id3v2: None, strip_private: false }
     }
 
     /// Attempts to read a MPEG-4 audio tag from the reader.
+    ///
+    /// Returns an error of kind [`ErrorKind::NotAnMp4`](crate::ErrorKind::NotAnMp4) if the reader
+    /// doesn't contain an MPEG-4 file. Files only containing tracks of other media types, like
+    /// video, are read as usual, unless
+    /// [`ReadConfig::require_audio_track`](crate::ReadConfig::require_audio_track) is set.
+    ///
+    /// If the file contains multiple movie atoms (`moov`), the first one containing a movie
+    /// header (`mvhd`) is read and all subsequent ones are ignored. Movie atoms without a header,
+    /// such as placeholders, are only read if no other movie atom is present. Writing a tag
+    /// follows the same rule.
+    ///
+    /// A movie atom with a length of 0, extending to the end of a partially written file, that
+    /// can't be parsed is treated as containing no metadata, so an empty tag is returned.
+    ///
+    /// Metadata items of a file level metadata atom (`meta`), which some tools write following
+    /// ISO base media file format conventions, are read if the movie atom doesn't contain an item
+    /// with the same identifier. When writing, they are written to the movie atom along with the
+    /// other items, and the item list of the file level metadata atom is turned into free space.
+    ///
+    /// 3GPP asset information atoms inside the user data atom (`udta`), like the title (`titl`)
+    /// or author (`auth`), which some QuickTime exports and phone recordings contain, are read as
+    /// the corresponding metadata items, if the item list doesn't contain them. Assets of a
+    /// language are read as localized data, see [`Self::localized`], and the first asset of an
+    /// item is also its regular value. When writing, they are written to the item list along with
+    /// the other items, and the assets are turned into free space.
+    ///
+    /// Only the fixed hierarchy of atoms that contain metadata or audio information is parsed,
+    /// and unknown children are skipped without being descended into, so the nesting depth of the
+    /// file doesn't affect the recursion depth of the parser.
     pub fn read_from(reader: &mut (impl Read + Seek)) -> crate::Result<Self> {
-        atom::read_tag_from(reader)
+        Self::read_with_config(reader, &ReadConfig::default())
+    }
+
+    /// Attempts to read a MPEG-4 audio tag from the reader using the configuration, see
+    /// [`Self::read_from`].
+    pub fn read_with_config(
+        reader: &mut (impl Read + Seek),
+        cfg: &ReadConfig,
+    ) -> crate::Result<Self> {
+        atom::read_tag_from(reader, &atom::ReadCtx::new(cfg))
+    }
+
+    /// Attempts to read a MPEG-4 audio tag from the reader, see [`Self::read_from`], and returns
+    /// statistics about the reading process alongside it. This can be used to find out which
+    /// atoms of a file are read and skipped, and how much data is read.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut file = std::fs::File::open("music.m4a").unwrap();
+    /// let (tag, stats) = Tag::read_from_with_stats(&mut file).unwrap();
+    /// println!("read {} bytes and skipped {:?}", stats.bytes_read, stats.skipped_atoms);
+    /// ```
+    pub fn read_from_with_stats(
+        reader: &mut (impl Read + Seek),
+    ) -> crate::Result<(Self, ReadStats)> {
+        Self::read_with_config_and_stats(reader, &ReadConfig::default())
+    }
+
+    /// Attempts to read a MPEG-4 audio tag from the reader using the configuration, and returns
+    /// statistics about the reading process alongside it, see [`Self::read_from_with_stats`]. When
+    /// reading leniently, the statistics contain warnings about malformed data that was recovered
+    /// from.
+    pub fn read_with_config_and_stats(
+        reader: &mut (impl Read + Seek),
+        cfg: &ReadConfig,
+    ) -> crate::Result<(Self, ReadStats)> {
+        atom::read_tag_with_stats(reader, cfg)
+    }
+
+    /// Attempts to read a MPEG-4 audio tag from the reader, see [`Self::read_from`], and reports
+    /// the progress to the callback, which is called with the number of bytes traversed and the
+    /// total number of bytes of the reader, after the filetype atom (`ftyp`) and after each
+    /// following top level atom.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut file = std::fs::File::open("audiobook.m4b").unwrap();
+    /// let tag = Tag::read_from_with_progress(&mut file, |done, total| {
+    ///     println!("{:.0}%", 100.0 * done as f64 / total as f64);
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn read_from_with_progress(
+        reader: &mut (impl Read + Seek),
+        progress: impl FnMut(u64, u64),
+    ) -> crate::Result<Self> {
+        let cfg = ReadConfig::default();
+        let progress = RefCell::new(progress);
+        atom::read_tag_from(reader, &atom::ReadCtx::with_progress(&cfg, &progress))
+    }
+
+    /// Attempts to read a MPEG-4 audio tag from the reader, starting with the atom at the offset,
+    /// which is useful when the location of the movie atom (`moov`) is already known. If the atom
+    /// is the filetype atom (`ftyp`), the tag is read as usual. If it is the movie atom, only it
+    /// is read, so the filetype is left empty and no XMP metadata is read.
+    pub fn from_reader_at(reader: &mut (impl Read + Seek), offset: u64) -> crate::Result<Self> {
+        atom::read_tag_from_at(reader, &atom::ReadCtx::new(&ReadConfig::default()), offset)
+    }
+
+    /// Attempts to read a MPEG-4 audio tag from the bytes of a movie atom (`moov`), which is
+    /// useful when only that byte range of a file is fetched, for example from remote storage.
+    ///
+    /// The bytes have to contain exactly one complete movie atom, starting with its 8 byte head,
+    /// or 16 byte head if it has an extended length, and followed by its children. Other top
+    /// level atoms, like the filetype (`ftyp`) or media data (`mdat`), aren't expected, so the
+    /// filetype is left empty and no XMP metadata is read.
+    ///
+    /// Returns an error of kind [`ErrorKind::AtomNotFound`](crate::ErrorKind::AtomNotFound) if
+    /// the bytes don't start with a movie atom, and an error of kind
+    /// [`ErrorKind::TruncatedAtom`](crate::ErrorKind::TruncatedAtom) if they end before it does,
+    /// or an error of kind [`ErrorKind::Parsing`](crate::ErrorKind::Parsing) if they continue after
+    /// it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::{Read, Seek, SeekFrom};
+    ///
+    /// use mp4ameta::Tag;
+    ///
+    /// // the offset and length of the movie atom, for example located using a range request
+    /// let (moov_pos, moov_len) = (32, 3090);
+    ///
+    /// let mut file = File::open("music.m4a").unwrap();
+    /// file.seek(SeekFrom::Start(moov_pos)).unwrap();
+    /// let mut moov = vec![0; moov_len];
+    /// file.read_exact(&mut moov).unwrap();
+    ///
+    /// let tag = Tag::read_from_moov_bytes(&moov).unwrap();
+    /// println!("{}", tag);
+    /// ```
+    pub fn read_from_moov_bytes(bytes: &[u8]) -> crate::Result<Self> {
+        let cfg = ReadConfig::default();
+        atom::read_tag_from_moov_bytes(bytes, &atom::ReadCtx::new(&cfg))
+    }
+
+    /// Attempts to asynchronously read a MPEG-4 audio tag from the reader.
+    #[cfg(feature = "tokio")]
+    pub async fn read_from_async(
+        reader: &mut (impl AsyncRead + AsyncSeek + Unpin),
+    ) -> crate::Result<Self> {
+        atom::read_tag_from_async(reader, &ReadConfig::default()).await
     }
 
     /// Attempts to read a MPEG-4 audio tag from the file at the indicated path.
     pub fn read_from_path(path: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::read_from_path_with_config(path, &ReadConfig::default())
+    }
+
+    /// Attempts to read a MPEG-4 audio tag from the file at the indicated path using the
+    /// configuration.
+    pub fn read_from_path_with_config(
+        path: impl AsRef<Path>,
+        cfg: &ReadConfig,
+    ) -> crate::Result<Self> {
         let mut file = BufReader::new(File::open(path)?);
-        Self::read_from(&mut file)
+        Self::read_with_config(&mut file, cfg)
     }
 
     /// Attempts to write the MPEG-4 audio tag to the writer. This will overwrite any metadata
-    /// previously present on the file.
+    /// previously present on the file. QuickTime metadata items identified by a
+    /// [`DataIdent::Key`] aren't written, and the QuickTime metadata of the file is left as is.
+    ///
+    /// Only the metadata inside the user data atom (`udta`) is replaced, so the other children of
+    /// the movie atom (`moov`) keep their original order, and other children of the user data
+    /// atom, like a QuickTime location (`©xyz`), are kept as they are. A missing user data atom is
+    /// appended. After [`Self::strip_private`] the QuickTime location is removed, and the
+    /// QuickTime metadata is rewritten without the removed items.
     pub fn write_to(&self, file: &File) -> crate::Result<()> {
-        atom::write_tag_to(file, &self.atoms)
+        self.write_with_config(file, &WriteConfig::default())
+    }
+
+    /// Attempts to write the MPEG-4 audio tag to the writer using the configuration. This will
+    /// overwrite any metadata previously present on the file.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use mp4ameta::{DataIdent, Data, MetadataStyle, Tag, WriteConfig};
+    ///
+    /// let mut tag = Tag::read_from_path("music.m4a").unwrap();
+    /// let title = DataIdent::key("com.apple.quicktime.title");
+    /// tag.set_data(title, Data::Utf8("title".into()));
+    ///
+    /// let cfg = WriteConfig {
+    ///     metadata_style: MetadataStyle::QuickTimeKeys,
+    ///     ..Default::default()
+    /// };
+    /// tag.write_to_path_with_config("music.m4a", &cfg).unwrap();
+    /// ```
+    pub fn write_with_config(&self, file: &File, cfg: &WriteConfig) -> crate::Result<()> {
+        let atoms = self.atoms_to_write(cfg)?;
+        let id3v2 = self.id3v2_to_write(cfg)?;
+        let id3v2 = id3v2.as_ref().map(|(l, d)| (*l, d.as_ref()));
+        let xmp = self.xmp.as_deref();
+        atom::write_tag_to(file, &atoms, xmp, id3v2, self.strip_private, cfg, None)
+    }
+
+    /// Attempts to write the MPEG-4 audio tag to the writer, see [`Self::write_to`], and reports
+    /// the progress to the callback, which is called with the number of bytes written and the
+    /// total number of bytes to write. Most of the time is spent moving the media data (`mdat`)
+    /// following the metadata, which is done in chunks, each of which is reported. If the media
+    /// data doesn't have to be moved, only a single call is made.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::fs::OpenOptions;
+    ///
+    /// use mp4ameta::Tag;
+    ///
+    /// let file = OpenOptions::new().read(true).write(true).open("audiobook.m4b").unwrap();
+    /// let mut tag = Tag::read_from(&mut &file).unwrap();
+    /// tag.set_title("title");
+    /// tag.write_to_with_progress(&file, |done, total| {
+    ///     println!("{:.0}%", 100.0 * done as f64 / total as f64);
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn write_to_with_progress(
+        &self,
+        file: &File,
+        progress: impl FnMut(u64, u64),
+    ) -> crate::Result<()> {
+        self.write_with_config_and_progress(file, &WriteConfig::default(), progress)
+    }
+
+    /// Attempts to write the MPEG-4 audio tag to the writer using the configuration, and reports
+    /// the progress to the callback, see [`Self::write_to_with_progress`]. The size of the chunks
+    /// in which the media data is moved is configured by
+    /// [`WriteConfig::copy_buf_size`](crate::WriteConfig::copy_buf_size).
+    pub fn write_with_config_and_progress(
+        &self,
+        file: &File,
+        cfg: &WriteConfig,
+        mut progress: impl FnMut(u64, u64),
+    ) -> crate::Result<()> {
+        let atoms = self.atoms_to_write(cfg)?;
+        let id3v2 = self.id3v2_to_write(cfg)?;
+        let id3v2 = id3v2.as_ref().map(|(l, d)| (*l, d.as_ref()));
+        let xmp = self.xmp.as_deref();
+        atom::write_tag_to(file, &atoms, xmp, id3v2, self.strip_private, cfg, Some(&mut progress))
     }
 
     /// Attempts to write the MPEG-4 audio tag to the path. This will overwrite any metadata
     /// previously present on the file.
     pub fn write_to_path(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        self.write_to_path_with_config(path, &WriteConfig::default())
+    }
+
+    /// Attempts to write the MPEG-4 audio tag to the path using the configuration. This will
+    /// overwrite any metadata previously present on the file.
+    pub fn write_to_path_with_config(
+        &self,
+        path: impl AsRef<Path>,
+        cfg: &WriteConfig,
+    ) -> crate::Result<()> {
         let file = OpenOptions::new().read(true).write(true).open(path)?;
-        self.write_to(&file)
+        self.write_with_config(&file, cfg)
     }
 
     /// Attempts to dump the MPEG-4 audio tag to the writer.
     pub fn dump_to(&self, writer: &mut impl Write) -> crate::Result<()> {
-        atom::dump_tag_to(writer, &self.atoms)
+        let cfg = WriteConfig::default();
+        let atoms = self.atoms_to_write(&cfg)?;
+        let id3v2 = self.id3v2_to_write(&cfg)?;
+        let id3v2 = id3v2.as_ref().map(|(l, d)| (*l, d.as_ref()));
+        atom::dump_tag_to(writer, &atoms, self.xmp.as_deref(), id3v2)
+    }
+
+    /// Attempts to encode the metadata as the user data atom (`udta`), containing the metadata
+    /// (`meta`) and item list (`ilst`) atoms, the way it is written to the movie atom (`moov`)
+    /// of a file.
+    ///
+    /// Metadata atoms are stored in insertion order, so encoding or writing the same tag always
+    /// produces identical bytes. To also get identical bytes for tags that contain the same
+    /// metadata in a different order or form, use [`Self::normalize`] first.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_title("title");
+    ///
+    /// let udta = tag.encode_to_vec().unwrap();
+    /// assert_eq!(&udta[4..8], b"udta");
+    /// assert_eq!(udta.len() as u64, tag.metadata_size());
+    /// ```
+    pub fn encode_to_vec(&self) -> crate::Result<Vec<u8>> {
+        atom::encode_metadata(&self.atoms_to_write(&WriteConfig::default())?)
+    }
+
+    /// Returns the atoms that are written using the configuration. Standard genres are omitted if
+    /// a custom genre is present and the [`GenrePolicy::PreferText`] is used. The data of atoms
+    /// with a fourcc registered in the [`WriteConfig::types`] is encoded using its codec. Utf-16
+    /// strings are prefixed with a byte order mark if [`WriteConfig::utf16_bom`] is set.
+    fn atoms_to_write(&self, cfg: &WriteConfig) -> crate::Result<Cow<'_, [AtomData]>> {
+        if cfg.id3v2 == crate::Id3v2Style::SynthesizedOnly {
+            return Ok(Cow::Borrowed(&[]));
+        }
+
+        let has_custom_genre = self.custom_genres().next().is_some();
+        let has_standard_genre = self.standard_genres().next().is_some();
+
+        let mut atoms = match cfg.genre_policy {
+            GenrePolicy::PreferText if has_custom_genre && has_standard_genre => {
+                let standard_genre = DataIdent::Fourcc(ident::STANDARD_GENRE);
+                let atoms = self.atoms.iter().filter(|a| a.ident != standard_genre);
+                Cow::Owned(atoms.cloned().collect())
+            }
+            _ => Cow::Borrowed(&self.atoms[..]),
+        };
+
+        if !cfg.types.is_empty() {
+            for a in atoms.to_mut().iter_mut() {
+                let codec = match &a.ident {
+                    DataIdent::Fourcc(f) => cfg.types.get(*f),
+                    _ => None,
+                };
+                if let Some(c) = codec {
+                    let data = a.data.iter_mut().chain(a.localized.iter_mut().map(|(_, d)| d));
+                    for d in data {
+                        *d = c.encode(d)?;
+                    }
+                }
+            }
+        }
+
+        if cfg.utf16_bom {
+            for a in atoms.to_mut().iter_mut() {
+                let data = a.data.iter_mut().chain(a.localized.iter_mut().map(|(_, d)| d));
+                for d in data {
+                    match d {
+                        Data::Utf16(s) if !s.starts_with('\u{feff}') => s.insert(0, '\u{feff}'),
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        Ok(atoms)
     }
 
     /// Attempts to dump the MPEG-4 audio tag to the writer.
@@ -125,13 +511,100 @@ impl Tag {
     }
 }
 
+/// ### Size
+impl Tag {
+    /// Returns the size in bytes the metadata region (`udta`, `meta`, `hdlr` and `ilst` atoms)
+    /// will occupy when written to a file.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut tag = Tag::default();
+    /// let empty_size = tag.metadata_size();
+    ///
+    /// tag.set_title("title");
+    /// assert_eq!(tag.metadata_size(), empty_size + 8 + 16 + 5);
+    /// ```
+    pub fn metadata_size(&self) -> u64 {
+        atom::metadata_len(&self.atoms)
+    }
+
+    /// Returns the total size in bytes the metadata adds to a file when written, as opposed to
+    /// the audio data. This is the size of the metadata region, see [`Self::metadata_size`], of
+    /// the user extension atom (`uuid`) containing the XMP packet, and of the top level metadata
+    /// atom (`meta`) containing the raw ID3v2 tag (`ID32`), if present. QuickTime metadata items
+    /// identified by a key are only written with
+    /// [`MetadataStyle::QuickTimeKeys`](crate::MetadataStyle::QuickTimeKeys) and aren't included.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Img, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    /// png.resize(300_000, 0);
+    /// tag.set_artwork(Img::png(png)).unwrap();
+    /// let overhead = tag.metadata_overhead();
+    /// assert_eq!(overhead, tag.metadata_size());
+    /// assert!(overhead > 300_000);
+    ///
+    /// tag.set_xmp(b"<x:xmpmeta/>".to_vec());
+    /// assert_eq!(tag.metadata_overhead(), overhead + 8 + 16 + 12);
+    /// ```
+    pub fn metadata_overhead(&self) -> u64 {
+        let xmp_len = self.xmp.as_deref().map_or(0, atom::xmp_len);
+        let id3v2_len = self.id3v2.as_ref().map_or(0, |(_, d)| atom::id3v2_len(d));
+        self.metadata_size() + xmp_len + id3v2_len
+    }
+
+    /// Returns the size in bytes of the metadata atoms grouped by their identifier, in the order
+    /// of their first occurrence. All freeform atoms are grouped under the freeform (`----`)
+    /// identifier. QuickTime metadata items identified by a key aren't included.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ident, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_title("title");
+    /// tag.set_artists(vec!["artist 1".to_owned(), "artist 2".to_owned()]);
+    ///
+    /// let breakdown = tag.size_breakdown();
+    /// assert_eq!(breakdown, vec![
+    ///     (ident::TITLE, 8 + 16 + 5),
+    ///     (ident::ARTIST, 8 + 16 + 8 + 16 + 8),
+    /// ]);
+    /// ```
+    pub fn size_breakdown(&self) -> Vec<(Fourcc, u64)> {
+        let mut breakdown: Vec<(Fourcc, u64)> = Vec::new();
+
+        for a in self.atoms.iter() {
+            let fourcc = match &a.ident {
+                DataIdent::Fourcc(f) => *f,
+                DataIdent::Freeform { .. } => ident::FREEFORM,
+                DataIdent::Key(_) => continue,
+            };
+            match breakdown.iter_mut().find(|(f, _)| *f == fourcc) {
+                Some((_, len)) => *len += a.len(),
+                None => breakdown.push((fourcc, a.len())),
+            }
+        }
+
+        breakdown
+    }
+}
+
 // ## Individual string values
 mp4ameta_proc::single_string_value_accessor!("album", "©alb");
 mp4ameta_proc::single_string_value_accessor!("copyright", "cprt");
 mp4ameta_proc::single_string_value_accessor!("encoder", "©too");
+mp4ameta_proc::single_string_value_accessor!("long_description", "ldes");
 mp4ameta_proc::single_string_value_accessor!("lyrics", "©lyr");
 mp4ameta_proc::single_string_value_accessor!("movement", "©mvn");
+mp4ameta_proc::single_string_value_accessor!("purchase_date", "purd");
 mp4ameta_proc::single_string_value_accessor!("title", "©nam");
+mp4ameta_proc::single_string_value_accessor!("track_subtitle", "©st3");
 mp4ameta_proc::single_string_value_accessor!("tv_episode_name", "tven");
 mp4ameta_proc::single_string_value_accessor!("tv_network_name", "tvnn");
 mp4ameta_proc::single_string_value_accessor!("tv_show_name", "tvsh");
@@ -147,13 +620,17 @@ mp4ameta_proc::multiple_string_values_accessor!("comment", "©cmt");
 mp4ameta_proc::multiple_string_values_accessor!("composer", "©wrt");
 mp4ameta_proc::multiple_string_values_accessor!("custom_genre", "©gen");
 mp4ameta_proc::multiple_string_values_accessor!("description", "desc");
+mp4ameta_proc::multiple_string_values_accessor!("director", "©dir");
 mp4ameta_proc::multiple_string_values_accessor!("grouping", "©grp");
 mp4ameta_proc::multiple_string_values_accessor!("keyword", "keyw");
 mp4ameta_proc::multiple_string_values_accessor!("lyricist", "----:com.apple.iTunes:LYRICIST");
+mp4ameta_proc::multiple_string_values_accessor!("performer", "©prf");
+mp4ameta_proc::multiple_string_values_accessor!("producer", "©prd");
 
 // ## Flags
 mp4ameta_proc::flag_value_accessor!("compilation", "cpil");
 mp4ameta_proc::flag_value_accessor!("gapless_playback", "pgap");
+mp4ameta_proc::flag_value_accessor!("podcast", "pcst");
 mp4ameta_proc::flag_value_accessor!("show_movement", "shwm");
 
 // ## Integer values
@@ -165,46 +642,553 @@ mp4ameta_proc::u32_value_accessor!("tv_episode", "tves");
 mp4ameta_proc::u32_value_accessor!("tv_season", "tvsn");
 
 // ## Custom values
+/// ### Purchase date
+///
+/// The purchase date is stored as a string, usually in the format `2009-01-29 18:43:37` or as an
+/// RFC 3339 date and time like `2009-01-29T18:43:37Z`.
+#[cfg(feature = "date")]
+impl Tag {
+    /// Returns the purchase date (`purd`) parsed as a date and time. Dates without a time are
+    /// interpreted as midnight, and times with an offset are converted to UTC.
+    ///
+    /// # Example
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_purchase_date("2009-01-29T18:43:37+01:00");
+    ///
+    /// let expected = NaiveDate::from_ymd_opt(2009, 1, 29).unwrap().and_hms_opt(17, 43, 37);
+    /// assert_eq!(tag.purchase_date_time(), expected);
+    /// ```
+    pub fn purchase_date_time(&self) -> Option<chrono::NaiveDateTime> {
+        use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+        let date = self.purchase_date()?.trim();
+        if let Ok(d) = DateTime::parse_from_rfc3339(date) {
+            return Some(d.naive_utc());
+        }
+        ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(date, f).ok())
+            .or_else(|| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0))
+    }
+
+    /// Sets the purchase date (`purd`) formatted like `2009-01-29 18:43:37`.
+    pub fn set_purchase_date_time(&mut self, date_time: chrono::NaiveDateTime) {
+        self.set_purchase_date(date_time.format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+}
+
+/// ### Release year
+impl Tag {
+    /// Returns the release year parsed from the year (`©day`), which is trusted if it is a 4 digit
+    /// year or an ISO 8601 date like `2009-01-29`, optionally followed by a time. Dates with a time
+    /// that matches the creation time of the movie header (`mvhd`) are ignored, since they were
+    /// most likely generated when the file was recorded, rather than entered by a user.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_year("2009");
+    /// assert_eq!(tag.release_year(), Some(2009));
+    ///
+    /// tag.set_year("2009-01-29T18:43:37Z");
+    /// assert_eq!(tag.release_year(), Some(2009));
+    ///
+    /// tag.set_year("early 2009");
+    /// assert_eq!(tag.release_year(), None);
+    /// ```
+    pub fn release_year(&self) -> Option<u16> {
+        let (year, time) = parse_iso_date(self.year()?.trim())?;
+        match (time, self.movie_header()) {
+            (Some(t), Some(h)) if t == h.creation_time as i64 => None,
+            _ => Some(year),
+        }
+    }
+}
+
+/// Parses a 4 digit year or an ISO 8601 date, returning the year and, if the date is followed by
+/// a time, the seconds since midnight, January 1, 1904 UTC. Fractional seconds are ignored, and
+/// times without a time zone designator are treated as UTC. The date math is done by hand, since
+/// `chrono` is only available with the `date` feature.
+fn parse_iso_date(date: &str) -> Option<(u16, Option<i64>)> {
+    let b = date.as_bytes();
+    let num = |start: usize, len: usize| -> Option<i64> {
+        let digits = b.get(start..start + len)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        Some(digits.iter().fold(0, |n, d| n * 10 + (d - b'0') as i64))
+    };
+    let sep = |pos: usize, chars: &[u8]| matches!(b.get(pos), Some(c) if chars.contains(c));
+
+    let year = num(0, 4)?;
+    let month = match b.len() {
+        4 => return Some((year as u16, None)),
+        n if n >= 7 && sep(4, b"-") => num(5, 2).filter(|m| (1..=12).contains(m))?,
+        _ => return None,
+    };
+    let day = match b.len() {
+        7 => return Some((year as u16, None)),
+        n if n >= 10 && sep(7, b"-") => num(8, 2).filter(|d| (1..=31).contains(d))?,
+        _ => return None,
+    };
+    if b.len() == 10 {
+        return Some((year as u16, None));
+    }
+
+    if !sep(10, b"T ") || !sep(13, b":") || !sep(16, b":") {
+        return None;
+    }
+    let (hours, minutes, seconds) = (num(11, 2)?, num(14, 2)?, num(17, 2)?);
+    let offset = match parse_utc_offset(&date[19..]) {
+        Some(o) => o,
+        None => return Some((year as u16, None)),
+    };
+    let days = days_from_civil(year, month, day) - days_from_civil(1904, 1, 1);
+    let time = days * 86400 + hours * 3600 + minutes * 60 + seconds - offset;
+    Some((year as u16, Some(time)))
+}
+
+/// Parses optional fractional seconds followed by an optional time zone designator, either `Z`
+/// or an offset like `+01:00`, `+0100` or `+01`, returning the offset from UTC in seconds.
+fn parse_utc_offset(zone: &str) -> Option<i64> {
+    let zone = match zone.strip_prefix('.') {
+        Some(z) => z.trim_start_matches(|c: char| c.is_ascii_digit()),
+        None => zone,
+    };
+    if zone.is_empty() || zone == "Z" {
+        return Some(0);
+    }
+
+    let sign = match zone.as_bytes()[0] {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let digits = zone[1..].replacen(':', "", 1);
+    if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = if digits.len() == 4 { digits[2..].parse().ok()? } else { 0 };
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Returns the number of days since 0000-03-01 of the date in the proleptic gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era
+}
+
+/// ### Comment
+///
+/// Besides the standard comment atom (`©cmt`), some taggers store comments inside a freeform atom
+/// (`----:com.apple.iTunes:COMMENT`). The comment accessors only read and write the standard
+/// comment, so the two aren't confused. Freeform comments can be read additionally with
+/// [`Self::all_comments`] and [`Self::any_comment`].
+impl Tag {
+    /// Returns all comments: first the standard comments (`©cmt`), then the comments of freeform
+    /// atoms with the mean `com.apple.iTunes` and the name `comment`, ignoring case.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Data, FreeformIdent, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// let freeform = FreeformIdent::new("com.apple.iTunes", "COMMENT");
+    /// tag.set_data(freeform, Data::Utf8("freeform".into()));
+    /// tag.set_comment("standard");
+    ///
+    /// assert_eq!(tag.comments().collect::<Vec<_>>(), ["standard"]);
+    /// assert_eq!(tag.all_comments().collect::<Vec<_>>(), ["standard", "freeform"]);
+    /// ```
+    pub fn all_comments(&self) -> impl Iterator<Item = &str> {
+        let freeform = self
+            .atoms
+            .iter()
+            .filter(|a| match &a.ident {
+                DataIdent::Freeform { mean, name } => {
+                    mean == ident::APPLE_ITUNES_MEAN && name.eq_ignore_ascii_case("comment")
+                }
+                _ => false,
+            })
+            .flat_map(|a| a.data.iter().filter_map(Data::string));
+        self.comments().chain(freeform)
+    }
+
+    /// Returns the first standard comment (`©cmt`) or, if there is none, the first freeform
+    /// comment, see [`Self::all_comments`].
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Data, FreeformIdent, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// let freeform = FreeformIdent::new("com.apple.iTunes", "comment");
+    /// tag.set_data(freeform, Data::Utf8("freeform".into()));
+    /// assert_eq!(tag.comment(), None);
+    /// assert_eq!(tag.any_comment(), Some("freeform"));
+    ///
+    /// tag.set_comment("standard");
+    /// assert_eq!(tag.any_comment(), Some("standard"));
+    /// ```
+    pub fn any_comment(&self) -> Option<&str> {
+        self.all_comments().next()
+    }
+}
+
 /// ### Artwork
 impl Tag {
-    /// Returns all artwork images (`covr`).
+    /// Returns all artwork images (`covr`). The format of each image stored with the reserved or
+    /// an unknown datatype is detected from the signature of its data when reading, while an
+    /// image datatype is kept, so an explicitly set format is preserved. Empty placeholder
+    /// images, which some taggers write to reserve a slot for artwork, are skipped, but kept when
+    /// writing the tag.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Img, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_artwork_unchecked(Img::jpeg(Vec::new()));
+    /// assert_eq!(tag.artworks().count(), 0);
+    /// assert_eq!(tag.artwork(), None);
+    /// ```
     pub fn artworks(&self) -> impl Iterator<Item = ImgRef> {
-        self.images_of(&ident::ARTWORK)
+        self.images_of(&ident::ARTWORK).filter(|i| !i.data.is_empty())
+    }
+
+    /// Returns the number of artwork images (`covr`), without accessing the image data.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Img, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// assert_eq!(tag.artwork_count(), 0);
+    ///
+    /// tag.add_artwork(Img::jpeg(b"\xff\xd8\xfffront".to_vec())).unwrap();
+    /// tag.add_artwork(Img::png(b"\x89PNG\r\n\x1a\nback".to_vec())).unwrap();
+    /// assert_eq!(tag.artwork_count(), 2);
+    /// ```
+    pub fn artwork_count(&self) -> usize {
+        self.artworks().count()
     }
 
     /// Returns the first artwork image (`covr`).
     pub fn artwork(&self) -> Option<ImgRef> {
-        self.images_of(&ident::ARTWORK).next()
+        self.artworks().next()
     }
 
-    /// Removes and returns all artwork images (`covr`).
+    /// Returns the artwork image (`covr`) at the index, or `None` if there are fewer artwork
+    /// images. Empty placeholder images are skipped, like in [`Self::artworks`].
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Img, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.add_artwork(Img::jpeg(b"\xff\xd8\xfffront".to_vec())).unwrap();
+    /// tag.add_artwork(Img::png(b"\x89PNG\r\n\x1a\nback".to_vec())).unwrap();
+    ///
+    /// assert_eq!(tag.artwork_at(1), Some(Img::png(&b"\x89PNG\r\n\x1a\nback"[..])));
+    /// assert_eq!(tag.artwork_at(2), None);
+    /// ```
+    pub fn artwork_at(&self, index: usize) -> Option<ImgRef<'_>> {
+        self.artworks().nth(index)
+    }
+
+    /// Returns all localized artwork images (`covr`) and their locale indicators, which allow
+    /// region specific artwork to be distinguished. Artwork images without a language, or with the
+    /// undetermined language (`und`), are returned by [`Self::artworks`] instead.
+    pub fn artworks_localized(&self) -> impl Iterator<Item = (ImgRef<'_>, Locale)> {
+        let localized = match self.atoms.iter().find(|a| ident::ARTWORK == a.ident) {
+            Some(a) => a.localized.iter(),
+            None => [].iter(),
+        };
+        localized
+            .filter_map(|(l, d)| Some((d.image()?, Locale::from(*l))))
+            .filter(|(i, _)| !i.data.is_empty())
+    }
+
+    /// Removes and returns all artwork images (`covr`). Empty placeholder images are removed, but
+    /// not returned.
     pub fn take_artworks(&mut self) -> impl Iterator<Item = ImgBuf> + '_ {
-        self.take_images_of(&ident::ARTWORK)
+        self.take_images_of(&ident::ARTWORK).filter(|i| !i.data.is_empty())
     }
 
     /// Removes all and returns the first artwork image (`covr`).
     pub fn take_artwork(&mut self) -> Option<ImgBuf> {
-        self.take_images_of(&ident::ARTWORK).next()
+        self.take_artworks().next()
+    }
+
+    /// Copies the data of the artwork image (`covr`) at the index directly from the reader to the
+    /// writer, without reading the image, or any other metadata, into memory. Returns the image
+    /// format, or `None` if there is no artwork image at the index.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut reader = File::open("music.m4a").unwrap();
+    /// let mut writer = File::create("cover").unwrap();
+    /// let fmt = Tag::extract_artwork_to(0, &mut writer, &mut reader).unwrap();
+    /// println!("extracted {:?} image", fmt);
+    /// ```
+    pub fn extract_artwork_to(
+        index: usize,
+        writer: &mut impl Write,
+        reader: &mut (impl Read + Seek),
+    ) -> crate::Result<Option<ImgFmt>> {
+        atom::extract_artwork_to(reader, index, writer)
     }
 
-    /// Sets the artwork image data (`covr`). This will remove all other artworks.
-    pub fn set_artwork(&mut self, image: Img<impl Into<Vec<u8>>>) {
+    /// Sets the artwork image data (`covr`). This will remove all other artworks. The image is
+    /// stored as a [`Data::SharedImage`], so clones of this tag share it instead of copying it.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidImageData`](crate::ErrorKind::InvalidImageData)
+    /// if the data doesn't start with the signature of the image format, and an error of kind
+    /// [`ErrorKind::ArtworkTooLarge`](crate::ErrorKind::ArtworkTooLarge) if the data is longer
+    /// than the [`DEFAULT_ARTWORK_SIZE_LIMIT`], see [`Self::set_artwork_with_limit`].
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ErrorKind, Img, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    ///
+    /// let err = tag.set_artwork(Img::png(b"not an image".to_vec())).unwrap_err();
+    /// assert!(matches!(err.kind, ErrorKind::InvalidImageData));
+    /// assert_eq!(tag.artwork(), None);
+    ///
+    /// tag.set_artwork(Img::png(b"\x89PNG\r\n\x1a\n".to_vec())).unwrap();
+    /// assert!(tag.artwork().is_some());
+    ///
+    /// let clone = tag.clone();
+    /// assert_eq!(clone.artwork().unwrap().data.as_ptr(), tag.artwork().unwrap().data.as_ptr());
+    /// ```
+    pub fn set_artwork(&mut self, image: Img<impl Into<Vec<u8>>>) -> crate::Result<()> {
+        self.set_artwork_with_limit(image, Some(DEFAULT_ARTWORK_SIZE_LIMIT))
+    }
+
+    /// Sets the artwork image data (`covr`), checking its length against the limit instead of the
+    /// [`DEFAULT_ARTWORK_SIZE_LIMIT`], or not at all if it is `None`. This will remove all other
+    /// artworks. Like [`Self::set_artwork`], the image is shared by clones of this tag.
+    ///
+    /// Returns an error of kind [`ErrorKind::ArtworkTooLarge`](crate::ErrorKind::ArtworkTooLarge),
+    /// containing the limit, if the data is longer, so the image can be downscaled accordingly.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ErrorKind, Img, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// let image = Img::png(b"\x89PNG\r\n\x1a\n".repeat(16));
+    ///
+    /// let err = tag.set_artwork_with_limit(image.clone(), Some(64)).unwrap_err();
+    /// assert!(matches!(err.kind, ErrorKind::ArtworkTooLarge { len: 128, limit: 64 }));
+    ///
+    /// tag.set_artwork_with_limit(image, None).unwrap();
+    /// assert!(tag.artwork().is_some());
+    /// ```
+    pub fn set_artwork_with_limit(
+        &mut self,
+        image: Img<impl Into<Vec<u8>>>,
+        limit: Option<usize>,
+    ) -> crate::Result<()> {
+        let image = Img::new(image.fmt, image.data.into());
+        check_artwork_size(&image, limit)?;
+        check_artwork_signature(&image)?;
+        self.set_data(ident::ARTWORK, Data::SharedImage(Arc::new(image)));
+        Ok(())
+    }
+
+    /// Sets the artwork image data (`covr`) after decoding it, to make sure it is neither truncated
+    /// nor otherwise corrupt. This will remove all other artworks. Like [`Self::set_artwork`], the
+    /// image is shared by clones of this tag.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidImageData`](crate::ErrorKind::InvalidImageData)
+    /// if the data can't be decoded as the image format.
+    #[cfg(feature = "image")]
+    pub fn set_artwork_validated(&mut self, image: Img<impl Into<Vec<u8>>>) -> crate::Result<()> {
+        let image = Img::new(image.fmt, image.data.into());
+        check_artwork_size(&image, Some(DEFAULT_ARTWORK_SIZE_LIMIT))?;
+        if let Err(e) = image.validate() {
+            return Err(crate::Error::new(
+                crate::ErrorKind::InvalidImageData,
+                format!("Error decoding {:?} image: {}", image.fmt, e),
+            ));
+        }
+        self.set_data(ident::ARTWORK, Data::SharedImage(Arc::new(image)));
+        Ok(())
+    }
+
+    /// Re-encodes all artworks (`covr`) that aren't jpeg images as jpeg, with the quality of
+    /// [`WriteConfig::artwork_jpeg_quality`]. The order of the artworks is kept. Returns the number
+    /// of re-encoded artworks.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidImageData`](crate::ErrorKind::InvalidImageData)
+    /// if an image can't be decoded as its format, in which case no artwork is modified.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Img, ImgFmt, Tag, WriteConfig};
+    ///
+    /// let png = std::fs::read("files/artwork.png").unwrap();
+    /// let mut tag = Tag::default();
+    /// tag.set_artwork(Img::png(png)).unwrap();
+    ///
+    /// let cfg = WriteConfig { artwork_jpeg_quality: 70, ..Default::default() };
+    /// assert_eq!(tag.artworks_to_jpeg(&cfg).unwrap(), 1);
+    /// assert_eq!(tag.artwork().unwrap().fmt, ImgFmt::Jpeg);
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn artworks_to_jpeg(&mut self, cfg: &WriteConfig) -> crate::Result<usize> {
+        let mut converted = Vec::new();
+        for img in self.artworks().filter(|i| i.fmt != ImgFmt::Jpeg) {
+            converted.push(img.to_jpeg(cfg.artwork_jpeg_quality)?);
+        }
+
+        let count = converted.len();
+        let mut converted = converted.into_iter();
+        for d in self.data_mut_of(&ident::ARTWORK) {
+            if matches!(d.image(), Some(i) if i.fmt != ImgFmt::Jpeg && !i.data.is_empty()) {
+                if let Some(jpeg) = converted.next() {
+                    *d = jpeg.into();
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Sets the artwork image data (`covr`) without checking the image signature. This will remove
+    /// all other artworks.
+    pub fn set_artwork_unchecked(&mut self, image: Img<impl Into<Vec<u8>>>) {
         self.set_data(ident::ARTWORK, image.into());
     }
 
+    /// Sets the artwork image data (`covr`) stored with the image format, overriding what the
+    /// image signature would indicate. This will remove all other artworks.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ImgFmt, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_artwork_with_fmt(b"\xff\xd8\x00progressive".to_vec(), ImgFmt::Jpeg);
+    /// assert_eq!(tag.artwork().unwrap().fmt, ImgFmt::Jpeg);
+    /// ```
+    pub fn set_artwork_with_fmt(&mut self, data: impl Into<Vec<u8>>, fmt: ImgFmt) {
+        self.set_artwork_unchecked(Img::new(fmt, data));
+    }
+
+    /// Sets the artwork image (`covr`) to the shared image, so multiple tags can reference the same
+    /// image data instead of each holding a copy. This will remove all other artworks.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidImageData`](crate::ErrorKind::InvalidImageData)
+    /// if the data doesn't start with the signature of the image format.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use mp4ameta::{Img, Tag};
+    ///
+    /// let cover = Arc::new(Img::jpeg(b"\xff\xd8\xffimage".to_vec()));
+    /// let mut tags = vec![Tag::default(), Tag::default()];
+    /// for t in tags.iter_mut() {
+    ///     t.set_artwork_shared(cover.clone()).unwrap();
+    /// }
+    ///
+    /// assert_eq!(Arc::strong_count(&cover), 3);
+    /// assert_eq!(tags[0].artwork().unwrap().data.as_ptr(), cover.data.as_ptr());
+    /// ```
+    pub fn set_artwork_shared(&mut self, image: Arc<ImgBuf>) -> crate::Result<()> {
+        check_artwork(&image)?;
+        self.set_data(ident::ARTWORK, Data::SharedImage(image));
+        Ok(())
+    }
+
     /// Sets all artwork image data (`covr`). This will remove all other artworks.
-    pub fn set_artworks(&mut self, images: impl IntoIterator<Item = ImgBuf>) {
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidImageData`](crate::ErrorKind::InvalidImageData)
+    /// if the data of any image doesn't start with the signature of its image format, in which
+    /// case the artworks are left unchanged.
+    pub fn set_artworks(&mut self, images: impl IntoIterator<Item = ImgBuf>) -> crate::Result<()> {
+        let images: Vec<ImgBuf> = images.into_iter().collect();
+        images.iter().try_for_each(check_artwork)?;
         self.set_all_data(ident::ARTWORK, images.into_iter().map(Img::into));
+        Ok(())
     }
 
-    /// Adds artwork image data (`covr`).
-    pub fn add_artwork(&mut self, image: Img<impl Into<Vec<u8>>>) {
-        self.add_data(ident::ARTWORK, image.into());
+    /// Adds artwork image data (`covr`). The image is stored as a [`Data::SharedImage`], so
+    /// clones of this tag share it instead of copying it.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidImageData`](crate::ErrorKind::InvalidImageData)
+    /// if the data doesn't start with the signature of the image format, and an error of kind
+    /// [`ErrorKind::ArtworkTooLarge`](crate::ErrorKind::ArtworkTooLarge) if the data is longer
+    /// than the [`DEFAULT_ARTWORK_SIZE_LIMIT`], see [`Self::add_artwork_with_limit`].
+    pub fn add_artwork(&mut self, image: Img<impl Into<Vec<u8>>>) -> crate::Result<()> {
+        self.add_artwork_with_limit(image, Some(DEFAULT_ARTWORK_SIZE_LIMIT))
+    }
+
+    /// Adds artwork image data (`covr`), checking its length against the limit instead of the
+    /// [`DEFAULT_ARTWORK_SIZE_LIMIT`], or not at all if it is `None`. Like [`Self::add_artwork`],
+    /// the image is shared by clones of this tag.
+    ///
+    /// Returns an error of kind [`ErrorKind::ArtworkTooLarge`](crate::ErrorKind::ArtworkTooLarge),
+    /// containing the limit, if the data is longer, so the image can be downscaled accordingly.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ErrorKind, Img, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// let image = Img::png(b"\x89PNG\r\n\x1a\n".repeat(16));
+    ///
+    /// let err = tag.add_artwork_with_limit(image.clone(), Some(64)).unwrap_err();
+    /// assert!(matches!(err.kind, ErrorKind::ArtworkTooLarge { len: 128, limit: 64 }));
+    ///
+    /// tag.add_artwork_with_limit(image.clone(), None).unwrap();
+    /// tag.add_artwork_with_limit(image, None).unwrap();
+    /// assert_eq!(tag.artwork_count(), 2);
+    ///
+    /// let clone = tag.clone();
+    /// assert_eq!(clone.artwork().unwrap().data.as_ptr(), tag.artwork().unwrap().data.as_ptr());
+    /// ```
+    pub fn add_artwork_with_limit(
+        &mut self,
+        image: Img<impl Into<Vec<u8>>>,
+        limit: Option<usize>,
+    ) -> crate::Result<()> {
+        let image = Img::new(image.fmt, image.data.into());
+        check_artwork_size(&image, limit)?;
+        check_artwork_signature(&image)?;
+        self.add_data(ident::ARTWORK, Data::SharedImage(Arc::new(image)));
+        Ok(())
     }
 
     /// Adds artwork image data (`covr`).
-    pub fn add_artworks(&mut self, images: impl IntoIterator<Item = ImgBuf>) {
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidImageData`](crate::ErrorKind::InvalidImageData)
+    /// if the data of any image doesn't start with the signature of its image format, in which
+    /// case no artwork is added.
+    pub fn add_artworks(&mut self, images: impl IntoIterator<Item = ImgBuf>) -> crate::Result<()> {
+        let images: Vec<ImgBuf> = images.into_iter().collect();
+        images.iter().try_for_each(check_artwork)?;
         self.add_all_data(ident::ARTWORK, images.into_iter().map(Img::into));
+        Ok(())
     }
 
     /// Removes all artworks (`covr`).
@@ -212,6 +1196,108 @@ impl Tag {
         self.remove_data_of(&ident::ARTWORK);
     }
 
+    /// Removes additional artworks (`covr`), starting with the last one, until the size of the
+    /// metadata, as returned by [`Self::metadata_size`], is at most `max_bytes`. The first
+    /// artwork, which is usually the front cover, is always kept and no image is re-encoded.
+    /// Returns the number of removed artworks.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Img, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.add_artwork(Img::jpeg(vec![0xff, 0xd8, 0xff, 0x00])).unwrap();
+    /// tag.add_artwork(Img::png(b"\x89PNG\r\n\x1a\n".repeat(128))).unwrap();
+    /// tag.add_artwork(Img::png(b"\x89PNG\r\n\x1a\n".repeat(128))).unwrap();
+    ///
+    /// assert_eq!(tag.shrink_artwork_to_fit(1500), 1);
+    /// assert_eq!(tag.artworks().count(), 2);
+    /// assert_eq!(tag.shrink_artwork_to_fit(0), 1);
+    /// assert_eq!(tag.artworks().count(), 1);
+    /// ```
+    pub fn shrink_artwork_to_fit(&mut self, max_bytes: u64) -> usize {
+        fn contains_artwork(a: &AtomData) -> bool {
+            ident::ARTWORK == a.ident && a.data.iter().any(|d| d.image().is_some())
+        }
+
+        let mut removed = 0;
+        while self.metadata_size() > max_bytes && self.artwork_count() > 1 {
+            let atom = self.atoms.iter_mut().rev().find(|a| contains_artwork(a));
+            let atom = match atom {
+                Some(a) => a,
+                None => break,
+            };
+            if let Some(i) = atom.data.iter().rposition(|d| d.image().is_some()) {
+                atom.data.remove(i);
+                removed += 1;
+            }
+        }
+        self.atoms.retain(|a| ident::ARTWORK != a.ident || !a.data.is_empty());
+        removed
+    }
+
+    /// Removes artworks (`covr`) whose image data is byte-identical to a previous artwork, which
+    /// can accumulate from repeated tagging. The order of the remaining artworks is kept. Returns
+    /// the number of removed artworks.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Img, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.add_artwork(Img::jpeg(b"\xff\xd8\xfffront".to_vec())).unwrap();
+    /// tag.add_artwork(Img::png(b"\x89PNG\r\n\x1a\nback".to_vec())).unwrap();
+    /// tag.add_artwork(Img::jpeg(b"\xff\xd8\xfffront".to_vec())).unwrap();
+    ///
+    /// assert_eq!(tag.dedup_artwork(), 1);
+    /// let artworks: Vec<_> = tag.artworks().map(|i| i.data).collect();
+    /// assert_eq!(artworks, [&b"\xff\xd8\xfffront"[..], &b"\x89PNG\r\n\x1a\nback"[..]]);
+    /// ```
+    pub fn dedup_artwork(&mut self) -> usize {
+        // images are grouped by the hash of their data, so they don't all have to be compared
+        let mut seen: HashMap<u64, Vec<&[u8]>> = HashMap::new();
+        let mut duplicates = Vec::new();
+        for (i, a) in self.atoms.iter().enumerate().filter(|(_, a)| ident::ARTWORK == a.ident) {
+            for (j, img) in a.data.iter().enumerate().filter_map(|(j, d)| Some((j, d.image()?))) {
+                let mut hasher = DefaultHasher::new();
+                img.data.hash(&mut hasher);
+                let same_hash = seen.entry(hasher.finish()).or_default();
+                match same_hash.contains(&img.data) {
+                    true => duplicates.push((i, j)),
+                    false => same_hash.push(img.data),
+                }
+            }
+        }
+
+        for (i, j) in duplicates.iter().rev() {
+            self.atoms[*i].data.remove(*j);
+        }
+        duplicates.len()
+    }
+
+    /// Converts all images, including artworks, into shared images. Clones of the tag will then
+    /// share the image data instead of copying it. An image is only copied once it's mutated.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ident, Img, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_artwork(Img::jpeg(b"\xff\xd8\xffimage".to_vec())).unwrap();
+    /// tag.share_images();
+    ///
+    /// let mut clone = tag.clone();
+    /// assert_eq!(tag.artwork().unwrap().data.as_ptr(), clone.artwork().unwrap().data.as_ptr());
+    ///
+    /// clone.images_mut_of(&ident::ARTWORK).next().unwrap().data.push(b'!');
+    /// assert_ne!(tag.artwork(), clone.artwork());
+    /// ```
+    pub fn share_images(&mut self) {
+        for a in self.atoms.iter_mut() {
+            a.data.iter_mut().for_each(Data::share_image);
+        }
+    }
+
     /// Returns information about all artworks formatted in an easily readable way.
     fn format_artworks(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fn format_artwork(f: &mut fmt::Formatter, i: ImgRef) -> fmt::Result {
@@ -235,7 +1321,7 @@ impl Tag {
             Ok(())
         }
 
-        if self.artworks().count() > 1 {
+        if self.artwork_count() > 1 {
             writeln!(f, "artworks:")?;
             for a in self.artworks() {
                 write!(f, "    ")?;
@@ -249,6 +1335,93 @@ impl Tag {
     }
 }
 
+/// The default maximum length in bytes of artwork image data. Larger images break the import of
+/// files by some players, like iTunes.
+pub const DEFAULT_ARTWORK_SIZE_LIMIT: usize = 20 * 1024 * 1024;
+
+/// Checks whether the image data starts with the signature of its image format and doesn't exceed
+/// the [`DEFAULT_ARTWORK_SIZE_LIMIT`].
+fn check_artwork(image: &ImgBuf) -> crate::Result<()> {
+    check_artwork_size(image, Some(DEFAULT_ARTWORK_SIZE_LIMIT))?;
+    check_artwork_signature(image)
+}
+
+/// Checks whether the length of the image data doesn't exceed the limit.
+fn check_artwork_size(image: &ImgBuf, limit: Option<usize>) -> crate::Result<()> {
+    let len = image.data.len();
+    match limit {
+        Some(limit) if len > limit => Err(crate::Error::new(
+            crate::ErrorKind::ArtworkTooLarge { len, limit },
+            format!("Image data of {} bytes exceeds the limit of {} bytes", len, limit),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Checks whether the image data starts with the signature of its image format.
+fn check_artwork_signature(image: &ImgBuf) -> crate::Result<()> {
+    match ImgFmt::sniff(&image.data) {
+        Some(fmt) if fmt == image.fmt => Ok(()),
+        Some(fmt) => Err(crate::Error::new(
+            crate::ErrorKind::InvalidImageData,
+            format!("Image data is of format {:?} but was declared as {:?}", fmt, image.fmt),
+        )),
+        None => Err(crate::Error::new(
+            crate::ErrorKind::InvalidImageData,
+            format!("Image data doesn't start with a known {:?} signature", image.fmt),
+        )),
+    }
+}
+
+/// ### Encoder source
+impl Tag {
+    /// Returns the application that likely encoded the file, detected from the encoder (`©too`),
+    /// see [`EncoderSource::detect`].
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{EncoderSource, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// assert_eq!(tag.likely_source(), None);
+    ///
+    /// tag.set_encoder("Lavf58.29.100");
+    /// assert_eq!(tag.likely_source(), Some(EncoderSource::Ffmpeg));
+    /// ```
+    pub fn likely_source(&self) -> Option<EncoderSource> {
+        self.encoder().and_then(EncoderSource::detect)
+    }
+}
+
+/// ### Gapless album
+impl Tag {
+    /// Returns whether the track is part of a gapless album (`pgap`), so players shouldn't insert
+    /// silence between it and the adjacent tracks. This is the same flag as
+    /// [`Self::gapless_playback`].
+    pub fn gapless(&self) -> bool {
+        self.gapless_playback()
+    }
+
+    /// Sets whether the track is part of a gapless album (`pgap`). Unlike
+    /// [`Self::remove_gapless_playback`], setting it to false explicitly stores the flag as 0.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ident, Data, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_gapless(true);
+    /// assert!(tag.gapless());
+    ///
+    /// tag.set_gapless(false);
+    /// assert!(!tag.gapless());
+    /// assert_eq!(tag.data_of(&ident::GAPLESS_PLAYBACK).next(), Some(&Data::BeSigned(vec![0])));
+    /// ```
+    pub fn set_gapless(&mut self, gapless: bool) {
+        self.set_data(ident::GAPLESS_PLAYBACK, Data::BeSigned(vec![gapless as u8]));
+    }
+}
+
 /// ### Media type
 impl Tag {
     /// Returns the media type (`stik`).
@@ -311,6 +1484,170 @@ impl Tag {
     }
 }
 
+/// ### XMP
+impl Tag {
+    /// Returns the raw XMP metadata packet stored inside the user extension (`uuid`) atom.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut tag = Tag::default();
+    /// assert_eq!(tag.xmp(), None);
+    ///
+    /// tag.set_xmp(b"<x:xmpmeta/>".to_vec());
+    /// assert_eq!(tag.xmp(), Some(&b"<x:xmpmeta/>"[..]));
+    ///
+    /// tag.remove_xmp();
+    /// assert_eq!(tag.xmp(), None);
+    /// ```
+    pub fn xmp(&self) -> Option<&[u8]> {
+        self.xmp.as_deref()
+    }
+
+    /// Sets the raw XMP metadata packet stored inside the user extension (`uuid`) atom.
+    pub fn set_xmp(&mut self, xmp: impl Into<Vec<u8>>) {
+        self.xmp = Some(xmp.into());
+    }
+
+    /// Removes and returns the raw XMP metadata packet.
+    pub fn take_xmp(&mut self) -> Option<Vec<u8>> {
+        self.xmp.take()
+    }
+
+    /// Removes the raw XMP metadata packet.
+    pub fn remove_xmp(&mut self) {
+        self.xmp = None;
+    }
+}
+
+/// ### Location
+impl Tag {
+    /// Returns the geographic location parsed from the ISO 6709 string (`©xyz`).
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Location, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_location(Location { lat: 12.3456, lon: -98.7654, alt: None });
+    /// assert_eq!(tag.location(), Some(Location::new(12.3456, -98.7654)));
+    /// assert_eq!(tag.strings_of(&mp4ameta::ident::LOCATION).next(), Some("+12.3456-098.7654/"));
+    /// ```
+    pub fn location(&self) -> Option<Location> {
+        self.strings_of(&ident::LOCATION).next()?.parse().ok()
+    }
+
+    /// Sets the geographic location as an ISO 6709 string (`©xyz`). The coordinates are rounded to
+    /// 4 decimal places.
+    pub fn set_location(&mut self, location: Location) {
+        self.set_data(ident::LOCATION, Data::Utf8(location.to_string()));
+    }
+
+    /// Removes the geographic location (`©xyz`).
+    pub fn remove_location(&mut self) {
+        self.remove_data_of(&ident::LOCATION);
+    }
+
+    fn format_location(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location() {
+            Some(l) => writeln!(f, "location: {}", l),
+            None => Ok(()),
+        }
+    }
+}
+
+/// ### Localized data
+///
+/// Data atoms contain a locale indicator, which allows multiple localized values of a metadata
+/// atom to coexist. Languages are identified by their 3 letter ISO 639-2/T code, for example
+/// `eng` or `jpn`. Values without a language, or with the undetermined language (`und`), are
+/// accessed using the regular data accessors. So is the first value of an atom when reading,
+/// regardless of its language, so metadata only stored in a single language isn't hidden.
+impl Tag {
+    /// Returns the localized string corresponding to the identifier and language.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ident, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_title("title");
+    /// tag.set_localized(ident::TITLE, "jpn", "タイトル").unwrap();
+    ///
+    /// assert_eq!(tag.title(), Some("title"));
+    /// assert_eq!(tag.localized(&ident::TITLE, "jpn"), Some("タイトル"));
+    /// assert_eq!(tag.localized(&ident::TITLE, "eng"), None);
+    /// assert_eq!(tag.languages_of(&ident::TITLE).collect::<Vec<_>>(), vec!["jpn"]);
+    /// ```
+    pub fn localized(&self, ident: &impl Ident, lang: &str) -> Option<&str> {
+        let code = atom::pack_language(lang)?;
+        let atom = self.atoms.iter().find(|a| ident == &a.ident)?;
+        atom.localized.iter().find(|(l, _)| language(*l) == code).and_then(|(_, d)| d.string())
+    }
+
+    /// Returns the languages of the localized data corresponding to the identifier.
+    pub fn languages_of<'a>(&'a self, ident: &'a impl Ident) -> impl Iterator<Item = String> + 'a {
+        let localized = match self.atoms.iter().find(|a| ident == &a.ident) {
+            Some(a) => a.localized.iter(),
+            None => [].iter(),
+        };
+        localized.filter_map(|(l, _)| atom::unpack_language(language(*l)))
+    }
+
+    /// Sets the localized string corresponding to the identifier and language, replacing an
+    /// existing one of the same language. This will return an error if the language isn't a 3
+    /// letter lowercase code.
+    pub fn set_localized(
+        &mut self,
+        ident: impl Ident + Into<DataIdent>,
+        lang: &str,
+        value: impl Into<String>,
+    ) -> crate::Result<()> {
+        let code = atom::pack_language(lang).ok_or_else(|| {
+            crate::Error::new(
+                crate::ErrorKind::Parsing,
+                format!("Invalid language '{}', expected a 3 letter lowercase code", lang),
+            )
+        })?;
+        let [l1, l0] = code.to_be_bytes();
+        let data = Data::Utf8(value.into());
+
+        let atom = match self.atoms.iter_mut().position(|a| ident == a.ident) {
+            Some(i) => &mut self.atoms[i],
+            None => {
+                self.atoms.push(AtomData::new(ident.into(), Vec::new()));
+                self.atoms.last_mut().unwrap()
+            }
+        };
+        match atom.localized.iter_mut().find(|(l, _)| language(*l) == code) {
+            Some((_, d)) => *d = data,
+            None => atom.localized.push(([0, 0, l1, l0], data)),
+        }
+
+        Ok(())
+    }
+
+    /// Removes the localized data corresponding to the identifier and language.
+    pub fn remove_localized(&mut self, ident: &impl Ident, lang: &str) {
+        let code = match atom::pack_language(lang) {
+            Some(c) => c,
+            None => return,
+        };
+        if let Some(i) = self.atoms.iter().position(|a| ident == &a.ident) {
+            self.atoms[i].localized.retain(|(l, _)| language(*l) != code);
+            if self.atoms[i].data.is_empty() && self.atoms[i].localized.is_empty() {
+                self.atoms.remove(i);
+            }
+        }
+    }
+}
+
+/// Returns the language code of the locale indicator.
+fn language(locale: [u8; 4]) -> u16 {
+    u16::from_be_bytes([locale[2], locale[3]])
+}
+
 /// ## Data accessors
 impl Tag {
     /// Returns references to all byte data corresponding to the identifier.
@@ -349,6 +1686,30 @@ impl Tag {
         self.data_mut_of(ident).filter_map(Data::bytes_mut)
     }
 
+    /// Returns the value of the flag corresponding to the identifier. A flag is true if the first
+    /// byte of its first byte data is nonzero, and false if it is absent or empty.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Tag, Data, Fourcc};
+    ///
+    /// let mut tag = Tag::default();
+    /// let test = Fourcc(*b"test");
+    /// assert!(!tag.flag_of(&test));
+    ///
+    /// tag.set_data(test, Data::BeSigned(vec![]));
+    /// assert!(!tag.flag_of(&test));
+    ///
+    /// tag.set_data(test, Data::BeSigned(vec![0]));
+    /// assert!(!tag.flag_of(&test));
+    ///
+    /// tag.set_data(test, Data::BeSigned(vec![2]));
+    /// assert!(tag.flag_of(&test));
+    /// ```
+    pub fn flag_of(&self, ident: &impl Ident) -> bool {
+        matches!(self.bytes_of(ident).next(), Some(&[b, ..]) if b != 0)
+    }
+
     /// Removes the atom corresponding to the identifier and returns all of it's byte data.
     ///
     /// # Example
@@ -482,6 +1843,32 @@ impl Tag {
         self.take_data_of(ident).filter_map(Data::into_image)
     }
 
+    /// Returns an iterator over the identifiers and references to the data of all metadata
+    /// atoms, in the order they are stored. Freeform atoms are yielded with their full freeform
+    /// identifier. Atoms without a dedicated accessor are included as well, with strings decoded
+    /// according to their datatype as [`Data::Utf8`] or [`Data::Utf16`].
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Tag, Data, DataIdent};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_title("title");
+    /// tag.set_isrc("isrc");
+    ///
+    /// let mut entries = tag.entries();
+    /// let (ident, data) = entries.next().unwrap();
+    /// assert_eq!(ident, &DataIdent::fourcc(*b"\xa9nam"));
+    /// assert_eq!(data, &[Data::Utf8("title".into())]);
+    /// let (ident, data) = entries.next().unwrap();
+    /// assert_eq!(ident, &DataIdent::freeform("com.apple.iTunes", "ISRC"));
+    /// assert_eq!(data, &[Data::Utf8("isrc".into())]);
+    /// assert!(entries.next().is_none());
+    /// ```
+    pub fn entries(&self) -> impl Iterator<Item = (&DataIdent, &[Data])> {
+        self.atoms.iter().map(|a| (&a.ident, a.data.as_slice()))
+    }
+
     /// Returns references to all data corresponding to the identifier.
     ///
     /// # Example
@@ -1060,6 +2447,64 @@ impl Tag {
         }
     }
 
+    /// Converts all strings, including localized ones, to the encoding by replacing
+    /// [`Data::Utf8`] with [`Data::Utf16`] or vice versa. The content of the strings is kept.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ident, Data, StringEncoding, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_title("title");
+    /// tag.transcode_strings(StringEncoding::Utf16);
+    ///
+    /// assert_eq!(tag.data_of(&ident::TITLE).next(), Some(&Data::Utf16("title".into())));
+    /// assert_eq!(tag.title(), Some("title"));
+    /// ```
+    pub fn transcode_strings(&mut self, to: StringEncoding) {
+        for a in self.atoms.iter_mut() {
+            let data = a.data.iter_mut().chain(a.localized.iter_mut().map(|(_, d)| d));
+            for d in data {
+                let transcoded = match (&mut *d, to) {
+                    (Data::Utf8(s), StringEncoding::Utf16) => Data::Utf16(std::mem::take(s)),
+                    (Data::Utf16(s), StringEncoding::Utf8) => Data::Utf8(std::mem::take(s)),
+                    _ => continue,
+                };
+                *d = transcoded;
+            }
+        }
+    }
+
+    /// Retains only the data matching the predicate, including localized data. Atoms that don't
+    /// contain any data afterwards are removed.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Tag, Data, DataIdent, Fourcc};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_title("title");
+    /// tag.add_data(Fourcc(*b"test"), Data::Reserved(vec![0; 16]));
+    /// tag.add_data(Fourcc(*b"test"), Data::Utf8("short".into()));
+    ///
+    /// tag.retain(|_, d| d.len() < 10);
+    ///
+    /// let test = DataIdent::from(Fourcc(*b"test"));
+    /// assert_eq!(tag.title(), Some("title"));
+    /// assert_eq!(tag.data().nth(1), Some((&test, &Data::Utf8("short".into()))));
+    ///
+    /// tag.retain(|ident, _| Fourcc(*b"test") != *ident);
+    /// assert_eq!(tag.data().count(), 1);
+    /// ```
+    pub fn retain(&mut self, mut predicate: impl FnMut(&DataIdent, &Data) -> bool) {
+        for a in self.atoms.iter_mut() {
+            let ident = &a.ident;
+            a.data.retain(|d| predicate(ident, d));
+            a.localized.retain(|(_, d)| predicate(ident, d));
+        }
+        self.atoms.retain(|a| !a.data.is_empty() || !a.localized.is_empty());
+    }
+
     /// Retains only the byte data matching the predicate. Other data will remain unaffected.
     ///
     /// # Example
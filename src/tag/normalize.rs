@@ -0,0 +1,129 @@
+use crate::{ident, Data, DataIdent, Fourcc, Tag, STANDARD_GENRES};
+
+/// Constructs data of a datatype from big endian integer bytes.
+type IntegerData = fn(Vec<u8>) -> Data;
+
+/// Integer atoms with the datatype and the width in bytes their values are conventionally stored
+/// with, matching the setters of the tag.
+const INTEGER_ATOMS: [(Fourcc, IntegerData, usize); 12] = [
+    (ident::ADVISORY_RATING, Data::Reserved, 1),
+    (ident::BPM, Data::BeSigned, 2),
+    (ident::COMPILATION, Data::BeSigned, 1),
+    (ident::GAPLESS_PLAYBACK, Data::BeSigned, 1),
+    (ident::MEDIA_TYPE, Data::Reserved, 1),
+    (ident::MOVEMENT_COUNT, Data::BeSigned, 2),
+    (ident::MOVEMENT_INDEX, Data::BeSigned, 2),
+    (ident::PODCAST, Data::BeSigned, 1),
+    (ident::SHOW_MOVEMENT, Data::BeSigned, 1),
+    (ident::STANDARD_GENRE, Data::Reserved, 2),
+    (ident::TV_EPISODE, Data::BeSigned, 4),
+    (ident::TV_SEASON, Data::BeSigned, 4),
+];
+
+/// ### Normalize
+impl Tag {
+    /// Canonicalizes the metadata, so tags containing the same metadata produce identical bytes
+    /// when written, regardless of the tool that wrote them.
+    ///
+    /// - Metadata atoms are ordered canonically: first atoms identified by a fourcc, sorted by
+    ///   the bytes of their fourcc, then freeform atoms (`----`), sorted by mean and name, then
+    ///   QuickTime metadata items, sorted by their key. The order of data inside an atom is kept.
+    /// - Standard genres (`gnre`) that are also stored as a custom genre (`©gen`) are removed.
+    /// - Trailing NUL characters of strings are trimmed.
+    /// - Integer values of known atoms, like the BPM (`tmpo`) or compilation flag (`cpil`), are
+    ///   stored with the datatype and width the setters of this tag use. Negative signed values
+    ///   are sign extended, and values that don't fit into that width are left as they are.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ident, Data, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_title("title\0\0");
+    /// tag.set_data(ident::BPM, Data::BeSigned(vec![0, 0, 0, 120]));
+    /// tag.set_artist("artist");
+    /// tag.set_standard_genre(18);
+    /// tag.set_custom_genre("Rock");
+    /// tag.normalize();
+    ///
+    /// assert_eq!(tag.title(), Some("title"));
+    /// assert_eq!(tag.data_of(&ident::BPM).next(), Some(&Data::BeSigned(vec![0, 120])));
+    /// assert_eq!(tag.genres().collect::<Vec<_>>(), vec!["Rock"]);
+    ///
+    /// let idents: Vec<String> = tag.data().map(|(i, _)| i.to_string()).collect();
+    /// assert_eq!(idents, ["tmpo", "©ART", "©gen", "©nam"]);
+    /// ```
+    pub fn normalize(&mut self) {
+        let custom_genres: Vec<String> = self.custom_genres().map(str::to_lowercase).collect();
+        let is_redundant_genre = |d: &Data| match d {
+            Data::Reserved(v) | Data::BeSigned(v) if v.len() >= 2 => {
+                let code = u16::from_be_bytes([v[0], v[1]]) as usize;
+                let name = code.checked_sub(1).and_then(|i| STANDARD_GENRES.get(i));
+                matches!(name, Some(n) if custom_genres.contains(&n.to_lowercase()))
+            }
+            _ => false,
+        };
+        for a in self.atoms.iter_mut().filter(|a| ident::STANDARD_GENRE == a.ident) {
+            a.data.retain(|d| !is_redundant_genre(d));
+        }
+        self.atoms.retain(|a| ident::STANDARD_GENRE != a.ident || !a.is_empty());
+
+        for a in self.atoms.iter_mut() {
+            let conventional = INTEGER_ATOMS.iter().find(|(f, _, _)| *f == a.ident);
+            let data = a.data.iter_mut().chain(a.localized.iter_mut().map(|(_, d)| d));
+            for d in data {
+                let signed = d.is_be_signed();
+                let resized = match d {
+                    Data::Utf8(s) | Data::Utf16(s) => {
+                        let len = s.trim_end_matches('\0').len();
+                        s.truncate(len);
+                        None
+                    }
+                    Data::Reserved(v) | Data::BeSigned(v) => {
+                        conventional.and_then(|(_, datatype, width)| {
+                            Some(datatype(resize_be_int(v, *width, signed)?))
+                        })
+                    }
+                    _ => None,
+                };
+                if let Some(r) = resized {
+                    *d = r;
+                }
+            }
+        }
+
+        self.atoms.sort_by(|a, b| sort_key(&a.ident).cmp(&sort_key(&b.ident)));
+    }
+}
+
+fn sort_key(ident: &DataIdent) -> (u8, &[u8], &str, &str) {
+    match ident {
+        DataIdent::Fourcc(f) => (0, &f.0, "", ""),
+        DataIdent::Freeform { mean, name } => (1, &[], mean, name),
+        DataIdent::Key(k) => (2, &[], k, ""),
+    }
+}
+
+/// Returns the big endian integer resized to the width, or `None` if its value doesn't fit.
+/// Negative signed integers are sign extended.
+fn resize_be_int(bytes: &[u8], width: usize, signed: bool) -> Option<Vec<u8>> {
+    let negative = signed && matches!(bytes.first(), Some(b) if b & 0x80 != 0);
+    let fill = if negative { 0xFF } else { 0 };
+
+    // leading fill bytes are redundant, unless the sign of a signed value depends on them
+    let mut value = bytes;
+    while let [first, rest @ ..] = value {
+        let keeps_sign = !signed || matches!(rest.first(), Some(b) if (b & 0x80 != 0) == negative);
+        if *first != fill || !keeps_sign {
+            break;
+        }
+        value = rest;
+    }
+    if value.len() > width {
+        return None;
+    }
+
+    let mut resized = vec![fill; width - value.len()];
+    resized.extend_from_slice(value);
+    Some(resized)
+}
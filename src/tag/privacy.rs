@@ -0,0 +1,94 @@
+use crate::{ident, DataIdent, Fourcc, Ident, Tag};
+
+/// Atoms that may identify the owner of a file, where it was recorded, or the software that was
+/// used, like the iTunes account (`apID`) and store identifiers that iTunes embeds into purchased
+/// files.
+const PRIVATE_ATOMS: [Fourcc; 13] = [
+    Fourcc(*b"akID"),
+    Fourcc(*b"apID"),
+    Fourcc(*b"atID"),
+    Fourcc(*b"cmID"),
+    Fourcc(*b"cnID"),
+    Fourcc(*b"geID"),
+    Fourcc(*b"ownr"),
+    Fourcc(*b"plID"),
+    Fourcc(*b"sfID"),
+    Fourcc(*b"xid "),
+    ident::PURCHASE_DATE,
+    ident::LOCATION,
+    ident::ENCODER,
+];
+
+/// The prefix of the keys of QuickTime metadata items containing location information, like
+/// `com.apple.quicktime.location.ISO6709`, which phones write to recordings.
+const LOCATION_KEY_PREFIX: &str = "com.apple.quicktime.location.";
+
+/// ### Privacy
+impl Tag {
+    /// Removes metadata that may identify the owner of the file, where it was recorded, or the
+    /// software that was used, before sharing it. This includes the iTunes account (`apID`) and
+    /// owner (`ownr`), the purchase date (`purd`), store identifiers like `cnID`, `plID` or
+    /// `sfID`, the geographic location (`©xyz`), QuickTime location items like
+    /// `com.apple.quicktime.location.ISO6709`, the encoder (`©too`), and the XMP metadata packet,
+    /// which may contain device and location information. Returns the number of removed atoms,
+    /// not counting the XMP packet.
+    ///
+    /// When the tag is written afterwards, a QuickTime location stored directly inside the user
+    /// data atom (`udta`) is overwritten with a free atom, and the QuickTime metadata atom
+    /// (`moov.meta`) is rewritten without the removed items.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Data, Fourcc, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_title("title");
+    /// tag.set_data(Fourcc(*b"apID"), Data::Utf8("user@example.com".into()));
+    /// tag.set_data(Fourcc(*b"cnID"), Data::BeSigned(vec![0, 0, 0, 7]));
+    /// tag.set_xmp(b"<x:xmpmeta/>".to_vec());
+    ///
+    /// assert_eq!(tag.strip_private(), 2);
+    /// assert_eq!(tag.title(), Some("title"));
+    /// assert_eq!(tag.data().count(), 1);
+    /// assert_eq!(tag.xmp(), None);
+    /// ```
+    pub fn strip_private(&mut self) -> usize {
+        self.xmp = None;
+        self.strip_private = true;
+        let len = self.atoms.len();
+        self.atoms.retain(|a| match &a.ident {
+            DataIdent::Key(k) => !k.starts_with(LOCATION_KEY_PREFIX),
+            i => !PRIVATE_ATOMS.iter().any(|f| f == i),
+        });
+        len - self.atoms.len()
+    }
+
+    /// Removes all metadata, except the atoms corresponding to the identifiers of the whitelist.
+    /// The XMP metadata packet is also removed. Returns the number of removed atoms, not counting
+    /// the XMP packet. Unless the whitelist contains the geographic location (`©xyz`), a location
+    /// outside the item list is removed when writing, like with [`Self::strip_private`].
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{ident, Tag};
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_title("title");
+    /// tag.set_artist("artist");
+    /// tag.set_comment("comment");
+    /// tag.set_isrc("USRC17607839");
+    ///
+    /// assert_eq!(tag.strip_all_but(&[ident::TITLE, ident::ARTIST]), 2);
+    /// assert_eq!(tag.title(), Some("title"));
+    /// assert_eq!(tag.artist(), Some("artist"));
+    /// assert_eq!(tag.comment(), None);
+    /// assert_eq!(tag.isrc(), None);
+    /// ```
+    pub fn strip_all_but(&mut self, whitelist: &[impl Ident]) -> usize {
+        self.xmp = None;
+        self.strip_private |= !whitelist.iter().any(|i| i.fourcc() == Some(ident::LOCATION));
+        let len = self.atoms.len();
+        self.atoms.retain(|a| whitelist.iter().any(|i| *i == a.ident));
+        len - self.atoms.len()
+    }
+}
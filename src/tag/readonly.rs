@@ -1,7 +1,7 @@
 use std::fmt;
 use std::time::Duration;
 
-use crate::{AudioInfo, ChannelConfig, SampleRate, Tag};
+use crate::{AudioInfo, ChannelConfig, MovieHeader, SampleRate, Tag};
 
 /// ### Audio information
 impl Tag {
@@ -39,6 +39,38 @@ impl Tag {
         }
     }
 
+    /// Returns the fields of the movie header (`mvhd`), which is left unchanged when writing.
+    pub fn movie_header(&self) -> Option<&MovieHeader> {
+        self.info.movie_header.as_ref()
+    }
+
+    /// Returns the number of samples of the audio track (`stsz`). Together with the encoder delay
+    /// and padding, which some encoders store in an `iTunSMPB` freeform atom, this can be used to
+    /// compute the exact number of playable samples.
+    pub fn audio_sample_count(&self) -> Option<u64> {
+        self.info.sample_count
+    }
+
+    /// Returns the time to sample table of the audio track (`stts`). Each entry consists of a
+    /// number of consecutive samples and their duration in units of the media timescale, so the
+    /// exact timestamp of a sample is the sum of the durations of all previous samples. This
+    /// allows seeking to precise offsets without a full demuxer.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Tag;
+    ///
+    /// let tag = Tag::read_from_path("files/sample.m4a").unwrap();
+    /// let durations = tag.sample_durations().unwrap();
+    /// assert_eq!(durations, [(20, 1024), (1, 919)]);
+    ///
+    /// let total: u64 = durations.iter().map(|(c, d)| *c as u64 * *d as u64).sum();
+    /// assert_eq!(total, 21_399);
+    /// ```
+    pub fn sample_durations(&self) -> Option<&[(u32, u32)]> {
+        self.info.sample_durations.as_deref()
+    }
+
     /// Returns the channel configuration.
     pub fn channel_config(&self) -> Option<ChannelConfig> {
         self.info.channel_config
@@ -86,6 +118,37 @@ impl Tag {
             None => Ok(()),
         }
     }
+
+    /// Returns the bit depth of lossless audio, which is read from the magic cookie of an Apple
+    /// Lossless Audio sample entry (`alac`), like 16 or 24.
+    pub fn bit_depth(&self) -> Option<u8> {
+        self.info.bit_depth
+    }
+
+    pub(crate) fn format_bit_depth(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.bit_depth() {
+            Some(b) => writeln!(f, "bit depth: {}bit", b),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns true if a track is protected by a DRM scheme, such as FairPlay (`drms`), false
+    /// otherwise. Metadata of protected files can still be edited, since the protection scheme
+    /// info (`sinf`) inside the sample description is never modified.
+    pub fn is_drm_protected(&self) -> bool {
+        self.info.drm_protected
+    }
+
+    /// Returns the ISO 639-2/T language code of the audio track (`mdhd`), like `eng`.
+    pub fn language(&self) -> Option<&str> {
+        self.info.language.as_deref()
+    }
+
+    /// Returns the BCP 47 language tag of the audio track (`elng`), like `en-US`. If the track
+    /// has none, the ISO 639-2/T language code (`mdhd`) is returned instead.
+    pub fn extended_language(&self) -> Option<&str> {
+        self.info.extended_language.as_deref().or_else(|| self.language())
+    }
 }
 
 /// ### Filetype
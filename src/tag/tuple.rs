@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::{atom, Data, Tag};
+use crate::{atom, Data, ErrorKind, Tag};
 
 /// ### Track
 ///
@@ -87,6 +87,55 @@ impl Tag {
         }
     }
 
+    /// Returns the track number and total number of tracks (`trkn`) formatted like `3/12`, or
+    /// only the track number if the total is missing. A missing track number is formatted as 0.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut tag = Tag::default();
+    /// assert_eq!(tag.track_string(), None);
+    ///
+    /// tag.set_track(3, 12);
+    /// assert_eq!(tag.track_string(), Some("3/12".to_owned()));
+    ///
+    /// tag.remove_total_tracks();
+    /// assert_eq!(tag.track_string(), Some("3".to_owned()));
+    /// ```
+    pub fn track_string(&self) -> Option<String> {
+        tuple_string(self.track())
+    }
+
+    /// Sets the track number and total number of tracks (`trkn`) parsed from a string like `3/12`.
+    /// The total is optional, and a total of 0 is treated as nonexistent.
+    ///
+    /// Returns an error of kind [`ErrorKind::Parsing`](crate::ErrorKind::Parsing) if the string
+    /// isn't of that form, in which case the track is left unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Tag;
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_track_string("3/12").unwrap();
+    /// assert_eq!(tag.track(), (Some(3), Some(12)));
+    ///
+    /// tag.set_track_string("4").unwrap();
+    /// assert_eq!(tag.track(), (Some(4), None));
+    ///
+    /// tag.set_track_string("5/0").unwrap();
+    /// assert_eq!(tag.track(), (Some(5), None));
+    ///
+    /// assert!(tag.set_track_string("5 of 12").is_err());
+    /// assert_eq!(tag.track(), (Some(5), None));
+    /// ```
+    pub fn set_track_string(&mut self, track: &str) -> crate::Result<()> {
+        let (number, total) = parse_tuple(track, "track")?;
+        self.set_track(number, total);
+        Ok(())
+    }
+
     /// Returns the track numer and total number of tracks formatted in an easily readable way.
     pub(crate) fn format_track(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.track() {
@@ -183,6 +232,23 @@ impl Tag {
         }
     }
 
+    /// Returns the disc number and total number of discs (`disk`) formatted like `1/2`, or only
+    /// the disc number if the total is missing. A missing disc number is formatted as 0.
+    pub fn disc_string(&self) -> Option<String> {
+        tuple_string(self.disc())
+    }
+
+    /// Sets the disc number and total number of discs (`disk`) parsed from a string like `1/2`.
+    /// The total is optional, and a total of 0 is treated as nonexistent.
+    ///
+    /// Returns an error of kind [`ErrorKind::Parsing`](crate::ErrorKind::Parsing) if the string
+    /// isn't of that form, in which case the disc is left unchanged.
+    pub fn set_disc_string(&mut self, disc: &str) -> crate::Result<()> {
+        let (number, total) = parse_tuple(disc, "disc")?;
+        self.set_disc(number, total);
+        Ok(())
+    }
+
     /// Returns the disc numer and total number of discs formatted in an easily readable way.
     pub(crate) fn format_disc(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.disc() {
@@ -194,6 +260,35 @@ impl Tag {
     }
 }
 
+fn tuple_string(tuple: (Option<u16>, Option<u16>)) -> Option<String> {
+    match tuple {
+        (Some(n), Some(t)) => Some(format!("{}/{}", n, t)),
+        (Some(n), None) => Some(n.to_string()),
+        (None, Some(t)) => Some(format!("0/{}", t)),
+        (None, None) => None,
+    }
+}
+
+fn parse_tuple(s: &str, name: &str) -> crate::Result<(u16, u16)> {
+    let mut parts = s.trim().splitn(2, '/');
+    let number = parts.next().and_then(|n| n.trim().parse().ok());
+    let total = match parts.next() {
+        Some(t) => t.trim().parse().ok(),
+        None => Some(0),
+    };
+
+    match (number, total) {
+        (Some(n), Some(t)) => Ok((n, t)),
+        _ => Err(crate::Error::new(
+            ErrorKind::Parsing,
+            format!(
+                "Invalid {} '{}', expected a number and an optional total like '3/12'",
+                name, s,
+            ),
+        )),
+    }
+}
+
 fn number(vec: &[u8]) -> Option<u16> {
     be_int!(vec, 2, u16).and_then(|n| if n == 0 { None } else { Some(n) })
 }
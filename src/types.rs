@@ -1,8 +1,9 @@
 use std::convert::TryFrom;
 use std::fmt;
+use std::str::FromStr;
 use std::time::Duration;
 
-use crate::ErrorKind;
+use crate::{ErrorKind, Fourcc};
 
 // iTunes media type indices
 /// A media type code stored in the `stik` atom.
@@ -190,6 +191,50 @@ impl fmt::Display for AdvisoryRating {
     }
 }
 
+/// An enum describing the application that likely encoded a file, detected from the encoder
+/// string stored in the `©too` atom.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EncoderSource {
+    /// FFmpeg or its libavformat (`Lavf`) and libavcodec (`Lavc`) libraries.
+    Ffmpeg,
+    /// The Fraunhofer FDK AAC encoder (`fdkaac`).
+    Fdkaac,
+    /// Apple iTunes.
+    ITunes,
+    /// Nero AAC.
+    Nero,
+}
+
+impl EncoderSource {
+    /// Attempts to detect the encoding application from an encoder string.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::EncoderSource;
+    ///
+    /// assert_eq!(EncoderSource::detect("Lavf58.29.100"), Some(EncoderSource::Ffmpeg));
+    /// assert_eq!(EncoderSource::detect("iTunes 12.9.5.5"), Some(EncoderSource::ITunes));
+    /// assert_eq!(EncoderSource::detect("fdkaac 1.0.0"), Some(EncoderSource::Fdkaac));
+    /// assert_eq!(EncoderSource::detect("Nero AAC codec / 1.5.4.0"), Some(EncoderSource::Nero));
+    /// assert_eq!(EncoderSource::detect("unknown"), None);
+    /// ```
+    pub fn detect(encoder: &str) -> Option<Self> {
+        let encoder = encoder.to_lowercase();
+        if encoder.starts_with("lavf") || encoder.starts_with("lavc") || encoder.contains("ffmpeg")
+        {
+            Some(Self::Ffmpeg)
+        } else if encoder.contains("fdkaac") || encoder.contains("libfdk") {
+            Some(Self::Fdkaac)
+        } else if encoder.contains("itunes") {
+            Some(Self::ITunes)
+        } else if encoder.contains("nero") {
+            Some(Self::Nero)
+        } else {
+            None
+        }
+    }
+}
+
 /// An enum representing the channel configuration of an MPEG-4 audio track.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ChannelConfig {
@@ -230,6 +275,20 @@ impl TryFrom<u8> for ChannelConfig {
 }
 
 impl ChannelConfig {
+    /// Returns the channel configuration of the number of channels, if there is one.
+    pub(crate) const fn from_channel_count(count: u8) -> Option<Self> {
+        match count {
+            1 => Some(Self::Mono),
+            2 => Some(Self::Stereo),
+            3 => Some(Self::Three),
+            4 => Some(Self::Four),
+            5 => Some(Self::Five),
+            6 => Some(Self::FiveOne),
+            8 => Some(Self::SevenOne),
+            _ => None,
+        }
+    }
+
     /// Returns the number of channels.
     pub const fn channel_count(&self) -> u8 {
         match self {
@@ -261,6 +320,10 @@ impl fmt::Display for ChannelConfig {
 /// An enum representing the sample rate of an MPEG-4 audio track.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum SampleRate {
+    /// A Sample rate of 192000Hz
+    Hz192000,
+    /// A Sample rate of 176400Hz
+    Hz176400,
     /// A Sample rate of 96000Hz
     Hz96000,
     /// A Sample rate of 88200Hz
@@ -322,9 +385,33 @@ impl fmt::Display for SampleRate {
 }
 
 impl SampleRate {
+    /// Returns the sample rate of the frequency in Hz, if there is one.
+    pub(crate) const fn from_hz(hz: u32) -> Option<Self> {
+        match hz {
+            192000 => Some(Self::Hz192000),
+            176400 => Some(Self::Hz176400),
+            96000 => Some(Self::Hz96000),
+            88200 => Some(Self::Hz88200),
+            64000 => Some(Self::Hz64000),
+            48000 => Some(Self::Hz48000),
+            44100 => Some(Self::Hz44100),
+            32000 => Some(Self::Hz32000),
+            24000 => Some(Self::Hz24000),
+            22050 => Some(Self::Hz22050),
+            16000 => Some(Self::Hz16000),
+            12000 => Some(Self::Hz12000),
+            11025 => Some(Self::Hz11025),
+            8000 => Some(Self::Hz8000),
+            7350 => Some(Self::Hz7350),
+            _ => None,
+        }
+    }
+
     /// Returns the sample rate in Hz.
     pub const fn hz(&self) -> u32 {
         match self {
+            Self::Hz192000 => 192000,
+            Self::Hz176400 => 176400,
             Self::Hz96000 => 96000,
             Self::Hz88200 => 88200,
             Self::Hz64000 => 64000,
@@ -355,6 +442,72 @@ pub struct AudioInfo {
     pub max_bitrate: Option<u32>,
     /// The average bitrate of the track.
     pub avg_bitrate: Option<u32>,
+    /// The bit depth of the track, which is only stored for Apple Lossless Audio (`alac`).
+    pub bit_depth: Option<u8>,
+    /// Whether a track is protected by a DRM scheme (`drms` or `enca` sample entry).
+    pub drm_protected: bool,
+    /// The ISO 639-2/T language code of the track (`mdhd`).
+    pub language: Option<String>,
+    /// The BCP 47 language tag of the track (`elng`).
+    pub extended_language: Option<String>,
+    /// The fields of the movie header (`mvhd`).
+    pub movie_header: Option<MovieHeader>,
+    /// The number of samples of the track (`stsz`).
+    pub sample_count: Option<u64>,
+    /// The time to sample table of the track (`stts`), consisting of the number of consecutive
+    /// samples and their duration in units of the media timescale.
+    pub sample_durations: Option<Vec<(u32, u32)>>,
+}
+
+/// A struct containing the fields of the movie header atom (`mvhd`). Times are stored as seconds
+/// since midnight, January 1, 1904 UTC, and durations in units of the timescale.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MovieHeader {
+    /// The version of the movie header, 0 for 32 bit and 1 for 64 bit times and durations.
+    pub version: u8,
+    /// The creation time.
+    pub creation_time: u64,
+    /// The modification time.
+    pub modification_time: u64,
+    /// The number of time units per second.
+    pub timescale: u32,
+    /// The duration of the longest track.
+    pub duration: u64,
+    /// The preferred playback rate as a 16.16 fixed point number, `0x0001_0000` is normal speed.
+    pub rate: u32,
+    /// The preferred volume as a 8.8 fixed point number, `0x0100` is full volume.
+    pub volume: u16,
+    /// The identifier of the next track that is added to the file.
+    pub next_track_id: u32,
+}
+
+/// A struct containing statistics about reading a tag, see
+/// [`Tag::read_from_with_stats`](crate::Tag::read_from_with_stats).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReadStats {
+    /// The number of bytes read from the reader.
+    pub bytes_read: u64,
+    /// The number of seeks performed on the reader, including queries of the stream position.
+    pub seeks: u64,
+    /// The number of atoms that were visited, including skipped ones.
+    pub atoms_visited: u64,
+    /// The identifiers of atoms that were skipped without being interpreted, in the order they
+    /// were encountered. This includes atoms like the media data (`mdat`), as well as atoms
+    /// unknown to this library.
+    pub skipped_atoms: Vec<Fourcc>,
+    /// Descriptions of malformed data that was recovered from, when reading with
+    /// [`ReadConfig::lenient`](crate::ReadConfig::lenient) set, and of unsupported data that was
+    /// skipped.
+    pub warnings: Vec<String>,
+}
+
+/// An enum representing the encodings strings can be stored with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StringEncoding {
+    /// Utf-8, the encoding iTunes uses.
+    Utf8,
+    /// Big endian utf-16.
+    Utf16,
 }
 
 /// An alias for an image reference.
@@ -364,7 +517,19 @@ pub type ImgMut<'a> = Img<&'a mut Vec<u8>>;
 /// An alias for an owned image buffer.
 pub type ImgBuf = Img<Vec<u8>>;
 
-/// A struct representing an image.
+/// A struct representing an image. The named constructors [`Img::bmp`], [`Img::jpeg`] and
+/// [`Img::png`] set the format implicitly.
+///
+/// # Example
+/// ```
+/// use mp4ameta::{Img, ImgFmt, Tag};
+///
+/// let image = Img::jpeg(b"\xff\xd8\xffimage".to_vec());
+/// assert_eq!(image, Img::new(ImgFmt::Jpeg, b"\xff\xd8\xffimage".to_vec()));
+///
+/// let mut tag = Tag::default();
+/// tag.set_artwork(image).unwrap();
+/// ```
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Img<T> {
     /// The image format.
@@ -395,6 +560,71 @@ impl<T> Img<T> {
     }
 }
 
+#[cfg(feature = "image")]
+impl<T: AsRef<[u8]>> Img<T> {
+    /// Attempts to decode the image data, to check that it is neither truncated nor otherwise
+    /// corrupt.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Img;
+    ///
+    /// let png = std::fs::read("files/artwork.png").unwrap();
+    /// assert!(Img::png(&png).validate().is_ok());
+    /// assert!(Img::png(&png[..png.len() / 2]).validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), image::ImageError> {
+        self.decode()?;
+        Ok(())
+    }
+
+    /// Decodes the image and re-encodes it as a jpeg with the quality, ranging from 1 to 100. The
+    /// alpha channel of transparent images is dropped.
+    ///
+    /// Returns an error of kind [`ErrorKind::InvalidImageData`] if the image data can't be
+    /// decoded as its format.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::{Img, ImgFmt, Tag};
+    ///
+    /// let png = std::fs::read("files/artwork.png").unwrap();
+    /// let jpeg = Img::png(&png).to_jpeg(90).unwrap();
+    /// assert_eq!(jpeg.fmt, ImgFmt::Jpeg);
+    ///
+    /// let mut tag = Tag::default();
+    /// tag.set_artwork(jpeg).unwrap();
+    /// ```
+    pub fn to_jpeg(&self, quality: u8) -> crate::Result<ImgBuf> {
+        let decoded = self.decode().map_err(|e| {
+            crate::Error::new(
+                ErrorKind::InvalidImageData,
+                format!("Error decoding {:?} image: {}", self.fmt, e),
+            )
+        })?;
+
+        let mut data = Vec::new();
+        let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut data, quality);
+        encoder.encode_image(&decoded.into_rgb8()).map_err(|e| {
+            crate::Error::new(
+                ErrorKind::InvalidImageData,
+                format!("Error encoding jpeg image: {}", e),
+            )
+        })?;
+
+        Ok(Img::jpeg(data))
+    }
+
+    fn decode(&self) -> Result<image::DynamicImage, image::ImageError> {
+        let fmt = match self.fmt {
+            ImgFmt::Bmp => image::ImageFormat::Bmp,
+            ImgFmt::Jpeg => image::ImageFormat::Jpeg,
+            ImgFmt::Png => image::ImageFormat::Png,
+        };
+        image::load_from_memory_with_format(self.data.as_ref(), fmt)
+    }
+}
+
 /// An enum representing image formats.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ImgFmt {
@@ -407,6 +637,29 @@ pub enum ImgFmt {
 }
 
 impl ImgFmt {
+    /// Attempts to detect the image format using the signature at the start of the data.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::ImgFmt;
+    ///
+    /// assert_eq!(ImgFmt::sniff(b"\x89PNG\r\n\x1a\n"), Some(ImgFmt::Png));
+    /// assert_eq!(ImgFmt::sniff(b"\xff\xd8\xff\xe0"), Some(ImgFmt::Jpeg));
+    /// assert_eq!(ImgFmt::sniff(b"BM"), Some(ImgFmt::Bmp));
+    /// assert_eq!(ImgFmt::sniff(b"text"), None);
+    /// ```
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\xff\xd8\xff") {
+            Some(Self::Jpeg)
+        } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if data.starts_with(b"BM") {
+            Some(Self::Bmp)
+        } else {
+            None
+        }
+    }
+
     /// Returns true if `self` is of type [`Self::Bmp`] false otherwise.
     pub fn is_bmp(&self) -> bool {
         matches!(self, Self::Bmp)
@@ -422,3 +675,112 @@ impl ImgFmt {
         matches!(self, Self::Png)
     }
 }
+
+/// A locale indicator of a data atom (`data`), consisting of a country and a language indicator,
+/// which allows multiple localized values of a metadata atom to coexist.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Locale {
+    /// The country indicator, either an ISO 3166 code or a Macintosh country code, or 0 if the
+    /// value isn't specific to a country.
+    pub country: u16,
+    /// The language indicator, either a packed ISO 639-2/T code or a Macintosh language code, or
+    /// 0 if the value isn't specific to a language.
+    pub language: u16,
+}
+
+impl Locale {
+    /// Returns the 3 letter ISO 639-2/T language code, like `jpn`, if the language indicator is a
+    /// packed one.
+    pub fn language_code(&self) -> Option<String> {
+        crate::atom::unpack_language(self.language)
+    }
+}
+
+impl From<[u8; 4]> for Locale {
+    fn from(locale: [u8; 4]) -> Self {
+        let [c1, c0, l1, l0] = locale;
+        Self { country: u16::from_be_bytes([c1, c0]), language: u16::from_be_bytes([l1, l0]) }
+    }
+}
+
+/// A geographic location stored in the `©xyz` atom as an ISO 6709 string, for example
+/// `+12.3456-098.7654+150.5/`. Coordinates are in decimal degrees and the altitude in meters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Location {
+    /// The latitude in degrees, positive values are north of the equator.
+    pub lat: f64,
+    /// The longitude in degrees, positive values are east of the prime meridian.
+    pub lon: f64,
+    /// The altitude in meters.
+    pub alt: Option<f64>,
+}
+
+impl Location {
+    /// Creates a new location without an altitude.
+    pub const fn new(lat: f64, lon: f64) -> Self {
+        Self { lat, lon, alt: None }
+    }
+}
+
+impl FromStr for Location {
+    type Err = crate::Error;
+
+    /// Parses an ISO 6709 string using decimal degrees. A trailing coordinate reference system
+    /// identifier such as `CRSWGS_84` is ignored.
+    ///
+    /// # Example
+    /// ```
+    /// use mp4ameta::Location;
+    ///
+    /// let location: Location = "+27.9881+086.9250+8848CRSWGS_84/".parse().unwrap();
+    /// assert_eq!(location.lat, 27.9881);
+    /// assert_eq!(location.lon, 86.925);
+    /// assert_eq!(location.alt, Some(8848.0));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || crate::Error::new(ErrorKind::Parsing, format!("Invalid ISO 6709 location '{}'", s));
+
+        let end = s.find(|c: char| c.is_ascii_alphabetic() || c == '/').unwrap_or(s.len());
+        let s_coords = &s[..end];
+
+        let mut values = Vec::new();
+        let mut start = 0;
+        for (i, c) in s_coords.char_indices().skip(1) {
+            if c == '+' || c == '-' {
+                values.push(&s_coords[start..i]);
+                start = i;
+            }
+        }
+        values.push(&s_coords[start..]);
+
+        let mut values = values.into_iter().map(|v| match v.as_bytes().first() {
+            Some(b'+') | Some(b'-') => v.parse::<f64>().map_err(|_| invalid()),
+            _ => Err(invalid()),
+        });
+
+        let lat = values.next().ok_or_else(invalid)??;
+        let lon = values.next().ok_or_else(invalid)??;
+        let alt = values.next().transpose()?;
+        if values.next().is_some()
+            || !(-90.0..=90.0).contains(&lat)
+            || !(-180.0..=180.0).contains(&lon)
+        {
+            return Err(invalid());
+        }
+
+        Ok(Self { lat, lon, alt })
+    }
+}
+
+impl fmt::Display for Location {
+    /// Formats the location as an ISO 6709 string, with the coordinates rounded to 4 decimal
+    /// places.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:+08.4}{:+09.4}", self.lat, self.lon)?;
+        if let Some(alt) = self.alt {
+            write!(f, "{:+}", alt)?;
+        }
+        write!(f, "/")
+    }
+}
@@ -1,9 +1,14 @@
 use std::fs;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use mp4ameta::{
-    AdvisoryRating, ChannelConfig, Data, Fourcc, Img, MediaType, SampleRate, Tag, STANDARD_GENRES,
+    ident, AdvisoryRating, ChannelConfig, Data, DataCodec, DataIdent, DataSummary, ErrorKind,
+    FieldChange, Fourcc, FreeformIdent, GenrePolicy, Id3v2Style, Img, ImgFmt, Locale, MediaType,
+    MetadataStyle, ReadConfig, SampleRate, StringEncoding, Tag, TypeRegistry, WriteConfig,
+    STANDARD_GENRES,
 };
 use walkdir::WalkDir;
 
@@ -61,7 +66,7 @@ fn get_tag_1() -> Tag {
     tag.set_title("TEST TITLE");
     tag.set_track(7, 13);
     tag.set_year("2013");
-    tag.set_artwork(Img::png(fs::read("files/artwork.png").unwrap()));
+    tag.set_artwork(Img::png(fs::read("files/artwork.png").unwrap())).unwrap();
     tag.set_isrc("TEST ISRC");
     tag.set_lyricist("TEST LYRICIST");
     tag
@@ -80,6 +85,7 @@ fn get_tag_2() -> Tag {
     tag.set_composer("NEW COMPOSER");
     tag.set_copyright("NEW COPYRIGHT");
     tag.set_description("NEW DESCRIPTION");
+    tag.set_long_description("NEW LONG DESCRIPTION");
     tag.set_disc(2, 0);
     tag.set_encoder("Lavf58.12.100");
     tag.set_gapless_playback();
@@ -89,9 +95,13 @@ fn get_tag_2() -> Tag {
     tag.set_lyrics("NEW LYRICS");
     tag.set_media_type(MediaType::AudioBook);
     tag.set_title("NEW TITLE");
+    tag.set_track_subtitle("NEW TRACK SUBTITLE");
+    tag.set_producer("NEW PRODUCER");
+    tag.set_director("NEW DIRECTOR");
+    tag.set_performers(vec!["NEW PERFORMER 1".to_owned(), "NEW PERFORMER 2".to_owned()]);
     tag.set_track(3, 7);
     tag.set_year("1998");
-    tag.set_artwork(Img::jpeg(b"NEW ARTWORK".to_vec()));
+    tag.set_artwork_unchecked(Img::jpeg(b"NEW ARTWORK".to_vec()));
     tag.set_isrc("NEW ISRC");
     tag.set_lyricist("NEW LYRICIST");
     tag
@@ -141,6 +151,7 @@ fn assert_tag_2(tag: &Tag) {
     assert_eq!(tag.composer(), Some("NEW COMPOSER"));
     assert_eq!(tag.copyright(), Some("NEW COPYRIGHT"));
     assert_eq!(tag.description(), Some("NEW DESCRIPTION"));
+    assert_eq!(tag.long_description(), Some("NEW LONG DESCRIPTION"));
     assert_eq!(tag.disc(), (Some(2), None));
     assert_eq!(tag.disc_number(), Some(2));
     assert_eq!(tag.total_discs(), None);
@@ -152,6 +163,10 @@ fn assert_tag_2(tag: &Tag) {
     assert_eq!(tag.lyrics(), Some("NEW LYRICS"));
     assert_eq!(tag.media_type(), Some(MediaType::AudioBook));
     assert_eq!(tag.title(), Some("NEW TITLE"));
+    assert_eq!(tag.track_subtitle(), Some("NEW TRACK SUBTITLE"));
+    assert_eq!(tag.producer(), Some("NEW PRODUCER"));
+    assert_eq!(tag.director(), Some("NEW DIRECTOR"));
+    assert_eq!(tag.performers().collect::<Vec<_>>(), ["NEW PERFORMER 1", "NEW PERFORMER 2"]);
     assert_eq!(tag.track(), (Some(3), Some(7)));
     assert_eq!(tag.track_number(), Some(3));
     assert_eq!(tag.total_tracks(), Some(7));
@@ -220,6 +235,32 @@ fn assert_readonly(tag: &Tag) {
     assert_eq!(tag.sample_rate(), Some(SampleRate::Hz44100));
     assert_eq!(tag.avg_bitrate(), Some(64776));
     assert_eq!(tag.max_bitrate(), Some(69000));
+    assert_eq!(tag.audio_sample_count(), Some(21));
+    assert_eq!(tag.sample_durations(), Some(&[(20, 1024), (1, 919)][..]));
+    assert_eq!(tag.bit_depth(), None);
+
+    let mvhd = tag.movie_header().unwrap();
+    assert_eq!((mvhd.version, mvhd.creation_time, mvhd.modification_time), (0, 0, 0));
+    assert_eq!((mvhd.timescale, mvhd.duration), (1000, 486));
+    assert_eq!((mvhd.rate, mvhd.volume, mvhd.next_track_id), (0x0001_0000, 0x0100, 2));
+}
+
+/// Returns the position and length of the first top-level atom with the identifier.
+fn top_level_bounds(data: &[u8], fourcc: &[u8; 4]) -> (usize, usize) {
+    let mut pos = 0;
+    loop {
+        let mut len = [0u8; 8];
+        len[4..].copy_from_slice(&data[pos..pos + 4]);
+        if len == [0, 0, 0, 0, 0, 0, 0, 1] {
+            len.copy_from_slice(&data[pos + 8..pos + 16]);
+        }
+        let len = u64::from_be_bytes(len) as usize;
+
+        if &data[pos + 4..pos + 8] == fourcc {
+            return (pos, len);
+        }
+        pos += len;
+    }
 }
 
 #[test]
@@ -233,126 +274,2256 @@ fn collection() {
 }
 
 #[test]
-fn sample_files() {
-    let _ = fs::remove_dir_all("target/files");
-    let _ = fs::create_dir("target/files");
+fn sample_files() {
+    let _ = fs::remove_dir_all("target/files");
+    let _ = fs::create_dir("target/files");
+
+    read_dir("files", |p, t| {
+        let file_name = p.file_name().unwrap().to_owned();
+        let mut path = PathBuf::from("target/files");
+        path.push(file_name);
+
+        println!("copying {} to {}...", p.display(), path.display());
+        fs::copy(p, &path).unwrap();
+
+        println!("writing empty tag");
+        Tag::default().write_to_path(&path).unwrap();
+        println!("reading empty tag");
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert!(tag.is_empty());
+        assert_eq!(tag.audio_info(), t.audio_info());
+
+        println!("writing sample tag 1");
+        get_tag_1().write_to_path(&path).unwrap();
+        println!("reading sample tag 1");
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_tag_1(&tag);
+        assert_eq!(tag.audio_info(), t.audio_info());
+
+        println!("writing sample tag 2");
+        get_tag_2().write_to_path(&path).unwrap();
+        println!("reading sample tag 2");
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_tag_2(&tag);
+        assert_eq!(tag.audio_info(), t.audio_info());
+        println!();
+    });
+}
+
+#[test]
+fn read_sample() {
+    let tag = Tag::read_from_path("files/sample.m4a").unwrap();
+
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+}
+
+#[test]
+fn read_with_stats() {
+    let data = fs::read("files/sample.m4a").unwrap();
+    let (tag, stats) = Tag::read_from_with_stats(&mut Cursor::new(&data)).unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+
+    assert!(stats.bytes_read > 0);
+    assert!(stats.bytes_read < data.len() as u64);
+    assert!(stats.seeks > 0);
+    assert!(stats.skipped_atoms.contains(&Fourcc(*b"mdat")));
+    assert!(stats.skipped_atoms.contains(&Fourcc(*b"tkhd")));
+    assert!(!stats.skipped_atoms.contains(&Fourcc(*b"ilst")));
+    assert!(stats.atoms_visited > stats.skipped_atoms.len() as u64 + tag.data().count() as u64);
+}
+
+#[test]
+fn read_with_progress() {
+    let data = fs::read("files/sample.m4a").unwrap();
+    let mut calls = Vec::new();
+    let tag = Tag::read_from_with_progress(&mut Cursor::new(&data), |done, total| {
+        calls.push((done, total));
+    })
+    .unwrap();
+    assert_tag_1(&tag);
+
+    let len = data.len() as u64;
+    // after ftyp, free, moov, free and mdat
+    assert_eq!(calls.len(), 5);
+    assert_eq!(calls[0], (24, len));
+    assert_eq!(calls[2], (3122, len));
+    assert_eq!(calls.last(), Some(&(len, len)));
+}
+
+#[test]
+fn write_with_progress() {
+    let path = "target/write_with_progress.m4a";
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    data.extend(atom(b"free", &[0; 200 * 1024]));
+    fs::write(path, &data).unwrap();
+    let (moov_pos, _) = top_level_bounds(&data, b"moov");
+
+    // exceed the padding following the moov atom, so the following data is moved
+    let mut tag = get_tag_1();
+    tag.set_comment("a".repeat(4096));
+    let file = fs::OpenOptions::new().read(true).write(true).open(path).unwrap();
+    let mut calls = Vec::new();
+    tag.write_to_with_progress(&file, |done, total| calls.push((done, total))).unwrap();
+
+    let new_len = fs::metadata(path).unwrap().len();
+    let total = new_len - moov_pos as u64;
+    assert!(calls.len() > 4);
+    assert!(calls.iter().all(|(_, t)| *t == total));
+    assert!(calls.windows(2).all(|w| w[0].0 < w[1].0));
+    assert_eq!(calls.last(), Some(&(total, total)));
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.comment(), Some("a".repeat(4096).as_str()));
+}
+
+#[test]
+fn write_streamed_copy() {
+    let path = "target/write_streamed_copy.m4a";
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    data.extend(atom(b"free", &[7; 10_000]));
+    fs::write(path, &data).unwrap();
+    let (moov_pos, moov_len) = top_level_bounds(&data, b"moov");
+    let moved_len = (data.len() - moov_pos - moov_len) as u64;
+
+    let mut tag = get_tag_1();
+    tag.set_comment("a".repeat(4096));
+    let cfg = WriteConfig { copy_buf_size: 1024, ..Default::default() };
+    let file = fs::OpenOptions::new().read(true).write(true).open(path).unwrap();
+    let mut calls = Vec::new();
+    tag.write_with_config_and_progress(&file, &cfg, |done, _| calls.push(done)).unwrap();
+
+    // the moved data is copied in chunks of the buffer size, followed by the new moov atom
+    let copied = &calls[..calls.len() - 1];
+    let previous = std::iter::once(&0).chain(copied);
+    assert!(copied.iter().zip(previous).all(|(c, p)| c - p == (moved_len - p).min(1024)));
+    assert_eq!(copied.last(), Some(&moved_len));
+
+    let written = fs::read(path).unwrap();
+    assert!(written.ends_with(&atom(b"free", &[7; 10_000])));
+    let (new_moov_pos, new_moov_len) = top_level_bounds(&written, b"moov");
+    assert_eq!(&written[new_moov_pos + new_moov_len..], &data[moov_pos + moov_len..]);
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.comment(), Some("a".repeat(4096).as_str()));
+    assert_readonly(&tag);
+
+    // without padding following the moov atom, shrinking the metadata moves the data forward
+    let mut data = written;
+    let free_pos = new_moov_pos + new_moov_len;
+    data[free_pos + 4..free_pos + 8].copy_from_slice(b"abcd");
+    fs::write(path, &data).unwrap();
+    let file = fs::OpenOptions::new().read(true).write(true).open(path).unwrap();
+    get_tag_1().write_with_config(&file, &cfg).unwrap();
+
+    let written = fs::read(path).unwrap();
+    let (moov_pos, moov_len) = top_level_bounds(&written, b"moov");
+    assert!(moov_len < new_moov_len);
+    assert_eq!(&written[moov_pos + moov_len..], &data[free_pos..]);
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+}
+
+#[test]
+fn read_mdat_to_eof() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let (mdat_pos, mdat_len) = top_level_bounds(&data, b"mdat");
+    assert_eq!(mdat_pos + mdat_len, data.len());
+    data[mdat_pos..mdat_pos + 4].copy_from_slice(&u32::to_be_bytes(0));
+
+    let tag = Tag::read_from(&mut Cursor::new(data)).unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+}
+
+/// Returns the big endian 32 bit integer at the position.
+fn be_u32_at(data: &[u8], pos: usize) -> u32 {
+    u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+}
+
+fn atom(fourcc: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut atom = u32::to_be_bytes(8 + content.len() as u32).to_vec();
+    atom.extend_from_slice(fourcc);
+    atom.extend_from_slice(content);
+    atom
+}
+
+/// Returns a handler reference atom (`hdlr`) with the handler type and an empty name.
+fn hdlr(handler_type: &[u8; 4]) -> Vec<u8> {
+    let mut hdlr = vec![0; 8];
+    hdlr.extend_from_slice(handler_type);
+    hdlr.extend_from_slice(&[0; 13]);
+    atom(b"hdlr", &hdlr)
+}
+
+/// Replaces the range of the data with the bytes and updates the lengths of the first atoms with
+/// the container identifiers. If the free atom following the moov atom is large enough, it absorbs
+/// the length difference, so the media data offsets stay valid.
+fn splice_atoms(data: &mut Vec<u8>, range: Range<usize>, bytes: &[u8], containers: &[&[u8; 4]]) {
+    let diff = bytes.len() as i64 - range.len() as i64;
+
+    let (moov_pos, moov_len) = top_level_bounds(data, b"moov");
+    let free_pos = moov_pos + moov_len;
+    let is_free = data.get(free_pos + 4..free_pos + 8) == Some(&b"free"[..]);
+    if is_free && be_u32_at(data, free_pos) as i64 - 8 >= diff {
+        let free_len = be_u32_at(data, free_pos) as usize;
+        let new_free_len = (free_len as i64 - diff) as usize;
+        data[free_pos..free_pos + 4].copy_from_slice(&u32::to_be_bytes(new_free_len as u32));
+        if new_free_len < free_len {
+            data.drain(free_pos + new_free_len..free_pos + free_len);
+        } else {
+            let padding = vec![0; new_free_len - free_len];
+            data.splice(free_pos + free_len..free_pos + free_len, padding);
+        }
+    }
+
+    for fourcc in containers.iter() {
+        let pos = data.windows(4).position(|w| w == *fourcc).unwrap() - 4;
+        let len = be_u32_at(data, pos) as i64 + diff;
+        data[pos..pos + 4].copy_from_slice(&u32::to_be_bytes(len as u32));
+    }
+    data.splice(range, bytes.iter().copied());
+}
+
+/// Inserts a QuickTime metadata (`meta`) atom with the utf-8 values at the end of the moov atom of
+/// the sample file.
+fn sample_with_quicktime_metadata(items: &[(&str, &str)]) -> Vec<u8> {
+    let mut keys = vec![0; 4];
+    keys.extend_from_slice(&u32::to_be_bytes(items.len() as u32));
+    let mut ilst = Vec::new();
+    for (i, (key, value)) in items.iter().enumerate() {
+        keys.extend(atom(b"mdta", key.as_bytes()));
+
+        let mut data = vec![0, 0, 0, 1, 0, 0, 0, 0];
+        data.extend_from_slice(value.as_bytes());
+        ilst.extend(atom(&u32::to_be_bytes(i as u32 + 1), &atom(b"data", &data)));
+    }
+
+    // QuickTime metadata atoms have no version and flags
+    let mut meta = hdlr(b"mdta");
+    meta.extend(atom(b"keys", &keys));
+    meta.extend(atom(b"ilst", &ilst));
+    let meta = atom(b"meta", &meta);
+
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let (moov_pos, moov_len) = top_level_bounds(&data, b"moov");
+    let moov_end = moov_pos + moov_len;
+    splice_atoms(&mut data, moov_end..moov_end, &meta, &[b"moov"]);
+    data
+}
+
+#[test]
+fn read_quicktime_metadata() {
+    let data = sample_with_quicktime_metadata(&[
+        ("com.apple.quicktime.title", "QT TITLE"),
+        ("com.apple.quicktime.creationdate", "2021-01-01T00:00:00Z"),
+    ]);
+
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+
+    let title = DataIdent::key("com.apple.quicktime.title");
+    assert_eq!(tag.strings_of(&title).next(), Some("QT TITLE"));
+    let date = DataIdent::key("com.apple.quicktime.creationdate");
+    assert_eq!(tag.strings_of(&date).next(), Some("2021-01-01T00:00:00Z"));
+    assert_eq!(tag.title(), Some("TEST TITLE"));
+
+    // the QuickTime metadata is left untouched when writing
+    let path = "target/read_quicktime_metadata.m4a";
+    fs::write(path, &data).unwrap();
+    get_tag_2().write_to_path(path).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_tag_2(&tag);
+    assert_eq!(tag.strings_of(&title).next(), Some("QT TITLE"));
+}
+
+#[test]
+fn read_quicktime_metadata_unknown_namespace() {
+    let mut data = sample_with_quicktime_metadata(&[
+        ("com.apple.quicktime.title", "QT TITLE"),
+        ("com.apple.quicktime.artist", "QT ARTIST"),
+    ]);
+    let pos = data.windows(29).position(|w| w == b"mdtacom.apple.quicktime.title").unwrap();
+    data[pos..pos + 4].copy_from_slice(b"udta");
+
+    let (tag, stats) = Tag::read_from_with_stats(&mut Cursor::new(&data)).unwrap();
+    assert_tag_1(&tag);
+    assert_eq!(tag.data_of(&DataIdent::key("com.apple.quicktime.title")).next(), None);
+    let artist = DataIdent::key("com.apple.quicktime.artist");
+    assert_eq!(tag.strings_of(&artist).next(), Some("QT ARTIST"));
+    assert_eq!(stats.warnings, vec!["Unknown metadata item key namespace 'udta'".to_owned()]);
+}
+
+#[test]
+fn write_quicktime_metadata() {
+    let path = "target/write_quicktime_metadata.m4a";
+    let _ = std::fs::remove_file(path);
+    std::fs::copy("files/sample.m4a", path).unwrap();
+    let cfg = WriteConfig { metadata_style: MetadataStyle::QuickTimeKeys, ..Default::default() };
+
+    let title = DataIdent::key("com.apple.quicktime.title");
+    let artist = DataIdent::key("com.apple.quicktime.artist");
+    let mut tag = get_tag_1();
+    tag.set_data(title.clone(), Data::Utf8("QT TITLE".into()));
+    tag.set_data(artist.clone(), Data::Utf8("QT ARTIST".into()));
+    tag.add_data(title.clone(), Data::Utf8("QT TITLE 2".into()));
+    tag.write_to_path_with_config(path, &cfg).unwrap();
+
+    let read = Tag::read_from_path(path).unwrap();
+    assert_tag_1(&read);
+    assert_readonly(&read);
+    assert!(tag.entries().eq(read.entries()));
+
+    // QuickTime metadata atoms have no version and flags
+    let data = fs::read(path).unwrap();
+    let (_, meta_pos, _) = moov_children(&data).into_iter().find(|(f, ..)| f == b"meta").unwrap();
+    assert_eq!(&data[meta_pos + 12..meta_pos + 16], b"hdlr");
+
+    tag.remove_data_of(&title);
+    tag.write_to_path_with_config(path, &cfg).unwrap();
+    let read = Tag::read_from_path(path).unwrap();
+    assert_eq!(read.data_of(&title).next(), None);
+    assert_eq!(read.strings_of(&artist).next(), Some("QT ARTIST"));
+
+    // the default style leaves the QuickTime metadata as is
+    get_tag_2().write_to_path(path).unwrap();
+    let read = Tag::read_from_path(path).unwrap();
+    assert_tag_2(&read);
+    assert_eq!(read.strings_of(&artist).next(), Some("QT ARTIST"));
+
+    get_tag_2().write_to_path_with_config(path, &cfg).unwrap();
+    let read = Tag::read_from_path(path).unwrap();
+    assert_tag_2(&read);
+    assert_eq!(read.data_of(&artist).next(), None);
+    assert_eq!(fs::metadata(path).unwrap().len(), fs::metadata("files/sample.m4a").unwrap().len());
+}
+
+#[test]
+fn write_localized() {
+    let path = "target/write_localized.m4a";
+    let _ = std::fs::remove_file(path);
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    let mut tag = get_tag_1();
+    tag.set_localized(ident::TITLE, "eng", "ENGLISH TITLE").unwrap();
+    tag.set_localized(ident::TITLE, "jpn", "日本語のタイトル").unwrap();
+    tag.set_localized(ident::ALBUM, "jpn", "日本語のアルバム").unwrap();
+    assert!(tag.set_localized(ident::TITLE, "english", "").is_err());
+    tag.write_to_path(path).unwrap();
+
+    let mut tag = Tag::read_from_path(path).unwrap();
+    assert_tag_1(&tag);
+    assert_eq!(tag.localized(&ident::TITLE, "eng"), Some("ENGLISH TITLE"));
+    assert_eq!(tag.localized(&ident::TITLE, "jpn"), Some("日本語のタイトル"));
+    assert_eq!(tag.localized(&ident::ALBUM, "jpn"), Some("日本語のアルバム"));
+    assert_eq!(tag.languages_of(&ident::TITLE).collect::<Vec<_>>(), vec!["eng", "jpn"]);
+
+    tag.remove_localized(&ident::TITLE, "eng");
+    tag.set_title("NEW TITLE");
+    assert_eq!(tag.localized(&ident::TITLE, "eng"), None);
+    assert_eq!(tag.localized(&ident::TITLE, "jpn"), Some("日本語のタイトル"));
+}
+
+#[test]
+fn read_only_localized() {
+    let mut artist = vec![0, 0, 0, 1, 0, 0, 0x15, 0xC7];
+    artist.extend_from_slice(b"ENGLISH ARTIST");
+    let mut artist_jpn = vec![0, 0, 0, 1, 0, 0, 0x2A, 0x0E];
+    artist_jpn.extend_from_slice("日本語のアーティスト".as_bytes());
+    let mut artist_data = atom(b"data", &artist);
+    artist_data.extend(atom(b"data", &artist_jpn));
+    let data = sample_with_raw_artist(atom(b"\xa9ART", &artist_data));
+
+    // the first value of an atom is its regular value, regardless of its language
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(tag.artist(), Some("ENGLISH ARTIST"));
+    assert_eq!(tag.localized(&ident::ARTIST, "eng"), None);
+    assert_eq!(tag.localized(&ident::ARTIST, "jpn"), Some("日本語のアーティスト"));
+}
+
+#[test]
+fn write_unknown_datatype() {
+    let path = "target/write_unknown_datatype.m4a";
+    let _ = std::fs::remove_file(path);
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    let unknown = Data::Unknown { datatype: 74, data: 1234i64.to_be_bytes().to_vec() };
+    let mut tag = get_tag_1();
+    tag.set_data(Fourcc(*b"unkn"), unknown.clone());
+    tag.write_to_path(path).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_tag_1(&tag);
+    assert!(tag.data_of(&Fourcc(*b"unkn")).all(Data::is_unknown));
+    assert_eq!(tag.data_of(&Fourcc(*b"unkn")).collect::<Vec<_>>(), vec![&unknown]);
+}
+
+#[test]
+fn write_rare_string_atoms() {
+    let path = "target/write_rare_string_atoms.m4a";
+    let _ = std::fs::remove_file(path);
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    let rare = vec![
+        (DataIdent::fourcc(*b"\xa9cpy"), Data::Utf8("RARE COPYRIGHT".into())),
+        (DataIdent::fourcc(*b"\xa9xyz"), Data::Utf8("somewhere".into())),
+        (DataIdent::fourcc(*b"\xa9enc"), Data::Utf16("RARE ENCODED BY".into())),
+    ];
+    let mut tag = get_tag_1();
+    tag.extend(rare.clone());
+    tag.write_to_path(path).unwrap();
+
+    let read = Tag::read_from_path(path).unwrap();
+    let entries: Vec<_> =
+        read.entries().filter(|(i, _)| rare.iter().any(|(r, _)| r == *i)).collect();
+    let expected: Vec<_> = rare.iter().map(|(i, d)| (i, std::slice::from_ref(d))).collect();
+    assert_eq!(entries, expected);
+
+    // copy all entries to a new tag and write it again
+    let mut copy = Tag::default();
+    for (ident, data) in read.entries() {
+        copy.add_all_data(ident.clone(), data.to_vec());
+    }
+    copy.write_to_path(path).unwrap();
+
+    let read = Tag::read_from_path(path).unwrap();
+    assert_tag_1(&read);
+    for (ident, data) in rare.iter() {
+        assert_eq!(read.data_of(ident).collect::<Vec<_>>(), vec![data]);
+    }
+}
+
+#[test]
+fn write_transcoded_strings() {
+    let path = "target/write_transcoded_strings.m4a";
+    let _ = std::fs::remove_file(path);
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    let mut tag = get_tag_1();
+    tag.set_localized(ident::TITLE, "jpn", "日本語のタイトル").unwrap();
+    tag.transcode_strings(StringEncoding::Utf16);
+    tag.write_to_path(path).unwrap();
+
+    let mut tag = Tag::read_from_path(path).unwrap();
+    assert_tag_1(&tag);
+    assert_eq!(tag.localized(&ident::TITLE, "jpn"), Some("日本語のタイトル"));
+    assert!(tag.data().filter(|(_, d)| d.is_string()).all(|(_, d)| d.is_utf16()));
+
+    tag.transcode_strings(StringEncoding::Utf8);
+    assert_tag_1(&tag);
+    assert!(tag.data().filter(|(_, d)| d.is_string()).all(|(_, d)| d.is_utf8()));
+}
+
+#[test]
+fn write_utf16_bom() {
+    let path = "target/write_utf16_bom.m4a";
+    let _ = std::fs::remove_file(path);
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    let mut tag = get_tag_1();
+    tag.set_data(ident::TITLE, Data::Utf16("UTF-16 TITLE".into()));
+    let cfg = WriteConfig { utf16_bom: true, ..Default::default() };
+    tag.write_to_path_with_config(path, &cfg).unwrap();
+
+    let written = fs::read(path).unwrap();
+    let title_pos = written.windows(4).position(|w| w == b"\xa9nam").unwrap() - 4;
+    let title = &written[title_pos..title_pos + 8 + 16 + 2 + 2 * 12];
+    assert_eq!(title[..4], u32::to_be_bytes(title.len() as u32));
+    assert_eq!(title[24..28], [0xfe, 0xff, 0, b'U']);
+
+    let read = Tag::read_from_path(path).unwrap();
+    assert_eq!(read.title(), Some("UTF-16 TITLE"));
+    assert_eq!(read.artist(), Some("TEST ARTIST"));
+
+    tag.write_to_path(path).unwrap();
+    let written = fs::read(path).unwrap();
+    let title_pos = written.windows(4).position(|w| w == b"\xa9nam").unwrap() - 4;
+    assert_eq!(written[title_pos + 24..title_pos + 26], [0, b'U']);
+    assert_eq!(Tag::read_from_path(path).unwrap().title(), Some("UTF-16 TITLE"));
+}
+
+#[test]
+fn extract_artwork() {
+    let path = "target/extract_artwork.m4a";
+    let _ = std::fs::remove_file(path);
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    let mut tag = get_tag_1();
+    tag.set_artworks(vec![
+        Img::jpeg(b"\xff\xd8\xfffirst".to_vec()),
+        Img::png(b"\x89PNG\r\n\x1a\nsecond".to_vec()),
+    ])
+    .unwrap();
+    tag.write_to_path(path).unwrap();
+
+    let mut reader = std::fs::File::open(path).unwrap();
+    let mut writer = Vec::new();
+    let fmt = Tag::extract_artwork_to(1, &mut writer, &mut reader).unwrap();
+    assert_eq!(fmt, Some(ImgFmt::Png));
+    assert_eq!(writer, b"\x89PNG\r\n\x1a\nsecond");
+
+    reader.seek(SeekFrom::Start(0)).unwrap();
+    let fmt = Tag::extract_artwork_to(2, &mut writer, &mut reader).unwrap();
+    assert_eq!(fmt, None);
+}
+
+#[test]
+fn read_mixed_artwork_formats() {
+    let path = "target/read_mixed_artwork_formats.m4a";
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    let jpeg = b"\xff\xd8\xfffront".to_vec();
+    let png = b"\x89PNG\r\n\x1a\nback".to_vec();
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.set_data(ident::ARTWORK, Data::Jpeg(jpeg.clone()));
+    tag.add_data(ident::ARTWORK, Data::Reserved(png.clone()));
+    tag.add_data(ident::ARTWORK, Data::Jpeg(png.clone()));
+    tag.add_data(ident::ARTWORK, Data::Reserved(b"unknown".to_vec()));
+    tag.add_data(ident::ARTWORK, Data::Reserved(jpeg.clone()));
+    tag.add_data(ident::ARTWORK, Data::Unknown { datatype: 99, data: png.clone() });
+    tag.write_to_path(path).unwrap();
+
+    // an image datatype takes precedence over the signature
+    let tag = Tag::read_from_path(path).unwrap();
+    let artworks: Vec<_> = tag.artworks().collect();
+    assert_eq!(
+        artworks,
+        [
+            Img::jpeg(&jpeg[..]),
+            Img::png(&png[..]),
+            Img::jpeg(&png[..]),
+            Img::jpeg(&jpeg[..]),
+            Img::png(&png[..]),
+        ]
+    );
+    // reserved data that isn't an image is kept as unknown data
+    let unknown: Vec<_> = tag.data_of(&ident::ARTWORK).filter(|d| !d.is_image()).collect();
+    assert_eq!(unknown, [&Data::Unknown { datatype: 0, data: b"unknown".to_vec() }]);
+
+    for (i, a) in artworks.iter().enumerate() {
+        let mut reader = std::fs::File::open(path).unwrap();
+        let mut writer = Vec::new();
+        let fmt = Tag::extract_artwork_to(i, &mut writer, &mut reader).unwrap();
+        assert_eq!(fmt.as_ref(), Some(&a.fmt));
+        assert_eq!(writer, a.data);
+        assert_eq!(tag.artwork_at(i).as_ref(), Some(a));
+    }
+    assert_eq!(tag.artwork_at(artworks.len()), None);
+}
+
+#[test]
+fn write_artwork_with_fmt() {
+    let path = "target/write_artwork_with_fmt.m4a";
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    // the forced format disagrees with the png signature
+    let png = b"\x89PNG\r\n\x1a\nforced".to_vec();
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.set_artwork_with_fmt(png.clone(), ImgFmt::Bmp);
+    tag.write_to_path(path).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.artwork(), Some(Img::bmp(&png[..])));
+}
+
+#[test]
+fn read_empty_artwork() {
+    let mut artist = vec![0, 0, 0, 1, 0, 0, 0, 0];
+    artist.extend_from_slice(b"TEST ARTIST");
+    let mut raw = atom(b"\xa9ART", &atom(b"data", &artist));
+    let mut covr = atom(b"data", &[0, 0, 0, 13, 0, 0, 0, 0]);
+    let mut jpeg = vec![0, 0, 0, 13, 0, 0, 0, 0];
+    jpeg.extend_from_slice(b"\xff\xd8\xffcover");
+    covr.extend(atom(b"data", &jpeg));
+    raw.extend(atom(b"covr", &covr));
+    let data = sample_with_raw_artist(raw);
+
+    // the empty placeholder is skipped
+    let png = fs::read("files/artwork.png").unwrap();
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(tag.artist(), Some("TEST ARTIST"));
+    let artworks: Vec<_> = tag.artworks().collect();
+    assert_eq!(artworks, [Img::jpeg(&b"\xff\xd8\xffcover"[..]), Img::png(&png[..])]);
+    assert_eq!(tag.artwork_count(), 2);
+    assert_eq!(tag.data_of(&ident::ARTWORK).count(), 3);
+
+    let mut writer = Vec::new();
+    let fmt = Tag::extract_artwork_to(0, &mut writer, &mut Cursor::new(&data)).unwrap();
+    assert_eq!(fmt, Some(ImgFmt::Jpeg));
+    assert_eq!(writer, b"\xff\xd8\xffcover");
+
+    // and kept when writing
+    let path = "target/read_empty_artwork.m4a";
+    fs::write(path, &data).unwrap();
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.set_title("NEW TITLE");
+    tag.write_to_path(path).unwrap();
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.title(), Some("NEW TITLE"));
+    assert_eq!(tag.artwork_count(), 2);
+    assert_eq!(tag.data_of(&ident::ARTWORK).next(), Some(&Data::Jpeg(Vec::new())));
+}
+
+#[test]
+fn read_localized_artwork() {
+    let mut artist = vec![0, 0, 0, 1, 0, 0, 0, 0];
+    artist.extend_from_slice(b"TEST ARTIST");
+    let mut raw = atom(b"\xa9ART", &atom(b"data", &artist));
+    // a regular jpeg image, a japanese png image for japan (0x0188), and an english jpeg image
+    // without a country
+    let mut regular = vec![0, 0, 0, 13, 0, 0, 0, 0];
+    regular.extend_from_slice(b"\xff\xd8\xffregular");
+    let mut png = vec![0, 0, 0, 14, 0x01, 0x88, 0x2A, 0x0E];
+    png.extend_from_slice(b"\x89PNG\r\n\x1a\njapan");
+    let mut jpeg = vec![0, 0, 0, 13, 0, 0, 0x15, 0xC7];
+    jpeg.extend_from_slice(b"\xff\xd8\xffenglish");
+    let mut covr = atom(b"data", &regular);
+    covr.extend(atom(b"data", &png));
+    covr.extend(atom(b"data", &jpeg));
+    raw.extend(atom(b"covr", &covr));
+    let data = sample_with_raw_artist(raw);
+
+    let path = "target/read_localized_artwork.m4a";
+    fs::write(path, &data).unwrap();
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.set_title("NEW TITLE");
+    tag.write_to_path(path).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.title(), Some("NEW TITLE"));
+    let png = fs::read("files/artwork.png").unwrap();
+    let artworks = [Img::jpeg(&b"\xff\xd8\xffregular"[..]), Img::png(&png[..])];
+    assert_eq!(tag.artworks().collect::<Vec<_>>(), artworks);
+
+    let localized: Vec<_> = tag.artworks_localized().collect();
+    assert_eq!(
+        localized,
+        [
+            (
+                Img::png(&b"\x89PNG\r\n\x1a\njapan"[..]),
+                Locale { country: 0x0188, language: 0x2A0E },
+            ),
+            (Img::jpeg(&b"\xff\xd8\xffenglish"[..]), Locale { country: 0, language: 0x15C7 }),
+        ]
+    );
+    assert_eq!(localized[0].1.language_code().as_deref(), Some("jpn"));
+    assert_eq!(localized[1].1.language_code().as_deref(), Some("eng"));
+}
+
+#[test]
+fn read_artwork_aliases() {
+    let mut artist = vec![0, 0, 0, 1, 0, 0, 0, 0];
+    artist.extend_from_slice(b"TEST ARTIST");
+    let mut raw = atom(b"\xa9ART", &atom(b"data", &artist));
+    let mut pict = vec![0, 0, 0, 0, 0, 0, 0, 0];
+    pict.extend_from_slice(b"\xff\xd8\xffpict");
+    raw.extend(atom(b"pict", &atom(b"data", &pict)));
+    let data = sample_with_raw_artist(raw);
+
+    // by default only the artwork atom is read as artwork
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_tag_1(&tag);
+    let artwork_count = tag.artwork_count();
+    assert_eq!(tag.bytes_of(&Fourcc(*b"pict")).count(), 1);
+
+    let artwork_atoms = vec![ident::ARTWORK, Fourcc(*b"pict")];
+    let cfg = ReadConfig { artwork_atoms, ..Default::default() };
+    let tag = Tag::read_with_config(&mut Cursor::new(&data), &cfg).unwrap();
+    assert_eq!(tag.artwork_count(), artwork_count + 1);
+    assert_eq!(tag.artworks().next(), Some(Img::jpeg(&b"\xff\xd8\xffpict"[..])));
+    assert_eq!(tag.data_of(&Fourcc(*b"pict")).count(), 0);
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn set_artwork_validated() {
+    let png = fs::read("files/artwork.png").unwrap();
+    let mut tag = Tag::default();
+
+    let err = tag.set_artwork_validated(Img::png(&png[..png.len() - 16])).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::InvalidImageData));
+    assert_eq!(tag.artwork(), None);
+
+    tag.set_artwork_validated(Img::png(png.clone())).unwrap();
+    assert_eq!(tag.artwork(), Some(Img::png(&png[..])));
+
+    // like with the checked setter, clones share the image
+    let clone = tag.clone();
+    assert_eq!(clone.artwork().unwrap().data.as_ptr(), tag.artwork().unwrap().data.as_ptr());
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn artwork_to_jpeg() {
+    let png = fs::read("files/artwork.png").unwrap();
+
+    let jpeg = Img::png(&png).to_jpeg(80).unwrap();
+    assert_eq!(ImgFmt::sniff(&jpeg.data), Some(ImgFmt::Jpeg));
+    assert!(jpeg.validate().is_ok());
+
+    let err = Img::png(&png[..png.len() / 2]).to_jpeg(80).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::InvalidImageData));
+}
+
+#[test]
+fn write_genre_policy() {
+    let path = "target/write_genre_policy.m4a";
+    let _ = std::fs::remove_file(path);
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    let mut tag = get_tag_1();
+    tag.set_standard_genre(18);
+    tag.set_custom_genre("Shoegaze");
+
+    let cfg = WriteConfig { genre_policy: GenrePolicy::KeepBoth, ..Default::default() };
+    tag.write_to_path_with_config(path, &cfg).unwrap();
+    let read = Tag::read_from_path(path).unwrap();
+    assert_eq!(read.standard_genre(), Some(18));
+    assert_eq!(read.custom_genre(), Some("Shoegaze"));
+
+    tag.write_to_path(path).unwrap();
+    let read = Tag::read_from_path(path).unwrap();
+    assert_eq!(read.standard_genre(), None);
+    assert_eq!(read.custom_genre(), Some("Shoegaze"));
+    assert_eq!(tag.standard_genre(), Some(18));
+}
+
+#[test]
+fn write_float() {
+    let path = "target/write_float.m4a";
+    let _ = std::fs::remove_file(path);
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    let mut tag = get_tag_1();
+    tag.set_data(Fourcc(*b"fl32"), Data::BeFloat32(-1.5));
+    tag.set_data(Fourcc(*b"fl64"), Data::BeFloat64(0.1));
+    tag.write_to_path(path).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_tag_1(&tag);
+    assert_eq!(tag.data_of(&Fourcc(*b"fl32")).next(), Some(&Data::BeFloat32(-1.5)));
+    assert_eq!(tag.data_of(&Fourcc(*b"fl64")).next().and_then(Data::as_f64), Some(0.1));
+}
+
+#[test]
+fn write_shared_images() {
+    let mut tag = get_tag_1();
+    tag.share_images();
+    let tag = tag.clone();
+
+    let _ = std::fs::remove_file("target/write_shared_images.m4a");
+    println!("copying files/sample.m4a to target/write_shared_images.m4a...");
+    std::fs::copy("files/sample.m4a", "target/write_shared_images.m4a").unwrap();
+
+    println!("writing...");
+    tag.write_to_path("target/write_shared_images.m4a").unwrap();
+
+    println!("reading...");
+    let tag = Tag::read_from_path("target/write_shared_images.m4a").unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+}
+
+#[test]
+fn diff_artwork() {
+    let old = get_tag_1();
+    let mut new = old.clone();
+    new.set_artwork_unchecked(Img::jpeg(vec![0xff; 16]));
+
+    let changes = old.diff(&new);
+    assert_eq!(changes.len(), 1);
+    match &changes[0] {
+        FieldChange::Changed { ident, old, new } => {
+            assert_eq!(ident, &DataIdent::Fourcc(ident::ARTWORK));
+            let len = fs::metadata("files/artwork.png").unwrap().len() as usize;
+            assert_eq!(old, &[DataSummary::Image { fmt: ImgFmt::Png, len }]);
+            assert_eq!(new, &[DataSummary::Image { fmt: ImgFmt::Jpeg, len: 16 }]);
+        }
+        c => panic!("unexpected change: {:?}", c),
+    }
+    assert!(new.diff(&new.clone()).is_empty());
+
+    let mut relabeled = new.clone();
+    relabeled.set_artwork_unchecked(Img::png(vec![0xff; 16]));
+    assert!(new.diff(&relabeled).is_empty());
+}
+
+#[test]
+fn write_faststart() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let (moov_pos, moov_len) = top_level_bounds(&data, b"moov");
+    let (mdat_pos, _) = top_level_bounds(&data, b"mdat");
+    let free_pos = moov_pos + moov_len;
+    let free_len = mdat_pos - free_pos;
+    assert_eq!(&data[free_pos + 4..free_pos + 8], b"free");
+
+    // split the padding into a free and a wide atom
+    data[free_pos..free_pos + 4].copy_from_slice(&u32::to_be_bytes(free_len as u32 - 8));
+    data[mdat_pos - 8..mdat_pos - 4].copy_from_slice(&u32::to_be_bytes(8));
+    data[mdat_pos - 4..mdat_pos].copy_from_slice(b"wide");
+    let mdat = data[mdat_pos..].to_vec();
+
+    let _ = std::fs::remove_file("target/write_faststart.m4a");
+    println!("writing faststart sample to target/write_faststart.m4a...");
+    fs::write("target/write_faststart.m4a", &data).unwrap();
+
+    for tag in [get_tag_1(), get_tag_2(), Tag::default()].iter() {
+        println!("writing...");
+        tag.write_to_path("target/write_faststart.m4a").unwrap();
+
+        println!("reading...");
+        let read_tag = Tag::read_from_path("target/write_faststart.m4a").unwrap();
+        assert!(tag.entries().eq(read_tag.entries()));
+        assert_readonly(&read_tag);
+
+        let new_data = fs::read("target/write_faststart.m4a").unwrap();
+        assert_eq!(new_data.len(), data.len());
+        assert_eq!(top_level_bounds(&new_data, b"wide"), (mdat_pos - 8, 8));
+        assert_eq!(&new_data[mdat_pos..], &mdat[..]);
+    }
+}
+
+#[test]
+fn write_mdat_to_eof() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let (mdat_pos, mdat_len) = top_level_bounds(&data, b"mdat");
+    data[mdat_pos..mdat_pos + 4].copy_from_slice(&u32::to_be_bytes(0));
+
+    let _ = std::fs::remove_file("target/write_mdat_to_eof.m4a");
+    println!("writing sample to target/write_mdat_to_eof.m4a...");
+    fs::write("target/write_mdat_to_eof.m4a", &data).unwrap();
+
+    let mut tag = get_tag_2();
+    tag.set_xmp(b"<x:xmpmeta/>".to_vec());
+
+    println!("writing...");
+    tag.write_to_path("target/write_mdat_to_eof.m4a").unwrap();
+
+    println!("reading...");
+    let tag = Tag::read_from_path("target/write_mdat_to_eof.m4a").unwrap();
+    assert_tag_2(&tag);
+    assert_readonly(&tag);
+    assert_eq!(tag.xmp(), Some(&b"<x:xmpmeta/>"[..]));
+
+    let data = fs::read("target/write_mdat_to_eof.m4a").unwrap();
+    let (_, new_mdat_len) = top_level_bounds(&data, b"mdat");
+    assert_eq!(new_mdat_len, mdat_len);
+}
+
+/// Returns the identifier, position and length of the consecutive atoms in the range.
+fn atoms_in(data: &[u8], start: usize, end: usize) -> Vec<([u8; 4], usize, usize)> {
+    let mut atoms = Vec::new();
+    let mut pos = start;
+    while pos < end {
+        let mut head = [0u8; 8];
+        head.copy_from_slice(&data[pos..pos + 8]);
+        let len = be_u32_at(&head, 0) as usize;
+        atoms.push(([head[4], head[5], head[6], head[7]], pos, len));
+        pos += len;
+    }
+    atoms
+}
+
+/// Returns the identifiers and bounds of the children of the movie atom (`moov`).
+fn moov_children(data: &[u8]) -> Vec<([u8; 4], usize, usize)> {
+    let (moov_pos, moov_len) = top_level_bounds(data, b"moov");
+    atoms_in(data, moov_pos + 8, moov_pos + moov_len)
+}
+
+#[test]
+fn write_new_meta_hdlr() {
+    let path = "target/write_new_meta_hdlr.m4a";
+    let sample = fs::read("files/sample.m4a").unwrap();
+    let meta_pos = sample.windows(4).position(|w| w == b"meta").unwrap();
+    let mut hdlr = vec![0; 8];
+    hdlr.extend_from_slice(b"mdirappl");
+    hdlr.extend_from_slice(&[0; 9]);
+
+    // replace the user data, metadata or handler atom of the metadata with a free atom
+    for fourcc in [b"udta", b"meta", b"hdlr"].iter() {
+        let mut data = sample.clone();
+        let pos = data[meta_pos..].windows(4).position(|w| w == *fourcc).map(|p| meta_pos + p);
+        let pos = pos.or_else(|| data.windows(4).position(|w| w == *fourcc)).unwrap();
+        data[pos..pos + 4].copy_from_slice(b"free");
+        fs::write(path, data).unwrap();
+
+        get_tag_1().write_to_path(path).unwrap();
+        let written = fs::read(path).unwrap();
+        let find = |atoms: Vec<([u8; 4], usize, usize)>, fourcc: &[u8; 4]| {
+            let (_, pos, len) = atoms.into_iter().find(|(f, _, _)| f == fourcc).unwrap();
+            (pos, pos + len)
+        };
+        let (udta_pos, udta_end) = find(moov_children(&written), b"udta");
+        let (meta_pos, meta_end) = find(atoms_in(&written, udta_pos + 8, udta_end), b"meta");
+        let (hdlr_pos, hdlr_end) = find(atoms_in(&written, meta_pos + 12, meta_end), b"hdlr");
+        assert_eq!(&written[hdlr_pos + 8..hdlr_end], &hdlr[..]);
+
+        let tag = Tag::read_from_path(path).unwrap();
+        assert_tag_1(&tag);
+        assert_readonly(&tag);
+    }
+}
+
+#[test]
+fn read_unexpected_meta_handler() {
+    let sample = fs::read("files/sample.m4a").unwrap();
+    let meta_pos = sample.windows(4).position(|w| w == b"meta").unwrap();
+    let hdlr_pos = meta_pos + sample[meta_pos..].windows(4).position(|w| w == b"hdlr").unwrap();
+    assert_eq!(&sample[hdlr_pos + 12..hdlr_pos + 16], b"mdir");
+
+    // a handler of an unexpected type
+    let mut data = sample.clone();
+    data[hdlr_pos + 12..hdlr_pos + 16].copy_from_slice(b"zzzz");
+    let tag = Tag::read_from(&mut Cursor::new(data)).unwrap();
+    assert_tag_1(&tag);
+
+    // a missing handler
+    let mut data = sample;
+    data[hdlr_pos..hdlr_pos + 4].copy_from_slice(b"free");
+    let tag = Tag::read_from(&mut Cursor::new(data)).unwrap();
+    assert_tag_1(&tag);
+}
+
+#[test]
+fn read_trak_without_mdia() {
+    let path = "target/read_trak_without_mdia.m4a";
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let (_, trak_pos, _) = *moov_children(&data).iter().find(|(f, _, _)| f == b"trak").unwrap();
+
+    // a track consisting only of a track header, preceding the audio track
+    let trak = atom(b"trak", &atom(b"tkhd", &[0; 84]));
+    splice_atoms(&mut data, trak_pos..trak_pos, &trak, &[b"moov"]);
+    fs::write(path, &data).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+
+    get_tag_2().write_to_path(path).unwrap();
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_tag_2(&tag);
+    assert_readonly(&tag);
+}
+
+#[test]
+fn write_keeps_moov_child_order() {
+    let data = fs::read("files/sample.m4a").unwrap();
+    let children = moov_children(&data);
+    let udta = children.iter().position(|(f, ..)| f == b"udta").unwrap();
+    let trak = children.iter().position(|(f, ..)| f == b"trak").unwrap();
+    assert!(trak < udta);
+
+    // a copy of the sample with the udta atom moved to the start of the moov atom
+    let mut udta_first = data.clone();
+    let (_, start, _) = children[0];
+    let (_, udta_pos, udta_len) = children[udta];
+    let mut moved = data[start..udta_pos].to_vec();
+    moved.splice(0..0, data[udta_pos..udta_pos + udta_len].iter().copied());
+    udta_first[start..udta_pos + udta_len].copy_from_slice(&moved);
+
+    for (i, data) in [data, udta_first].iter().enumerate() {
+        let path = format!("target/write_keeps_moov_child_order_{}.m4a", i);
+        fs::write(&path, data).unwrap();
+
+        let mut tag = Tag::read_from_path(&path).unwrap();
+        tag.set_lyrics("new lyrics ".repeat(100));
+        tag.write_to_path(&path).unwrap();
+
+        let written = fs::read(&path).unwrap();
+        let idents = |d: &[u8]| moov_children(d).into_iter().map(|(f, ..)| f).collect::<Vec<_>>();
+        assert_eq!(idents(&written), idents(data));
+        assert_ne!(moov_children(&written), moov_children(data));
+        assert_eq!(Tag::read_from_path(&path).unwrap().lyrics(), Some(&*"new lyrics ".repeat(100)));
+    }
+}
+
+/// Inserts the children into the user data atom (`udta`) of the sample file, before the meta atom.
+fn sample_with_udta_children(children: &[u8]) -> Vec<u8> {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let udta_pos = data.windows(4).position(|w| w == b"udta").unwrap() - 4;
+    splice_atoms(&mut data, udta_pos + 8..udta_pos + 8, children, &[b"moov", b"udta"]);
+    data
+}
+
+#[test]
+fn write_keeps_udta_children() {
+    let path = "target/write_keeps_udta_children.m4a";
+    let mut children = atom(b"\xa9xyz", b"\x00\x12\x15\xc7+12.3456-098.7654/");
+    children.extend(atom(b"name", b"USER DATA NAME"));
+
+    fs::write(path, sample_with_udta_children(&children)).unwrap();
+    assert_tag_1(&Tag::read_from_path(path).unwrap());
+
+    let tags = [get_tag_2(), Tag::default()];
+    for tag in tags.iter() {
+        tag.write_to_path(path).unwrap();
+        let written = fs::read(path).unwrap();
+        let udta_pos = written.windows(4).position(|w| w == b"udta").unwrap() - 4;
+        assert_eq!(&written[udta_pos + 8..udta_pos + 8 + children.len()], &children[..]);
+    }
+    get_tag_2().write_to_path(path).unwrap();
+    assert_tag_2(&Tag::read_from_path(path).unwrap());
+}
+
+#[test]
+fn read_3gpp_assets() {
+    let path = "target/read_3gpp_assets.m4a";
+    let mut children = atom(b"titl", b"\0\0\0\0\x15\xc73GPP TITLE\0");
+    children.extend(atom(b"titl", b"\0\0\0\0\x1a\x41TITRE 3GPP\0"));
+    let mut auth = b"\0\0\0\0\x15\xc7\xfe\xff".to_vec();
+    auth.extend("3GPP AUTHOR\0".encode_utf16().flat_map(u16::to_be_bytes));
+    children.extend(atom(b"auth", &auth));
+    children.extend(atom(b"albm", b"\0\0\0\0\x15\xc73GPP ALBUM\0\x03"));
+    children.extend(atom(b"yrrc", b"\0\0\0\0\x07\xda"));
+    children.extend(atom(b"dscp", b"\0\0\0\0"));
+    let mut data = sample_with_udta_children(&children);
+    fs::write(path, &data).unwrap();
+
+    // the item list takes precedence
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_tag_1(&tag);
+
+    let ilst_pos = data.windows(4).position(|w| w == b"ilst").unwrap();
+    data[ilst_pos..ilst_pos + 4].copy_from_slice(b"free");
+    fs::write(path, &data).unwrap();
+    let mut tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.title(), Some("3GPP TITLE"));
+    assert_eq!(tag.data_of(&ident::TITLE).count(), 1);
+    assert_eq!(tag.localized(&ident::TITLE, "eng"), Some("3GPP TITLE"));
+    assert_eq!(tag.localized(&ident::TITLE, "fra"), Some("TITRE 3GPP"));
+    assert_eq!(tag.languages_of(&ident::TITLE).collect::<Vec<_>>(), ["eng", "fra"]);
+    assert_eq!(tag.artist(), Some("3GPP AUTHOR"));
+    assert_eq!(tag.album(), Some("3GPP ALBUM"));
+    assert_eq!(tag.year(), Some("2010"));
+    assert_eq!(tag.description(), None);
+
+    // the assets are written to the item list, so removals stick
+    tag.remove_title();
+    tag.write_to_path(path).unwrap();
+    let written = fs::read(path).unwrap();
+    assert!(!written.windows(4).any(|w| w == b"titl" || w == b"auth"));
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.title(), None);
+    assert_eq!(tag.artist(), Some("3GPP AUTHOR"));
+    assert_eq!(tag.album(), Some("3GPP ALBUM"));
+    assert_eq!(tag.year(), Some("2010"));
+}
+
+/// Returns an ID3v2.3 tag containing a title frame (`TIT2`).
+fn id3v2_with_title(title: &str) -> Vec<u8> {
+    let mut frame = b"TIT2".to_vec();
+    frame.extend_from_slice(&u32::to_be_bytes(title.len() as u32 + 1));
+    frame.extend_from_slice(&[0, 0, 0]);
+    frame.extend_from_slice(title.as_bytes());
+    let mut id3v2 = b"ID3\x03\x00\x00\x00\x00\x00".to_vec();
+    id3v2.push(frame.len() as u8);
+    id3v2.extend(frame);
+    id3v2
+}
+
+/// Returns a metadata atom with an `ID32` handler containing the ID3v2 tag.
+fn id32_meta(id3v2: &[u8]) -> Vec<u8> {
+    let mut id32 = b"\0\0\0\0\x15\xc7".to_vec();
+    id32.extend_from_slice(id3v2);
+    let mut content = vec![0; 4];
+    content.extend(hdlr(b"ID32"));
+    content.extend(atom(b"ID32", &id32));
+    atom(b"meta", &content)
+}
+
+#[test]
+fn read_id3v2_box() {
+    let path = "target/read_id3v2_box.m4a";
+    let id3v2 = id3v2_with_title("ID3 TITLE");
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    data.extend(id32_meta(&id3v2));
+    fs::write(path, &data).unwrap();
+
+    // the item list takes precedence
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.id3v2_box(), Some(&id3v2[..]));
+    assert_tag_1(&tag);
+
+    Tag::default().write_to_path(path).unwrap();
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.id3v2_box(), Some(&id3v2[..]));
+    #[cfg(feature = "id3")]
+    assert_eq!(tag.title(), Some("ID3 TITLE"));
+    #[cfg(not(feature = "id3"))]
+    assert!(tag.is_empty());
+}
+
+#[test]
+fn write_id3v2_box() {
+    let path = "target/write_id3v2_box.m4a";
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    data.extend(id32_meta(&id3v2_with_title("ID3 TITLE")));
+    fs::write(path, &data).unwrap();
+    let raw = WriteConfig { id3v2: Id3v2Style::Raw, ..Default::default() };
+
+    // the language is kept
+    let mut tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.id3v2_language().as_deref(), Some("eng"));
+    let id3v2 = id3v2_with_title("NEW ID3 TITLE");
+    tag.set_id3v2_box(id3v2.clone());
+    tag.write_to_path_with_config(path, &raw).unwrap();
+
+    let written = fs::read(path).unwrap();
+    assert_eq!(written.windows(4).filter(|w| w == b"ID32").count(), 2);
+    assert!(written.ends_with(&id32_meta(&id3v2)));
+    let mut tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.id3v2_box(), Some(&id3v2[..]));
+    assert_eq!(tag.id3v2_language().as_deref(), Some("eng"));
+    assert_tag_1(&tag);
+
+    // by default the ID3v2 atom is kept
+    tag.remove_id3v2_box();
+    tag.write_to_path(path).unwrap();
+    assert_eq!(Tag::read_from_path(path).unwrap().id3v2_box(), Some(&id3v2[..]));
+
+    tag.write_to_path_with_config(path, &raw).unwrap();
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.id3v2_box(), None);
+    assert_tag_1(&tag);
+
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.set_id3v2_box(id3v2.clone());
+    tag.write_to_path_with_config(path, &raw).unwrap();
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.id3v2_box(), Some(&id3v2[..]));
+    assert_eq!(tag.id3v2_language().as_deref(), Some("und"));
+}
+
+#[cfg(feature = "id3")]
+#[test]
+fn write_id3v2_synthesized() {
+    let path = "target/write_id3v2_synthesized.m4a";
+    fs::copy("files/sample.m4a", path).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    let cfg = WriteConfig { id3v2: Id3v2Style::Synthesized, ..Default::default() };
+    tag.write_to_path_with_config(path, &cfg).unwrap();
+    let tag = Tag::read_from_path(path).unwrap();
+    assert!(tag.id3v2_box().is_some());
+    assert_tag_1(&tag);
+
+    // without an item list, the metadata is read from the ID3v2 tag
+    let cfg = WriteConfig { id3v2: Id3v2Style::SynthesizedOnly, ..Default::default() };
+    tag.write_to_path_with_config(path, &cfg).unwrap();
+    let written = Tag::read_from_path(path).unwrap();
+    assert_eq!(written.title(), Some("TEST TITLE"));
+    assert_eq!(written.artist(), Some("TEST ARTIST"));
+    assert_eq!(written.album(), Some("TEST ALBUM"));
+    assert_eq!(written.composer(), tag.composer());
+    assert_eq!(written.genre(), tag.genre());
+    assert_eq!(written.year(), tag.year());
+    assert_eq!(written.track(), tag.track());
+    assert_eq!(written.disc(), tag.disc());
+    assert_eq!(written.comment(), tag.comment());
+    assert_eq!(written.lyrics(), tag.lyrics());
+    assert_eq!(written.data_of(&ident::ARTWORK).count(), 0);
+}
+
+#[cfg(not(feature = "id3"))]
+#[test]
+fn write_id3v2_synthesized_without_feature() {
+    let path = "target/write_id3v2_synthesized_without_feature.m4a";
+    fs::copy("files/sample.m4a", path).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    for style in [Id3v2Style::Synthesized, Id3v2Style::SynthesizedOnly].iter() {
+        let cfg = WriteConfig { id3v2: *style, ..Default::default() };
+        let err = tag.write_to_path_with_config(path, &cfg).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::UnwritableData));
+    }
+    assert_eq!(fs::read(path).unwrap(), fs::read("files/sample.m4a").unwrap());
+}
+
+#[cfg(feature = "id3")]
+#[test]
+fn read_id3v2_box_fields() {
+    use id3::TagLike;
+
+    let mut id3 = id3::Tag::new();
+    id3.set_title("ID3 TITLE");
+    id3.set_artist("ID3 ARTIST");
+    id3.set_album("ID3 ALBUM");
+    id3.set_genre("(17)");
+    id3.set_year(2010);
+    id3.set_track(3);
+    id3.set_total_tracks(12);
+    id3.add_frame(id3::frame::Comment {
+        lang: "eng".to_owned(),
+        description: String::new(),
+        text: "ID3 COMMENT".to_owned(),
+    });
+    let mut id3v2 = Vec::new();
+    id3.write_to(&mut id3v2, id3::Version::Id3v24).unwrap();
+
+    let mut sample = fs::read("files/sample.m4a").unwrap();
+    let udta_pos = sample.windows(4).position(|w| w == b"udta").unwrap();
+    sample[udta_pos..udta_pos + 4].copy_from_slice(b"free");
+
+    let data = [&sample[..], &id32_meta(&id3v2)].concat();
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(tag.title(), Some("ID3 TITLE"));
+    assert_eq!(tag.artist(), Some("ID3 ARTIST"));
+    assert_eq!(tag.album(), Some("ID3 ALBUM"));
+    assert_eq!(tag.genre(), Some("Rock"));
+    assert_eq!(tag.year(), Some("2010"));
+    assert_eq!(tag.track(), (Some(3), Some(12)));
+    assert_eq!(tag.comment(), Some("ID3 COMMENT"));
+
+    // an invalid ID3v2 tag is only kept as is
+    let data = [&sample[..], &id32_meta(b"garbage")].concat();
+    let (tag, stats) = Tag::read_from_with_stats(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(tag.id3v2_box(), Some(&b"garbage"[..]));
+    assert!(tag.is_empty());
+    assert_eq!(stats.warnings.len(), 1);
+    assert!(stats.warnings[0].starts_with("Error reading ID3v2 atom (ID32)"));
+}
+
+/// A reader of the prefix, followed by zeros and the suffix, which doesn't need to store the
+/// zeros, so files of several gigabytes can be simulated.
+struct GapReader<'a> {
+    prefix: &'a [u8],
+    gap_len: u64,
+    suffix: &'a [u8],
+    pos: u64,
+}
+
+impl Read for GapReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let prefix_len = self.prefix.len() as u64;
+        let suffix_pos = prefix_len + self.gap_len;
+        let n = if self.pos < prefix_len {
+            (&self.prefix[self.pos as usize..]).read(buf)?
+        } else if self.pos < suffix_pos {
+            let n = (suffix_pos - self.pos).min(buf.len() as u64) as usize;
+            buf[..n].iter_mut().for_each(|b| *b = 0);
+            n
+        } else {
+            let start = (self.pos - suffix_pos).min(self.suffix.len() as u64);
+            (&self.suffix[start as usize..]).read(buf)?
+        };
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for GapReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let len = self.prefix.len() as u64 + self.gap_len + self.suffix.len() as u64;
+        let pos = match pos {
+            SeekFrom::Start(p) => p as i64,
+            SeekFrom::Current(o) => self.pos as i64 + o,
+            SeekFrom::End(o) => len as i64 + o,
+        };
+        if pos < 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "seek before the start"));
+        }
+        self.pos = pos as u64;
+        Ok(self.pos)
+    }
+}
+
+#[test]
+fn read_large_mdat_before_moov() {
+    let data = fs::read("files/sample-64.mp4").unwrap();
+    let (ftyp_pos, ftyp_len) = top_level_bounds(&data, b"ftyp");
+    let (moov_pos, moov_len) = top_level_bounds(&data, b"moov");
+    let mdat_len: u64 = 16 + 5 * 1024 * 1024 * 1024;
+
+    let mut prefix = data[ftyp_pos..ftyp_pos + ftyp_len].to_vec();
+    prefix.extend_from_slice(&u32::to_be_bytes(1));
+    prefix.extend_from_slice(b"mdat");
+    prefix.extend_from_slice(&u64::to_be_bytes(mdat_len));
+    let mut reader = GapReader {
+        prefix: &prefix,
+        gap_len: mdat_len - 16,
+        suffix: &data[moov_pos..moov_pos + moov_len],
+        pos: 0,
+    };
+
+    let tag = Tag::read_from(&mut reader).unwrap();
+    let expected = Tag::read_from_path("files/sample-64.mp4").unwrap();
+    assert_eq!(tag, expected);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn read_sample_async() {
+    let mut file = tokio::fs::File::open("files/sample.m4a").await.unwrap();
+    let tag = Tag::read_from_async(&mut file).await.unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+
+    let mut file = tokio::fs::File::open("files/sample-64.mp4").await.unwrap();
+    let tag = Tag::read_from_async(&mut file).await.unwrap();
+    assert_eq!(tag, Tag::read_from_path("files/sample-64.mp4").unwrap());
+}
+
+#[test]
+fn read_sample_multi_data() {
+    let tag = Tag::read_from_path("files/sample-multi-data.m4a").unwrap();
+
+    assert_tag_3(&tag);
+    assert_readonly(&tag);
+}
+
+#[test]
+fn write() {
+    let tag = get_tag_2();
+
+    let _ = std::fs::remove_file("target/write.m4a");
+    println!("copying files/sample.m4a to target/write.m4a...");
+    std::fs::copy("files/sample.m4a", "target/write.m4a").unwrap();
+
+    println!("writing...");
+    tag.write_to_path("target/write.m4a").unwrap();
+
+    println!("reading...");
+    let tag = Tag::read_from_path("target/write.m4a").unwrap();
+    assert_tag_2(&tag);
+    assert_readonly(&tag);
+}
+
+#[test]
+fn write_same() {
+    let tag = get_tag_1();
+
+    let _ = std::fs::remove_file("target/write_same.m4a");
+    println!("copying files/sample.m4a to target/write_same.m4a...");
+    std::fs::copy("files/sample.m4a", "target/write_same.m4a").unwrap();
+
+    println!("writing...");
+    tag.write_to_path("target/write_same.m4a").unwrap();
+
+    println!("reading...");
+    let tag = Tag::read_from_path("target/write_same.m4a").unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+}
+
+#[test]
+fn write_bigger() {
+    let mut tag = get_tag_2();
+    let data: Vec<u8> = (0..2048).map(|n| (n % 255) as u8).collect();
+    tag.add_data(Fourcc(*b"test"), Data::Reserved(data));
+
+    let _ = std::fs::remove_file("target/write_bigger.m4a");
+    println!("copying files/sample.m4a to target/write_bigger.m4a...");
+    std::fs::copy("files/sample.m4a", "target/write_bigger.m4a").unwrap();
+
+    println!("writing...");
+    tag.write_to_path("target/write_bigger.m4a").unwrap();
+
+    println!("reading...");
+    let tag = Tag::read_from_path("target/write_bigger.m4a").unwrap();
+    assert_tag_2(&tag);
+    assert_readonly(&tag);
+}
+
+#[test]
+fn write_empty() {
+    let tag = Tag::default();
+
+    let _ = std::fs::remove_file("target/write_empty.m4a");
+    println!("copying files/sample.m4a to target/write_empty.m4a...");
+    std::fs::copy("files/sample.m4a", "target/write_empty.m4a").unwrap();
+
+    println!("writing...");
+    tag.write_to_path("target/write_empty.m4a").unwrap();
+
+    println!("reading...");
+    let tag = Tag::read_from_path("target/write_empty.m4a").unwrap();
+    assert!(tag.is_empty());
+    assert_readonly(&tag);
+}
+
+#[test]
+fn write_xmp() {
+    let mut tag = get_tag_1();
+    tag.set_xmp(b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>".to_vec());
+
+    let _ = std::fs::remove_file("target/write_xmp.m4a");
+    println!("copying files/sample.m4a to target/write_xmp.m4a...");
+    std::fs::copy("files/sample.m4a", "target/write_xmp.m4a").unwrap();
+
+    println!("writing...");
+    tag.write_to_path("target/write_xmp.m4a").unwrap();
+
+    println!("reading...");
+    let mut tag = Tag::read_from_path("target/write_xmp.m4a").unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+    assert_eq!(tag.xmp(), Some(&b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>"[..]));
+
+    let xmp: Vec<u8> = (0..1024).map(|n| (n % 255) as u8).collect();
+    tag.set_xmp(xmp.clone());
+
+    println!("writing bigger...");
+    tag.write_to_path("target/write_xmp.m4a").unwrap();
+
+    println!("reading...");
+    let mut tag = Tag::read_from_path("target/write_xmp.m4a").unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+    assert_eq!(tag.xmp(), Some(&xmp[..]));
+
+    tag.remove_xmp();
+
+    println!("removing...");
+    tag.write_to_path("target/write_xmp.m4a").unwrap();
+
+    println!("reading...");
+    let tag = Tag::read_from_path("target/write_xmp.m4a").unwrap();
+    assert_tag_1(&tag);
+    assert_readonly(&tag);
+    assert_eq!(tag.xmp(), None);
+}
+
+#[test]
+fn read_short_uuid() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    data.extend(atom(b"uuid", b"short"));
+
+    let (tag, stats) = Tag::read_from_with_stats(&mut Cursor::new(&data)).unwrap();
+    assert_tag_1(&tag);
+    assert_eq!(tag.xmp(), None);
+    assert_eq!(stats.warnings, ["User extension (uuid) atom is too short to contain a uuid"]);
+}
+
+#[test]
+fn write_drm_protected() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let stsd_pos = data.windows(4).position(|w| w == b"stsd").unwrap();
+    let mp4a_pos = stsd_pos + data[stsd_pos..].windows(4).position(|w| w == b"mp4a").unwrap();
+    data[mp4a_pos..mp4a_pos + 4].copy_from_slice(b"drms");
+    let stsd_len = be_u32_at(&data, stsd_pos - 4) as usize;
+    let stsd = data[stsd_pos..stsd_pos - 4 + stsd_len].to_vec();
+
+    let _ = std::fs::remove_file("target/write_drm_protected.m4p");
+    println!("writing protected sample to target/write_drm_protected.m4p...");
+    fs::write("target/write_drm_protected.m4p", &data).unwrap();
+
+    println!("reading...");
+    let mut tag = Tag::read_from_path("target/write_drm_protected.m4p").unwrap();
+    assert!(tag.is_drm_protected());
+    assert_readonly(&tag);
+
+    tag.set_title("NEW TITLE");
+
+    println!("writing...");
+    tag.write_to_path("target/write_drm_protected.m4p").unwrap();
+
+    println!("reading...");
+    let tag = Tag::read_from_path("target/write_drm_protected.m4p").unwrap();
+    assert!(tag.is_drm_protected());
+    assert_readonly(&tag);
+    assert_eq!(tag.title(), Some("NEW TITLE"));
+
+    let data = fs::read("target/write_drm_protected.m4p").unwrap();
+    assert!(data.windows(stsd.len()).any(|w| w == &stsd[..]));
+}
+
+#[test]
+fn read_malformed() {
+    let data = fs::read("files/sample.m4a").unwrap();
+    let moov_pos = data.windows(4).position(|w| w == b"moov").unwrap() - 4;
+    let udta_pos = data.windows(4).position(|w| w == b"udta").unwrap() - 4;
+
+    let mut moov_overrun = data.clone();
+    moov_overrun[moov_pos..moov_pos + 4].copy_from_slice(&u32::to_be_bytes(u32::MAX));
+    let err = Tag::read_from(&mut Cursor::new(moov_overrun)).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        ErrorKind::TruncatedAtom { fourcc: Fourcc(f), expected, .. }
+            if &f == b"moov" && expected == u32::MAX as u64
+    ));
+
+    let mut udta_overrun = data;
+    udta_overrun[udta_pos..udta_pos + 4].copy_from_slice(&u32::to_be_bytes(0x00ff_ffff));
+    let err = Tag::read_from(&mut Cursor::new(udta_overrun)).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::Malformed(p) if p == udta_pos as u64));
+}
+
+#[test]
+fn read_at_offset() {
+    let data = fs::read("files/sample.m4a").unwrap();
+    let (moov_pos, _) = top_level_bounds(&data, b"moov");
+    let mut reader = Cursor::new(data);
+
+    let tag = Tag::from_reader_at(&mut reader, moov_pos as u64).unwrap();
+    assert_tag_1(&tag);
+    assert_eq!(tag.filetype(), "");
+
+    let tag = Tag::from_reader_at(&mut reader, 0).unwrap();
+    assert_tag_1(&tag);
+    assert_eq!(tag.filetype(), "M4A \u{0}\u{0}\u{2}\u{0}isomiso2");
+
+    let err = Tag::from_reader_at(&mut reader, moov_pos as u64 + 8).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::AtomNotFound(Fourcc(f)) if &f == b"moov"));
+}
+
+#[test]
+fn read_from_moov_bytes() {
+    let data = fs::read("files/sample.m4a").unwrap();
+    let (moov_pos, moov_len) = top_level_bounds(&data, b"moov");
+    let moov = &data[moov_pos..moov_pos + moov_len];
 
-    read_dir("files", |p, t| {
-        let file_name = p.file_name().unwrap().to_owned();
-        let mut path = PathBuf::from("target/files");
-        path.push(file_name);
+    let tag = Tag::read_from_moov_bytes(moov).unwrap();
+    assert_tag_1(&tag);
+    assert_eq!(tag.filetype(), "");
+    assert_eq!(tag.duration(), Tag::read_from(&mut Cursor::new(&data)).unwrap().duration());
 
-        println!("copying {} to {}...", p.display(), path.display());
-        fs::copy(p, &path).unwrap();
+    let err = Tag::read_from_moov_bytes(&moov[..moov_len - 1]).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::TruncatedAtom { .. }));
 
-        println!("writing empty tag");
-        Tag::default().write_to_path(&path).unwrap();
-        println!("reading empty tag");
-        let tag = Tag::read_from_path(&path).unwrap();
-        assert!(tag.is_empty());
-        assert_eq!(tag.audio_info(), t.audio_info());
+    let mut trailing = moov.to_vec();
+    trailing.extend_from_slice(&[0; 8]);
+    let err = Tag::read_from_moov_bytes(&trailing).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::Parsing));
 
-        println!("writing sample tag 1");
-        get_tag_1().write_to_path(&path).unwrap();
-        println!("reading sample tag 1");
-        let tag = Tag::read_from_path(&path).unwrap();
+    let err = Tag::read_from_moov_bytes(&data).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::AtomNotFound(Fourcc(f)) if &f == b"moov"));
+}
+
+#[test]
+fn read_sample_count() {
+    let sample = fs::read("files/sample.m4a").unwrap();
+    let stsz_pos = sample.windows(4).position(|w| w == b"stsz").unwrap() - 4;
+    let read = |sample_size: u32, sample_count: u32| {
+        let mut data = sample.clone();
+        data[stsz_pos + 12..stsz_pos + 16].copy_from_slice(&sample_size.to_be_bytes());
+        data[stsz_pos + 16..stsz_pos + 20].copy_from_slice(&sample_count.to_be_bytes());
+        Tag::read_from(&mut Cursor::new(data)).unwrap().audio_sample_count()
+    };
+
+    // table of sample sizes
+    assert_eq!(read(0, 21), Some(21));
+    // constant sample size, the table is ignored
+    assert_eq!(read(372, 1000), Some(1000));
+    // truncated table of sample sizes
+    assert_eq!(read(0, 1000), Some(21));
+}
+
+/// Replaces the mp4a sample entry of the sample file with the sample entry.
+fn sample_with_sample_entry(entry: Vec<u8>) -> Vec<u8> {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let mp4a_pos = data.windows(4).position(|w| w == b"mp4a").unwrap() - 4;
+    let mp4a_end = mp4a_pos + be_u32_at(&data, mp4a_pos) as usize;
+    let containers = [b"moov", b"trak", b"mdia", b"minf", b"stbl", b"stsd"];
+    splice_atoms(&mut data, mp4a_pos..mp4a_end, &entry, &containers);
+    data
+}
+
+#[test]
+fn read_alac_magic_cookie() {
+    let mut cookie = vec![0; 4];
+    cookie.extend_from_slice(&u32::to_be_bytes(4096));
+    // compatible version, bit depth, rice parameters and channel count
+    cookie.extend_from_slice(&[0, 24, 40, 10, 14, 2]);
+    cookie.extend_from_slice(&u16::to_be_bytes(255));
+    cookie.extend_from_slice(&u32::to_be_bytes(0));
+    cookie.extend_from_slice(&u32::to_be_bytes(2_304_000));
+    cookie.extend_from_slice(&u32::to_be_bytes(96000));
+    // the generic fields of the sample entry differ from the magic cookie
+    let mut entry = vec![0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0];
+    entry.extend_from_slice(&[0, 1, 0, 16, 0, 0, 0, 0]);
+    entry.extend_from_slice(&u32::to_be_bytes(44100 << 16));
+    entry.extend(atom(b"alac", &cookie));
+    let data = sample_with_sample_entry(atom(b"alac", &entry));
+
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_tag_1(&tag);
+    assert_eq!(tag.bit_depth(), Some(24));
+    assert_eq!(tag.sample_rate(), Some(SampleRate::Hz96000));
+    assert_eq!(tag.channel_config(), Some(ChannelConfig::Stereo));
+    assert_eq!(tag.avg_bitrate(), Some(2_304_000));
+    assert_eq!(tag.max_bitrate(), None);
+    assert!(tag.to_string().contains("bit depth: 24bit"));
+
+    // a QuickTime sound sample description of version 1 has 16 additional bytes
+    let mut v1_entry = entry[..entry.len() - cookie.len() - 8].to_vec();
+    v1_entry[9] = 1;
+    v1_entry.extend_from_slice(&[0; 16]);
+    let mut v1_cookie = cookie.clone();
+    v1_cookie[24..28].copy_from_slice(&u32::to_be_bytes(192000));
+    v1_entry.extend(atom(b"alac", &v1_cookie));
+    let data = sample_with_sample_entry(atom(b"alac", &v1_entry));
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(tag.bit_depth(), Some(24));
+    assert_eq!(tag.sample_rate(), Some(SampleRate::Hz192000));
+
+    // a magic cookie that is too short, declares an invalid length or is missing is skipped with a
+    // warning
+    let entry = entry[..entry.len() - cookie.len() - 8].to_vec();
+    let short = [&entry[..], &atom(b"alac", &cookie[..20])].concat();
+    let mut too_long = [&entry[..], &atom(b"alac", &cookie)].concat();
+    too_long[entry.len()..entry.len() + 4].copy_from_slice(&u32::to_be_bytes(1000));
+    let mut too_small = too_long.clone();
+    too_small[entry.len()..entry.len() + 4].copy_from_slice(&u32::to_be_bytes(4));
+    for entry in [short, too_long, too_small, entry].iter() {
+        let data = sample_with_sample_entry(atom(b"alac", entry));
+        let (tag, stats) = Tag::read_from_with_stats(&mut Cursor::new(&data)).unwrap();
         assert_tag_1(&tag);
-        assert_eq!(tag.audio_info(), t.audio_info());
+        assert_eq!(tag.bit_depth(), None);
+        assert_eq!(tag.sample_rate(), None);
+        assert_eq!(stats.warnings, ["Missing or invalid alac magic cookie"]);
+    }
+}
 
-        println!("writing sample tag 2");
-        get_tag_2().write_to_path(&path).unwrap();
-        println!("reading sample tag 2");
-        let tag = Tag::read_from_path(&path).unwrap();
-        assert_tag_2(&tag);
-        assert_eq!(tag.audio_info(), t.audio_info());
-        println!();
-    });
+/// Switches the moov, udta and meta atoms of the sample file to extended heads.
+fn sample_with_extended_heads() -> Vec<u8> {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+
+    // starting with the innermost atom, so the heads of its containers are still standard
+    let atoms: [(&[u8; 4], &[&[u8; 4]]); 3] =
+        [(b"meta", &[b"moov", b"udta"]), (b"udta", &[b"moov"]), (b"moov", &[])];
+    for (fourcc, containers) in atoms.iter() {
+        let pos = data.windows(4).position(|w| w == *fourcc).unwrap() - 4;
+        let mut head = u32::to_be_bytes(1).to_vec();
+        head.extend_from_slice(*fourcc);
+        head.extend_from_slice(&u64::to_be_bytes(be_u32_at(&data, pos) as u64 + 8));
+        splice_atoms(&mut data, pos..pos + 8, &head, containers);
+    }
+    data
 }
 
 #[test]
-fn read_sample() {
-    let tag = Tag::read_from_path("files/sample.m4a").unwrap();
+fn write_extended_heads() {
+    let path = "target/write_extended_heads.m4a";
+    fs::write(path, sample_with_extended_heads()).unwrap();
 
+    let tag = Tag::read_from_path(path).unwrap();
     assert_tag_1(&tag);
     assert_readonly(&tag);
+
+    get_tag_2().write_to_path(path).unwrap();
+    let data = fs::read(path).unwrap();
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_tag_2(&tag);
+    assert_readonly(&tag);
+
+    // the lengths of the extended heads are updated
+    let (moov_pos, moov_len) = top_level_bounds(&data, b"moov");
+    let udta_pos = data.windows(4).position(|w| w == b"udta").unwrap() - 4;
+    let meta_pos = udta_pos + data[udta_pos..].windows(4).position(|w| w == b"meta").unwrap() - 4;
+    for pos in [moov_pos, udta_pos, meta_pos].iter() {
+        assert_eq!(data[*pos..pos + 4], [0, 0, 0, 1]);
+    }
+    let mut udta_len = [0; 8];
+    udta_len.copy_from_slice(&data[udta_pos + 8..udta_pos + 16]);
+    assert_eq!(udta_pos + u64::from_be_bytes(udta_len) as usize, moov_pos + moov_len);
 }
 
 #[test]
-fn read_sample_multi_data() {
-    let tag = Tag::read_from_path("files/sample-multi-data.m4a").unwrap();
-
-    assert_tag_3(&tag);
+#[ignore = "requires more than 12 GiB of memory"]
+fn write_extended_moov() {
+    let path = "target/write_extended_moov.m4a";
+    fs::copy("files/sample.m4a", path).unwrap();
+
+    // artwork that grows the moov, udta and meta atoms beyond the maximum length of 4 GiB of a
+    // standard head
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png.resize(u32::MAX as usize, 0);
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.add_data(ident::ARTWORK, Data::Png(png));
+    tag.write_to_path(path).unwrap();
+    drop(tag);
+
+    let mut file = fs::File::open(path).unwrap();
+    let (mut head, mut len) = ([0; 8], [0; 8]);
+    file.seek(SeekFrom::Start(32)).unwrap();
+    file.read_exact(&mut head).unwrap();
+    file.read_exact(&mut len).unwrap();
+    assert_eq!(head, *b"\0\0\0\x01moov");
+    assert!(u64::from_be_bytes(len) > u32::MAX as u64);
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_tag_1(&tag);
     assert_readonly(&tag);
+    assert_eq!(tag.artwork_count(), 2);
+    assert_eq!(tag.artworks().nth(1).unwrap().data.len(), u32::MAX as usize);
 }
 
 #[test]
-fn write() {
-    let tag = get_tag_2();
+fn read_sample_durations() {
+    let sample = fs::read("files/sample.m4a").unwrap();
+    let stts_pos = sample.windows(4).position(|w| w == b"stts").unwrap() - 4;
+    let read = |entry_count: u32| {
+        let mut data = sample.clone();
+        data[stts_pos + 12..stts_pos + 16].copy_from_slice(&entry_count.to_be_bytes());
+        Tag::read_from(&mut Cursor::new(data)).unwrap().sample_durations().map(<[_]>::to_vec)
+    };
+
+    assert_eq!(read(2), Some(vec![(20, 1024), (1, 919)]));
+    assert_eq!(read(1), Some(vec![(20, 1024)]));
+    assert_eq!(read(0), Some(vec![]));
+    // truncated table
+    assert_eq!(read(1000), Some(vec![(20, 1024), (1, 919)]));
+
+    let mut data = sample.clone();
+    data[stts_pos + 8] = 1;
+    let err = Tag::read_from(&mut Cursor::new(data)).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::UnknownVersion(1)));
+}
 
-    let _ = std::fs::remove_file("target/write.m4a");
-    println!("copying files/sample.m4a to target/write.m4a...");
-    std::fs::copy("files/sample.m4a", "target/write.m4a").unwrap();
+#[test]
+fn release_year() {
+    let path = "target/release_year.m4a";
+    let _ = std::fs::remove_file(path);
+    std::fs::copy("files/sample.m4a", path).unwrap();
+
+    let mut tag = get_tag_1();
+    tag.set_year("2020-05-17T10:20:30Z");
+    tag.write_to_path(path).unwrap();
+    assert_eq!(Tag::read_from_path(path).unwrap().release_year(), Some(2020));
+
+    // set the creation time of the movie header to the same time
+    let mut data = fs::read(path).unwrap();
+    let mvhd_pos = data.windows(4).position(|w| w == b"mvhd").unwrap() - 4;
+    data[mvhd_pos + 12..mvhd_pos + 16].copy_from_slice(&3_672_555_630u32.to_be_bytes());
+    fs::write(path, data).unwrap();
+
+    let mut tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.movie_header().unwrap().creation_time, 3_672_555_630);
+    assert_eq!(tag.year(), Some("2020-05-17T10:20:30Z"));
+    assert_eq!(tag.release_year(), None);
+
+    let same_times =
+        ["2020-05-17 10:20:30.250", "2020-05-17T12:20:30+02:00", "2020-05-17T05:20:30-0500"];
+    for same in same_times.iter() {
+        tag.set_year(*same);
+        assert_eq!(tag.release_year(), None);
+    }
+    tag.set_year("2020-05-17 10:20:31");
+    assert_eq!(tag.release_year(), Some(2020));
+    tag.set_year("2020-05-17T10:20:30+02");
+    assert_eq!(tag.release_year(), Some(2020));
+    tag.set_year("2019-05");
+    assert_eq!(tag.release_year(), Some(2019));
+    for invalid in ["20", "2019-13", "2019-05-17T10", "2019/05/17", "May 2019"].iter() {
+        tag.set_year(*invalid);
+        assert_eq!(tag.release_year(), None);
+    }
+}
 
-    println!("writing...");
-    tag.write_to_path("target/write.m4a").unwrap();
+#[test]
+fn read_extended_language() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(tag.language(), None);
+    assert_eq!(tag.extended_language(), None);
+
+    let mut elng = vec![0; 4];
+    elng.extend_from_slice(b"en-US\0");
+    let elng = atom(b"elng", &elng);
+
+    // the elng atom is inserted as the first child of the mdia atom, the media data offsets
+    // aren't updated since they aren't needed for reading
+    let mdia_pos = data.windows(4).position(|w| w == b"mdia").unwrap() - 4;
+    splice_atoms(&mut data, mdia_pos + 8..mdia_pos + 8, &elng, &[b"moov", b"trak", b"mdia"]);
+
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_tag_1(&tag);
+    assert_eq!(tag.extended_language(), Some("en-US"));
+}
 
-    println!("reading...");
-    let tag = Tag::read_from_path("target/write.m4a").unwrap();
-    assert_tag_2(&tag);
-    assert_readonly(&tag);
+#[test]
+fn read_truncated() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let (moov_pos, moov_len) = top_level_bounds(&data, b"moov");
+    data.truncate(moov_pos + moov_len / 2);
+
+    let available = (data.len() - moov_pos) as u64;
+    let err = Tag::read_from(&mut Cursor::new(data)).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        ErrorKind::TruncatedAtom { fourcc: Fourcc(f), expected, available: a }
+            if &f == b"moov" && expected == moov_len as u64 && a == available
+    ));
 }
 
 #[test]
-fn write_same() {
+fn read_duplicate_moov() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let (moov_pos, _) = top_level_bounds(&data, b"moov");
+    let placeholder = atom(b"moov", &atom(b"free", &[0; 4]));
+    data.splice(moov_pos..moov_pos, placeholder.iter().copied());
+    data.extend(placeholder);
+
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_tag_1(&tag);
+
+    let _ = fs::remove_file("target/duplicate_moov.m4a");
+    fs::write("target/duplicate_moov.m4a", &data).unwrap();
+
+    let mut tag = Tag::read_from_path("target/duplicate_moov.m4a").unwrap();
+    tag.set_title("new title");
+    tag.write_to_path("target/duplicate_moov.m4a").unwrap();
+
+    let tag = Tag::read_from_path("target/duplicate_moov.m4a").unwrap();
+    assert_eq!(tag.title(), Some("new title"));
+    assert_eq!(tag.artist(), Some("TEST ARTIST"));
+}
+
+#[test]
+fn read_not_an_mp4() {
+    let data = fs::read("files/artwork.png").unwrap();
+    let err = Tag::read_from(&mut Cursor::new(data)).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::NotAnMp4));
+
+    let err = Tag::read_from(&mut Cursor::new(b"tiny")).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::NotAnMp4));
+
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let (moov_pos, moov_len) = top_level_bounds(&data, b"moov");
+    data.drain(moov_pos..moov_pos + moov_len);
+    let err = Tag::read_from(&mut Cursor::new(data)).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::NotAnMp4));
+}
+
+#[test]
+fn read_no_audio_track() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let pos = data.windows(4).position(|w| w == b"soun").unwrap();
+    data[pos..pos + 4].copy_from_slice(b"vide");
+
+    // silent videos can also be tagged
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_tag_1(&tag);
+
+    let cfg = ReadConfig { require_audio_track: true, ..Default::default() };
+    let err = Tag::read_with_config(&mut Cursor::new(&data), &cfg).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::NoAudioTrack));
+}
+
+#[test]
+fn read_deeply_nested() {
+    const DEPTH: usize = 100_000;
+    const CONTAINERS: [&[u8; 4]; 4] = [b"trak", b"mdia", b"minf", b"stbl"];
+
+    let mut moov = Vec::with_capacity(8 * DEPTH);
+    for i in 0..DEPTH {
+        moov.extend_from_slice(&u32::to_be_bytes(8 * (DEPTH - i) as u32));
+        moov.extend_from_slice(CONTAINERS[i % CONTAINERS.len()]);
+    }
+
+    let mut data = atom(b"ftyp", b"M4A \0\0\0\0");
+    data.extend(atom(b"moov", &moov));
+
+    let tag = Tag::read_from(&mut Cursor::new(data)).unwrap();
+    assert_eq!(tag.data().count(), 0);
+}
+
+#[test]
+fn ilst_free_atoms() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+
+    // a free atom is inserted after the first item and a free and a skip atom at the end of the
+    // item list
+    let ilst_pos = data.windows(4).position(|w| w == b"ilst").unwrap() - 4;
+    let ilst_len = be_u32_at(&data, ilst_pos) as usize;
+    let first_pos = ilst_pos + 8;
+    let first_len = be_u32_at(&data, first_pos) as usize;
+    let inner_free = atom(b"free", &[0; 8]);
+    let mut trailing_free = atom(b"free", &[0; 24]);
+    trailing_free.extend(atom(b"skip", &[0; 32]));
+    let containers = [b"moov", b"udta", b"meta", b"ilst"];
+    let ilst_end = ilst_pos + ilst_len;
+    splice_atoms(&mut data, ilst_end..ilst_end, &trailing_free, &containers);
+    let first_end = first_pos + first_len;
+    splice_atoms(&mut data, first_end..first_end, &inner_free, &containers);
+
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_tag_1(&tag);
+    assert!(tag.data().all(|(i, _)| Fourcc(*b"free") != *i && Fourcc(*b"skip") != *i));
+
+    // returns the length of the item list and of the free atom at its end
+    let ilst_and_padding_len = |path: &str| {
+        let data = fs::read(path).unwrap();
+        let ilst_pos = data.windows(4).position(|w| w == b"ilst").unwrap() - 4;
+        let ilst_len = be_u32_at(&data, ilst_pos) as usize;
+        let children = atoms_in(&data, ilst_pos + 8, ilst_pos + ilst_len);
+        let padding = children.last().filter(|(f, ..)| f == b"free").map(|(_, _, len)| *len);
+        (ilst_len, padding)
+    };
+
+    // the padding absorbs the length difference of the items, including the dropped inner free
+    // atom, while the item list keeps its length
+    let ilst_len = ilst_len + inner_free.len() + trailing_free.len();
+    let title_diff = "TEST TITLE".len() - "new title".len();
+    let kept = (ilst_len, Some(72 + 16 + title_diff));
+    let dropped = (ilst_len - 88 - title_diff, None);
+    for (keep_ilst_padding, lens) in [(true, kept), (false, dropped)].iter() {
+        let path = "target/ilst_free_atoms.m4a";
+        fs::write(path, &data).unwrap();
+
+        let cfg = WriteConfig { keep_ilst_padding: *keep_ilst_padding, ..Default::default() };
+        let mut tag = Tag::read_from_path(path).unwrap();
+        tag.set_title("new title");
+        tag.write_to_path_with_config(path, &cfg).unwrap();
+
+        let tag = Tag::read_from_path(path).unwrap();
+        assert_eq!(tag.title(), Some("new title"));
+        assert_eq!(tag.artist(), Some("TEST ARTIST"));
+        assert_eq!(ilst_and_padding_len(path), *lens);
+    }
+
+    // if the items don't fit, the padding keeps its length
+    let path = "target/ilst_free_atoms.m4a";
+    fs::write(path, &data).unwrap();
+    let cfg = WriteConfig { keep_ilst_padding: true, ..Default::default() };
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.set_comment("a".repeat(200));
+    tag.write_to_path_with_config(path, &cfg).unwrap();
+    assert_eq!(ilst_and_padding_len(path).1, Some(72));
+}
+
+#[test]
+fn reproducible_write() {
     let tag = get_tag_1();
+    let encoded = tag.encode_to_vec().unwrap();
+    for _ in 0..8 {
+        assert_eq!(tag.clone().encode_to_vec().unwrap(), encoded);
+    }
 
-    let _ = std::fs::remove_file("target/write_same.m4a");
-    println!("copying files/sample.m4a to target/write_same.m4a...");
-    std::fs::copy("files/sample.m4a", "target/write_same.m4a").unwrap();
+    let mut written = Vec::new();
+    for i in 0..2 {
+        let path = format!("target/reproducible_write_{}.m4a", i);
+        fs::copy("files/sample.m4a", &path).unwrap();
+        tag.write_to_path(&path).unwrap();
+        written.push(fs::read(&path).unwrap());
 
-    println!("writing...");
-    tag.write_to_path("target/write_same.m4a").unwrap();
+        let tag = Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.encode_to_vec().unwrap(), encoded);
+    }
+    assert_eq!(written[0], written[1]);
 
-    println!("reading...");
-    let tag = Tag::read_from_path("target/write_same.m4a").unwrap();
+    let mut reordered = Tag::default();
+    for (ident, data) in tag.data().collect::<Vec<_>>().into_iter().rev() {
+        reordered.add_data(ident.clone(), data.clone());
+    }
+    assert_ne!(reordered.encode_to_vec().unwrap(), encoded);
+
+    let mut tag = tag;
+    tag.normalize();
+    reordered.normalize();
+    assert_eq!(reordered.encode_to_vec().unwrap(), tag.encode_to_vec().unwrap());
+}
+
+#[test]
+fn normalize_integers() {
+    let mut tag = Tag::default();
+    tag.set_data(ident::BPM, Data::BeSigned(vec![0xFF, 0xFF, 0xFF, 0x88]));
+    tag.set_data(ident::TV_EPISODE, Data::BeSigned(vec![0x80]));
+    tag.set_data(ident::MOVEMENT_COUNT, Data::BeSigned(vec![0, 0, 0, 0x80]));
+    tag.set_data(ident::TV_SEASON, Data::BeSigned(vec![0x80, 0, 0, 0, 0]));
+    tag.set_data(ident::MEDIA_TYPE, Data::Reserved(vec![0, 0, 0, 0x80]));
+    tag.normalize();
+
+    let data = |ident| tag.data_of(&ident).next().cloned();
+    assert_eq!(data(ident::BPM), Some(Data::BeSigned(vec![0xFF, 0x88])));
+    assert_eq!(data(ident::TV_EPISODE), Some(Data::BeSigned(vec![0xFF, 0xFF, 0xFF, 0x80])));
+    assert_eq!(data(ident::MOVEMENT_COUNT), Some(Data::BeSigned(vec![0, 0x80])));
+    assert_eq!(data(ident::TV_SEASON), Some(Data::BeSigned(vec![0x80, 0, 0, 0, 0])));
+    assert_eq!(data(ident::MEDIA_TYPE), Some(Data::Reserved(vec![0x80])));
+}
+
+/// Replaces the artist atom (`©ART`) of the sample file with the raw atom.
+fn sample_with_raw_artist(raw_artist: Vec<u8>) -> Vec<u8> {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let artist_pos = data.windows(4).position(|w| w == b"\xa9ART").unwrap() - 4;
+    let artist_end = artist_pos + be_u32_at(&data, artist_pos) as usize;
+    let containers = [b"moov", b"udta", b"meta", b"ilst"];
+    splice_atoms(&mut data, artist_pos..artist_end, &raw_artist, &containers);
+    data
+}
+
+#[cfg(feature = "encoding_rs")]
+#[test]
+fn read_fallback_encoding() {
+    let mut artist = vec![0, 0, 0, 1, 0, 0, 0, 0];
+    artist.extend_from_slice(b"Caf\xe9 \x80");
+    let data = sample_with_raw_artist(atom(b"\xa9ART", &atom(b"data", &artist)));
+
+    let err = Tag::read_from(&mut Cursor::new(&data)).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::Utf8StringDecoding(_)));
+
+    let cfg =
+        ReadConfig { fallback_encoding: Some(encoding_rs::WINDOWS_1252), ..Default::default() };
+    let (tag, stats) = Tag::read_with_config_and_stats(&mut Cursor::new(&data), &cfg).unwrap();
+    assert_eq!(tag.artist(), Some("Café €"));
+    assert_eq!(stats.warnings, ["Decoded invalid utf-8 of '©ART' as windows-1252"]);
+    assert_eq!(tag.title(), Some("TEST TITLE"));
+}
+
+#[test]
+fn read_mixed_multi_data() {
+    let path = "target/read_mixed_multi_data.m4a";
+    let mut artist = atom(b"data", b"\0\0\0\0\0\0\0\0blob");
+    artist.extend(atom(b"data", b"\0\0\0\x01\0\0\0\0TEST ARTIST"));
+    artist.extend(atom(b"data", b"\0\0\0\x15\0\0\0\0\x01"));
+    artist.extend(atom(b"data", b"\0\0\0\x01\0\0\0\0OTHER ARTIST"));
+    fs::write(path, sample_with_raw_artist(atom(b"\xa9ART", &artist))).unwrap();
+
+    let expected = [
+        Data::Reserved(b"blob".to_vec()),
+        Data::Utf8("TEST ARTIST".to_owned()),
+        Data::BeSigned(vec![1]),
+        Data::Utf8("OTHER ARTIST".to_owned()),
+    ];
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.data_of(&ident::ARTIST).cloned().collect::<Vec<_>>(), expected);
+    assert_eq!(tag.artist(), Some("TEST ARTIST"));
+    assert_eq!(tag.artists().collect::<Vec<_>>(), ["TEST ARTIST", "OTHER ARTIST"]);
+    assert_eq!(tag.bytes_of(&ident::ARTIST).next(), Some(&b"blob"[..]));
+
+    // the data is written in the same order
+    tag.write_to_path(path).unwrap();
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.data_of(&ident::ARTIST).cloned().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn read_data_atom_header() {
+    // typical data atom with a utf-8 datatype and the default locale
+    let mut content = vec![0, 0, 0, 1, 0, 0, 0, 0];
+    content.extend_from_slice(b"ARTIST");
+    let data = sample_with_raw_artist(atom(b"\xa9ART", &atom(b"data", &content)));
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(tag.artist(), Some("ARTIST"));
+    assert_eq!(tag.title(), Some("TEST TITLE"));
+
+    // minimal data atom consisting only of the 8 byte header
+    let data = sample_with_raw_artist(atom(b"\xa9ART", &atom(b"data", &[0, 0, 0, 1, 0, 0, 0, 0])));
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(tag.artist(), Some(""));
+    assert_eq!(tag.title(), Some("TEST TITLE"));
+
+    // data atom that is too short to contain the header
+    let data = sample_with_raw_artist(atom(b"\xa9ART", &atom(b"data", &[0, 0, 0, 1])));
+    let err = Tag::read_from(&mut Cursor::new(&data)).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::Malformed(_)));
+    assert!(err.description.contains("less than 8 bytes"));
+}
+
+#[test]
+fn read_data_atom_length() {
+    let artist_pos = |data: &[u8]| data.windows(4).position(|w| w == b"\xa9ART").unwrap() - 4;
+
+    // data atom declaring a length that exceeds the artist atom
+    let mut artist = atom(b"\xa9ART", &atom(b"data", b"\0\0\0\x01\0\0\0\0ARTIST"));
+    artist[8..12].copy_from_slice(&u32::to_be_bytes(64));
+    let data = sample_with_raw_artist(artist);
+    let err = Tag::read_from(&mut Cursor::new(&data)).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::Malformed(p) if p == artist_pos(&data) as u64 + 8));
+
+    // utf-16 data of an odd length
+    let content = b"\0\0\0\x02\0\0\0\0\0A\0R\0";
+    let data = sample_with_raw_artist(atom(b"\xa9ART", &atom(b"data", content)));
+    let err = Tag::read_from(&mut Cursor::new(&data)).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::Malformed(p) if p == artist_pos(&data) as u64 + 8));
+    assert!(err.description.contains("odd length"));
+
+    let cfg = ReadConfig { lenient: true, ..Default::default() };
+    let (tag, stats) = Tag::read_with_config_and_stats(&mut Cursor::new(&data), &cfg).unwrap();
+    assert_eq!(tag.artist(), Some("AR"));
+    assert_eq!(tag.title(), Some("TEST TITLE"));
+    let pos = artist_pos(&data) + 8;
+    let expected = format!("Truncated odd length utf-16 data of '©ART' at offset {}", pos);
+    assert_eq!(stats.warnings, [expected]);
+
+    let content = &content[..content.len() - 1];
+    let data = sample_with_raw_artist(atom(b"\xa9ART", &atom(b"data", content)));
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(tag.artist(), Some("AR"));
+    assert_eq!(tag.title(), Some("TEST TITLE"));
+}
+
+#[test]
+fn read_lenient_missing_data_atom() {
+    // the content of the artist atom is replaced by the raw string
+    let data = sample_with_raw_artist(atom(b"\xa9ART", b"RAW ARTIST\0"));
+
+    assert!(Tag::read_from(&mut Cursor::new(&data)).is_err());
+
+    let cfg = ReadConfig { lenient: true, ..Default::default() };
+    let tag = Tag::read_with_config(&mut Cursor::new(&data), &cfg).unwrap();
+    assert_eq!(tag.artist(), Some("RAW ARTIST"));
+    assert_eq!(tag.title(), Some("TEST TITLE"));
+    assert_eq!(tag.album(), Some("TEST ALBUM"));
+}
+
+#[test]
+fn read_lenient_ilst_length() {
+    let sample = fs::read("files/sample.m4a").unwrap();
+    let ilst_pos = sample.windows(4).position(|w| w == b"ilst").unwrap() - 4;
+    let cfg = ReadConfig { lenient: true, ..Default::default() };
+
+    for diff in [-4i64, 4].iter() {
+        let mut data = sample.clone();
+        let len = (be_u32_at(&data, ilst_pos) as i64 + diff) as u32;
+        data[ilst_pos..ilst_pos + 4].copy_from_slice(&len.to_be_bytes());
+
+        assert!(Tag::read_from(&mut Cursor::new(&data)).is_err());
+
+        let (tag, stats) = Tag::read_with_config_and_stats(&mut Cursor::new(&data), &cfg).unwrap();
+        assert_tag_1(&tag);
+        assert_eq!(tag.tv_show_name(), Some("TEST TV SHOW NAME"));
+        assert_eq!(stats.warnings.len(), 1);
+    }
+}
+
+#[test]
+fn encode_freeform_with_multiple_data_atoms() {
+    let ident = DataIdent::freeform("com.example", "LIST");
+    let mut tag = Tag::default();
+    tag.add_all_data(ident.clone(), vec![Data::Utf8("one".into()), Data::Utf8("two".into())]);
+
+    let udta = tag.encode_to_vec().unwrap();
+    let pos = udta.windows(4).position(|w| w == b"----").unwrap() - 4;
+    let len = be_u32_at(&udta, pos) as usize;
+    // head + mean + name + 2 data atoms
+    assert_eq!(len, 8 + (12 + 11) + (12 + 4) + 2 * (16 + 3));
+    assert_eq!(pos + len, udta.len());
+
+    let children = atoms_in(&udta, pos + 8, pos + len);
+    let children = children.iter().map(|(f, ..)| f).collect::<Vec<_>>();
+    assert_eq!(children, [b"mean", b"name", b"data", b"data"]);
+
+    let path = "target/encode_freeform_with_multiple_data_atoms.m4a";
+    fs::copy("files/sample.m4a", path).unwrap();
+    tag.write_to_path(path).unwrap();
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.strings_of(&ident).collect::<Vec<_>>(), ["one", "two"]);
+}
+
+#[test]
+fn read_file_level_meta() {
+    let item = |fourcc: &[u8; 4], value: &str| {
+        let mut data = vec![0, 0, 0, 1, 0, 0, 0, 0];
+        data.extend_from_slice(value.as_bytes());
+        atom(fourcc, &atom(b"data", &data))
+    };
+    let mut ilst = item(b"\xa9nam", "file level title");
+    ilst.extend(item(b"test", "file level value"));
+    let mut meta = vec![0; 4];
+    meta.extend(hdlr(b"mdir"));
+    meta.extend(atom(b"ilst", &ilst));
+
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    data.extend(atom(b"meta", &meta));
+
+    let tag = Tag::read_from(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(tag.title(), Some("TEST TITLE"));
+    assert_eq!(tag.strings_of(&Fourcc(*b"test")).collect::<Vec<_>>(), ["file level value"]);
+    assert_tag_1(&tag);
+
+    let path = "target/read_file_level_meta.m4a";
+    fs::write(path, &data).unwrap();
+    tag.write_to_path(path).unwrap();
+    let mut tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.strings_of(&Fourcc(*b"test")).collect::<Vec<_>>(), ["file level value"]);
+    assert_tag_1(&tag);
+
+    tag.remove_data_of(&Fourcc(*b"test"));
+    tag.write_to_path(path).unwrap();
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.strings_of(&Fourcc(*b"test")).next(), None);
     assert_tag_1(&tag);
-    assert_readonly(&tag);
 }
 
 #[test]
-fn write_bigger() {
-    let mut tag = get_tag_2();
-    let data: Vec<u8> = (0..2048).map(|n| (n % 255) as u8).collect();
-    tag.add_data(Fourcc(*b"test"), Data::Reserved(data));
+fn read_track_filter() {
+    // a copy of the sample with a video track preceding the audio track
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let children = moov_children(&data);
+    let (_, trak_pos, trak_len) = *children.iter().find(|(f, ..)| f == b"trak").unwrap();
+    let mut video = data[trak_pos..trak_pos + trak_len].to_vec();
+    let hdlr = video.windows(4).position(|w| w == b"soun").unwrap();
+    video[hdlr..hdlr + 4].copy_from_slice(b"vide");
+    let mp4a = video.windows(4).position(|w| w == b"mp4a").unwrap();
+    video[mp4a..mp4a + 4].copy_from_slice(b"avc1");
+    splice_atoms(&mut data, trak_pos..trak_pos, &video, &[b"moov"]);
+
+    let read = |cfg: ReadConfig| Tag::read_with_config(&mut Cursor::new(&data), &cfg).unwrap();
+
+    let tag = read(ReadConfig::default());
+    assert_eq!(tag.sample_rate(), Some(SampleRate::Hz44100));
+    assert_tag_1(&tag);
 
-    let _ = std::fs::remove_file("target/write_bigger.m4a");
-    println!("copying files/sample.m4a to target/write_bigger.m4a...");
-    std::fs::copy("files/sample.m4a", "target/write_bigger.m4a").unwrap();
+    let tag = read(ReadConfig { max_tracks: Some(1), ..Default::default() });
+    assert_eq!(tag.sample_rate(), None);
+    assert_tag_1(&tag);
 
-    println!("writing...");
-    tag.write_to_path("target/write_bigger.m4a").unwrap();
+    let soun = Fourcc(*b"soun");
+    assert_readonly(&read(ReadConfig { handler_filter: vec![soun], ..Default::default() }));
 
-    println!("reading...");
-    let tag = Tag::read_from_path("target/write_bigger.m4a").unwrap();
-    assert_tag_2(&tag);
-    assert_readonly(&tag);
+    let cfg = ReadConfig { max_tracks: Some(1), handler_filter: vec![soun], ..Default::default() };
+    assert_readonly(&read(cfg));
+
+    let cfg = ReadConfig { handler_filter: vec![Fourcc(*b"vide")], ..Default::default() };
+    let tag = read(cfg);
+    assert_eq!(tag.sample_rate(), None);
+    assert_tag_1(&tag);
 }
 
 #[test]
-fn write_empty() {
-    let tag = Tag::default();
+fn read_zero_length_moov() {
+    let mut data = fs::read("files/sample.m4a").unwrap();
+    let (moov_pos, moov_len) = top_level_bounds(&data, b"moov");
+    data[moov_pos..moov_pos + 4].copy_from_slice(&u32::to_be_bytes(0));
+
+    for len in [8, moov_len / 2].iter() {
+        let data = &data[..moov_pos + len];
+        let tag = Tag::read_from(&mut Cursor::new(data)).unwrap();
+        assert_eq!(tag.filetype(), "M4A \u{0}\u{0}\u{2}\u{0}isomiso2");
+        assert_eq!(tag.data().count(), 0);
+        assert_eq!(tag.duration(), None);
+    }
+}
 
-    let _ = std::fs::remove_file("target/write_empty.m4a");
-    println!("copying files/sample.m4a to target/write_empty.m4a...");
-    std::fs::copy("files/sample.m4a", "target/write_empty.m4a").unwrap();
+struct ReversedString;
 
-    println!("writing...");
-    tag.write_to_path("target/write_empty.m4a").unwrap();
+impl DataCodec for ReversedString {
+    fn decode(&self, _datatype: u32, bytes: Vec<u8>) -> mp4ameta::Result<Data> {
+        Ok(Data::Utf8(String::from_utf8(bytes)?.chars().rev().collect()))
+    }
 
-    println!("reading...");
-    let tag = Tag::read_from_path("target/write_empty.m4a").unwrap();
-    assert!(tag.is_empty());
-    assert_readonly(&tag);
+    fn encode(&self, data: &Data) -> mp4ameta::Result<Data> {
+        Ok(Data::Utf8(data.string().unwrap_or_default().chars().rev().collect()))
+    }
+}
+
+#[test]
+fn type_registry() {
+    let mut types = TypeRegistry::new();
+    types.register(ident::TITLE, ReversedString);
+
+    let read_cfg = ReadConfig { types: types.clone(), ..Default::default() };
+    let tag = Tag::read_from_path_with_config("files/sample.m4a", &read_cfg).unwrap();
+    assert_eq!(tag.title(), Some("ELTIT TSET"));
+    assert_eq!(tag.artist(), Some("TEST ARTIST"));
+
+    let path = "target/type_registry.m4a";
+    fs::copy("files/sample.m4a", path).unwrap();
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.set_title("title");
+    let write_cfg = WriteConfig { types, ..Default::default() };
+    tag.write_to_path_with_config(path, &write_cfg).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.title(), Some("eltit"));
+    let tag = Tag::read_from_path_with_config(path, &read_cfg).unwrap();
+    assert_eq!(tag.title(), Some("title"));
 }
 
 #[test]
@@ -381,6 +2552,144 @@ fn dump_2() {
     assert_tag_2(&tag);
 }
 
+#[test]
+fn metadata_overhead() {
+    let mut tag = get_tag_1();
+    tag.set_xmp(b"<x:xmpmeta/>".to_vec());
+    tag.set_id3v2_box(id3v2_with_title("ID3 TITLE"));
+
+    // a dump only contains the metadata, inside an ftyp and moov atom
+    let mut data = Vec::new();
+    tag.dump_to(&mut data).unwrap();
+    let (_, ftyp_len) = top_level_bounds(&data, b"ftyp");
+    let (_, moov_len) = top_level_bounds(&data, b"moov");
+    let (_, uuid_len) = top_level_bounds(&data, b"uuid");
+    let (_, meta_len) = top_level_bounds(&data, b"meta");
+    assert_eq!(moov_len as u64 - 8, tag.metadata_size());
+    assert_eq!((moov_len - 8 + uuid_len + meta_len) as u64, tag.metadata_overhead());
+    assert_eq!(data.len(), ftyp_len + moov_len + uuid_len + meta_len);
+}
+
+#[test]
+fn retain_freeform() {
+    let mut tag = Tag::read_from_path("files/sample.m4a").unwrap();
+    assert_eq!(tag.isrc(), Some("TEST ISRC"));
+    assert_eq!(tag.lyricist(), Some("TEST LYRICIST"));
+    let other = FreeformIdent::new("com.example", "test");
+    tag.set_data(other.clone(), Data::Utf8("test".to_owned()));
+
+    tag.retain(|ident, _| match ident {
+        DataIdent::Freeform { mean, .. } => mean != "com.apple.iTunes",
+        _ => true,
+    });
+
+    assert_eq!(tag.isrc(), None);
+    assert_eq!(tag.lyricists().next(), None);
+    assert_eq!(tag.strings_of(&other).next(), Some("test"));
+    assert!(tag.data().all(|(i, _)| !matches!(i, DataIdent::Freeform { mean, .. }
+        if mean == "com.apple.iTunes")));
+    assert_eq!(tag.title(), Some("TEST TITLE"));
+    assert_eq!(tag.artist(), Some("TEST ARTIST"));
+}
+
+#[test]
+fn freeform_comment() {
+    let path = "target/freeform_comment.m4a";
+    fs::copy("files/sample.m4a", path).unwrap();
+    let freeform = FreeformIdent::new("com.apple.iTunes", "comment");
+
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.remove_comments();
+    tag.set_data(freeform.clone(), Data::Utf8("FREEFORM COMMENT".to_owned()));
+    tag.write_to_path(path).unwrap();
+
+    let mut tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.comment(), None);
+    assert_eq!(tag.any_comment(), Some("FREEFORM COMMENT"));
+
+    // setting the comment only writes the standard comment atom
+    tag.set_comment("TEST COMMENT");
+    tag.write_to_path(path).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.comments().collect::<Vec<_>>(), ["TEST COMMENT"]);
+    assert_eq!(tag.strings_of(&freeform).collect::<Vec<_>>(), ["FREEFORM COMMENT"]);
+    assert_eq!(tag.any_comment(), Some("TEST COMMENT"));
+    assert_eq!(tag.all_comments().collect::<Vec<_>>(), ["TEST COMMENT", "FREEFORM COMMENT"]);
+    assert_tag_1(&tag);
+}
+
+#[test]
+fn strip_private() {
+    let path = "target/strip_private.m4a";
+    fs::copy("files/sample.m4a", path).unwrap();
+
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.set_data(Fourcc(*b"apID"), Data::Utf8("user@example.com".to_owned()));
+    tag.set_data(Fourcc(*b"ownr"), Data::Utf8("owner".to_owned()));
+    tag.set_data(Fourcc(*b"sfID"), Data::BeSigned(vec![0, 0, 0, 143]));
+    tag.set_location("+48.8577+002.295/".parse().unwrap());
+    tag.set_xmp(b"<x:xmpmeta/>".to_vec());
+    tag.write_to_path(path).unwrap();
+
+    let mut tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.strings_of(&Fourcc(*b"apID")).next(), Some("user@example.com"));
+    assert_eq!(tag.strip_private(), 5);
+    assert_eq!(tag.xmp(), None);
+    tag.write_to_path(path).unwrap();
+
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.title(), Some("TEST TITLE"));
+    assert_eq!(tag.artist(), Some("TEST ARTIST"));
+    assert_eq!(tag.encoder(), None);
+    assert_eq!(tag.location(), None);
+    assert_eq!(tag.xmp(), None);
+    for fourcc in [b"apID", b"ownr", b"sfID"] {
+        assert_eq!(tag.data_of(&Fourcc(*fourcc)).next(), None);
+    }
+
+    let mut tag = get_tag_1();
+    let removed = tag.data().count() - 2;
+    assert_eq!(tag.strip_all_but(&[ident::TITLE, ident::ALBUM]), removed);
+    let idents: Vec<String> = tag.data().map(|(i, _)| i.to_string()).collect();
+    assert_eq!(idents, ["©alb", "©nam"]);
+}
+
+#[test]
+fn strip_private_quicktime_location() {
+    let path = "target/strip_private_quicktime_location.m4a";
+    let location = DataIdent::key("com.apple.quicktime.location.ISO6709");
+    let make = DataIdent::key("com.apple.quicktime.make");
+
+    // an iPhone recording with a location in the QuickTime metadata, and one in the user data
+    let items = [
+        ("com.apple.quicktime.location.ISO6709", "+37.3349-122.0090+030.000/"),
+        ("com.apple.quicktime.make", "Apple"),
+    ];
+    let udta_location = atom(b"\xa9xyz", b"\x00\x12\x15\xc7+12.3456-098.7654/");
+    let samples =
+        [sample_with_quicktime_metadata(&items), sample_with_udta_children(&udta_location)];
+    for data in samples.iter() {
+        fs::write(path, data).unwrap();
+        let mut tag = Tag::read_from_path(path).unwrap();
+        tag.strip_private();
+        tag.write_to_path(path).unwrap();
+
+        let written = fs::read(path).unwrap();
+        assert!(!written.windows(8).any(|w| w == b"+37.3349" || w == b"+12.3456"));
+        let tag = Tag::read_from_path(path).unwrap();
+        assert_eq!(tag.data_of(&location).next(), None);
+        assert_eq!(tag.title(), Some("TEST TITLE"));
+    }
+
+    fs::write(path, sample_with_quicktime_metadata(&items)).unwrap();
+    let mut tag = Tag::read_from_path(path).unwrap();
+    tag.strip_private();
+    tag.write_to_path(path).unwrap();
+    let tag = Tag::read_from_path(path).unwrap();
+    assert_eq!(tag.strings_of(&make).collect::<Vec<_>>(), ["Apple"]);
+}
+
 #[test]
 fn multiple_values() {
     let mut tag = Tag::default();
@@ -520,6 +2829,8 @@ fn track_disc_handling() {
     assert_eq!(tag.disc(), (None, Some(total_discs)));
     assert_eq!(tag.disc_number(), None);
     assert_eq!(tag.total_discs(), Some(total_discs));
+    assert_eq!(tag.track_string(), Some("0/16".to_owned()));
+    assert_eq!(tag.disc_string(), Some("0/3".to_owned()));
 
     tag.remove_total_tracks();
     tag.remove_total_discs();
@@ -530,6 +2841,47 @@ fn track_disc_handling() {
     assert_eq!(tag.disc(), (None, None));
     assert_eq!(tag.disc_number(), None);
     assert_eq!(tag.total_discs(), None);
+    assert_eq!(tag.track_string(), None);
+    assert_eq!(tag.disc_string(), None);
+
+    tag.set_disc_string(" 2 / 3 ").unwrap();
+    assert_eq!(tag.disc(), (Some(disc_number), Some(total_discs)));
+    assert_eq!(tag.disc_string(), Some("2/3".to_owned()));
+    for invalid in ["", "/3", "2/", "2/3/4", "-2", "two"].iter() {
+        let err = tag.set_disc_string(invalid).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::Parsing));
+    }
+    assert_eq!(tag.disc(), (Some(disc_number), Some(total_discs)));
+}
+
+#[test]
+fn flag_handling() {
+    type Getter = fn(&Tag) -> bool;
+    let flags: [(Fourcc, Getter); 4] = [
+        (ident::COMPILATION, Tag::compilation),
+        (ident::GAPLESS_PLAYBACK, Tag::gapless_playback),
+        (ident::PODCAST, Tag::podcast),
+        (ident::SHOW_MOVEMENT, Tag::show_movement),
+    ];
+    let encodings: [(Vec<u8>, bool); 4] =
+        [(vec![], false), (vec![0], false), (vec![1], true), (vec![0xFF], true)];
+
+    let path = "target/flag_handling.m4a";
+    fs::copy("files/sample.m4a", path).unwrap();
+    for (ident, flag) in flags.iter() {
+        assert!(!flag(&Tag::default()));
+
+        for (bytes, expected) in encodings.iter() {
+            let mut tag = Tag::default();
+            tag.set_data(*ident, Data::BeSigned(bytes.clone()));
+            assert_eq!(flag(&tag), *expected);
+            assert_eq!(tag.flag_of(ident), *expected);
+
+            tag.write_to_path(path).unwrap();
+            let tag = Tag::read_from_path(path).unwrap();
+            assert_eq!(flag(&tag), *expected);
+        }
+    }
 }
 
 #[test]
@@ -578,7 +2930,7 @@ fn tag_destructuring() {
     tag.set_lyrics("TEST LYRICS");
     tag.set_title("TEST TITLE");
     tag.set_year("2013");
-    tag.set_artwork(Img::png(b"TEST ARTWORK".to_vec()));
+    tag.set_artwork_unchecked(Img::png(b"TEST ARTWORK".to_vec()));
 
     assert_eq!(tag.take_album(), Some("TEST ALBUM".to_string()));
     assert_eq!(tag.take_album_artist(), Some("TEST ALBUM ARTIST".to_string()));